@@ -49,6 +49,22 @@ impl Info {
     pub fn max_bytes_per_nchar(&self) -> i32 {
         self.max_bytes_per_nchar
     }
+
+    /// Encode the given string in the encoding used for CHAR data. UTF-8 strings are returned
+    /// unchanged. For the single byte encodings US-ASCII and ISO-8859-1 each character is mapped to
+    /// its byte value, and characters that cannot be represented are replaced with '?', as Oracle
+    /// does. Other encodings are not converted, and the UTF-8 bytes are returned unchanged.
+    pub fn encode(&self, s: &str) -> Vec<u8> {
+        let max = match &self.encoding.to_uppercase()[..] {
+            "US-ASCII" | "ASCII" => 0x7f,
+            "ISO-8859-1" | "LATIN1" => 0xff,
+            _ => return s.as_bytes().to_vec(),
+        };
+
+        s.chars()
+            .map(|c| if (c as u32) <= max { c as u32 as u8 } else { b'?' })
+            .collect()
+    }
 }
 
 impl From<ODPIEncodingInfo> for Info {
@@ -66,3 +82,24 @@ impl From<ODPIEncodingInfo> for Info {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::Info;
+
+    fn info(encoding: &str) -> Info {
+        Info {
+            encoding: encoding.to_string(),
+            nchar_encoding: encoding.to_string(),
+            max_bytes_per_char: 1,
+            max_bytes_per_nchar: 1,
+        }
+    }
+
+    #[test]
+    fn encode() {
+        assert_eq!(info("UTF-8").encode("żółw"), "żółw".as_bytes().to_vec());
+        assert_eq!(info("ISO-8859-1").encode("café ☃"), vec![b'c', b'a', b'f', 0xe9, b' ', b'?']);
+        assert_eq!(info("US-ASCII").encode("café"), b"caf?".to_vec());
+    }
+}
@@ -11,8 +11,51 @@
 use odpi::structs::ODPIVersionInfo;
 use std::fmt;
 
+/// Features that are only available with recent enough versions of the Oracle Client and Oracle
+/// Database.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Feature {
+    /// Implicit results returned from PL/SQL with `dbms_sql.return_result()`.
+    ImplicitResults,
+    /// Batch errors, collected by executing with the DPI_MODE_EXEC_BATCH_ERRORS mode.
+    BatchErrors,
+    /// Row counts for each iteration of `Statement::execute_many()`, collected by executing with
+    /// the DPI_MODE_EXEC_ARRAY_DML_ROWCOUNTS mode.
+    ArrayDmlRowCounts,
+    /// The native JSON data type.
+    JsonType,
+    /// Binding the PL/SQL BOOLEAN type, using DPI_ORACLE_TYPE_BOOLEAN.
+    PlsqlBoolean,
+}
+
+impl Feature {
+    /// Returns the (major, minor) version that both the client and the server must be at for the
+    /// feature to be available.
+    pub fn required_version(&self) -> (u32, u32) {
+        match *self {
+            Feature::ImplicitResults |
+            Feature::BatchErrors |
+            Feature::ArrayDmlRowCounts |
+            Feature::PlsqlBoolean => (12, 1),
+            Feature::JsonType => (21, 1),
+        }
+    }
+
+    /// Is the feature available with the given client and server versions? When the client
+    /// version is not known only the server version is checked.
+    pub fn is_supported(&self, client: Option<&Info>, server: &Info) -> bool {
+        let (major, minor) = self.required_version();
+        server.at_least(major, minor) && client.map_or(true, |c| c.at_least(major, minor))
+    }
+}
+
 /// Wrapper for the `ODPIVersionInfo` structure.
+#[derive(Clone, Debug)]
 pub struct Info {
+    /// The major version.
+    major: u32,
+    /// The minor (release) version.
+    minor: u32,
     /// The version.
     version: String,
     /// The version number.
@@ -22,6 +65,14 @@ pub struct Info {
 }
 
 impl Info {
+    /// Is this version at least the given major and minor (release) version?
+    ///
+    /// * `major` - the major version, i.e. 12 for 12.1.0.2.0.
+    /// * `minor` - the release version, i.e. 1 for 12.1.0.2.0.
+    pub fn at_least(&self, major: u32, minor: u32) -> bool {
+        (self.major, self.minor) >= (major, minor)
+    }
+
     /// Get the `version` value.
     pub fn version(&self) -> &str {
         &self.version
@@ -57,6 +108,8 @@ impl From<ODPIVersionInfo> for Info {
                               ovi.port_release_num,
                               ovi.port_update_num);
         Info {
+            major: ovi.version_num as u32,
+            minor: ovi.release_num as u32,
             version: version,
             version_num: ovi.full_version_num,
             release: None,
@@ -75,3 +128,47 @@ impl fmt::Display for Info {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{Feature, Info};
+    use odpi::structs::ODPIVersionInfo;
+
+    fn info(major: i32, minor: i32) -> Info {
+        ODPIVersionInfo {
+                version_num: major,
+                release_num: minor,
+                update_num: 0,
+                port_release_num: 0,
+                port_update_num: 0,
+                full_version_num: 0,
+            }
+            .into()
+    }
+
+    #[test]
+    fn at_least() {
+        let ver = info(12, 1);
+        assert!(ver.at_least(11, 2));
+        assert!(ver.at_least(12, 0));
+        assert!(ver.at_least(12, 1));
+        assert!(!ver.at_least(12, 2));
+        assert!(!ver.at_least(18, 0));
+        assert!(info(18, 0).at_least(12, 2));
+        assert!(!info(11, 2).at_least(12, 1));
+    }
+
+    #[test]
+    fn is_supported() {
+        let old = info(11, 2);
+        let current = info(12, 1);
+        let json = info(21, 3);
+
+        assert!(Feature::ImplicitResults.is_supported(Some(&current), &current));
+        assert!(Feature::BatchErrors.is_supported(None, &json));
+        assert!(!Feature::BatchErrors.is_supported(Some(&old), &current));
+        assert!(!Feature::ArrayDmlRowCounts.is_supported(Some(&current), &old));
+        assert!(!Feature::JsonType.is_supported(Some(&current), &json));
+        assert!(Feature::JsonType.is_supported(Some(&json), &json));
+    }
+}
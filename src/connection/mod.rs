@@ -12,30 +12,86 @@
 //! `close()` or releasing the last reference to the connection by calling the function `release()`.
 //! Connection handles are used to create all handles other than session pools and context handles.
 use common::{encoding, version};
-use context::Context;
+use common::version::Feature;
+use context::{Context, Handle};
+use data::{FromData, OwnedData, ToData};
 use context::params::{CommonCreate, ConnCreate, SubscrCreate};
 use dequeue;
 use enqueue;
-use error::{ErrorKind, Result};
+use error::{Error, ErrorKind, Result};
 use lob::Lob;
 use message::Properties;
 use object::Object;
 use objecttype::ObjectType;
-use odpi::{externs, flags};
+use odpi::{constants, externs, flags, oci};
 use odpi::opaque::ODPIConn;
 use odpi::structs::{ODPIEncodingInfo, ODPIVersionInfo};
+use row::FromRow;
 use slog::Logger;
-use statement::Statement;
+use statement::{RowIterator, Statement, StmtMetric};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::fmt;
+use std::os::raw::{c_int, c_void};
 use std::ptr;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 use subscription::Subscription;
-use util::ODPIStr;
+use util::{quote_ident, split_script, ODPIStr};
 use variable::Var;
 
-/// Connection handles are used to represent connections to the database.
+/// The Oracle error code raised when a SELECT INTO in PL/SQL finds no rows, ORA-01403.
+const ORA_NO_DATA_FOUND: i32 = 1403;
+
+/// The Oracle error code raised when a resource is busy, ORA-00054.
+const ORA_RESOURCE_BUSY: i32 = 54;
+
+/// Connection handles are used to represent connections to the database. A `Connection` owns the
+/// reference to the ODPI-C connection it was created with, and any added with `add_ref()`, and
+/// releases whatever references it still holds when it is dropped.
 #[allow(dead_code)]
 pub struct Connection {
     /// The ODPI-C connection.
     inner: *mut ODPIConn,
+    /// The ODPI-C context the connection was created with, if known, used to read error details.
+    /// Holding it keeps the ODPI-C context alive for as long as the connection.
+    context: Option<Arc<Handle>>,
+    /// The version of the Oracle Client, if known.
+    client_version: Option<version::Info>,
+    /// Results cached by `query_cached()`, keyed by the caller supplied key.
+    query_cache: RefCell<HashMap<String, (Instant, Vec<Vec<OwnedData>>)>>,
+    /// The number of times `query_cached()` has run its query against the database.
+    query_cache_loads: Cell<u64>,
+    /// The fetch array size given to every statement prepared on the connection, set by
+    /// `set_default_fetch_array_size()`. Zero leaves the ODPI-C default in place.
+    default_fetch_array_size: Cell<u32>,
+    /// The edition set by `set_edition()`, which takes precedence over the edition the connection
+    /// was created with.
+    edition: RefCell<Option<String>>,
+    /// The external name, cached by `get_external_name()` and invalidated by
+    /// `set_external_name()`.
+    external_name: RefCell<Option<String>>,
+    /// The internal name, cached by `get_internal_name()` and invalidated by
+    /// `set_internal_name()`.
+    internal_name: RefCell<Option<String>>,
+    /// The version of the Oracle Database, cached by `get_server_version()`.
+    server_version: RefCell<Option<version::Info>>,
+    /// The background thread started by `enable_keepalive()`, stopped when the connection is
+    /// dropped.
+    keepalive: RefCell<Option<Keepalive>>,
+    /// Whether an open transaction is rolled back when the connection is dropped.
+    rollback_on_drop: Cell<bool>,
+    /// The sink given to every statement prepared on the connection, set by `set_metrics_sink()`.
+    metrics_sink: RefCell<Option<Arc<Box<Fn(StmtMetric) + Send + Sync>>>>,
+    /// The number of references to `inner` held by this handle: the one it was created with plus
+    /// those added by `add_ref()`, less those given up by `release()`.
+    refs: Cell<u32>,
+    /// Set once `close()` has been called, after which the session is no longer used when the
+    /// connection is dropped.
+    closed: Cell<bool>,
     /// Optional stdout logger.
     stdout: Option<Logger>,
     /// Optoinal stderr logger.
@@ -47,6 +103,14 @@ impl Connection {
     /// connection needs to be maintained independently of the reference returned when the
     /// connection was created.
     pub fn add_ref(&self) -> Result<()> {
+        self.add_handle_ref()?;
+        self.refs.set(self.refs.get() + 1);
+        Ok(())
+    }
+
+    /// Add a reference to the connection that is owned by something other than this handle, such
+    /// as a `CancelToken`, and so is not released when the connection is dropped.
+    fn add_handle_ref(&self) -> Result<()> {
         try_dpi!(externs::dpiConn_addRef(self.inner),
                  Ok(()),
                  ErrorKind::Connection("dpiConn_addRef".to_string()))
@@ -81,6 +145,14 @@ impl Connection {
         }
     }
 
+    /// Begins a read only transaction by executing `SET TRANSACTION READ ONLY`. All queries in the
+    /// transaction see the database as it was when the transaction began, and any insert, update
+    /// or delete fails with ORA-01456. The transaction ends with the next commit or rollback; see
+    /// `read_only()` for a guard that commits automatically.
+    pub fn begin_read_only(&self) -> Result<()> {
+        self.execute_sql("set transaction read only")
+    }
+
     /// Performs an immediate (asynchronous) termination of any currently executing function on the
     /// server associated with the connection.
     pub fn break_execution(&self) -> Result<()> {
@@ -89,6 +161,30 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_breakExecution".to_string()))
     }
 
+    /// Returns the given string encoded in the CHAR encoding of the connection, which is the
+    /// encoding given to `CommonCreate::set_encoding()` when the connection was created. All
+    /// strings passed to ODPI-C are expected in this encoding, and the database converts them to
+    /// its own character set. Only UTF-8, US-ASCII and ISO-8859-1 are converted; for any other
+    /// client encoding the UTF-8 bytes are returned unchanged, so connecting with UTF-8 is
+    /// recommended whenever the database character set is not UTF-8.
+    ///
+    /// * `s` - the string to encode.
+    pub fn bytes_for_charset(&self, s: &str) -> Vec<u8> {
+        match self.get_encoding_info() {
+            Ok(info) => info.encode(s),
+            Err(_) => s.as_bytes().to_vec(),
+        }
+    }
+
+    /// Returns a token that can be used from other threads to interrupt the operation currently
+    /// running on the connection, as with `break_execution()`. The token holds its own reference
+    /// to the connection, so it remains safe to use after the connection has been released, and
+    /// may be cloned and shared freely.
+    pub fn cancel_token(&self) -> Result<CancelToken> {
+        self.add_handle_ref()?;
+        Ok(CancelToken { conn: Arc::new(Mutex::new(CancelConn(self.inner))) })
+    }
+
     /// Changes the password of the specified user.
     ///
     /// * `username` - the name of the user whose password is to be changed, as a byte string in the
@@ -117,6 +213,83 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_changePassword".to_string()))
     }
 
+    /// Changes the password of the specified user, as with `change_password()`, then creates a new
+    /// standalone connection using the new password and releases this connection. Existing
+    /// sessions keep working after a password change, so this is only needed when the caller
+    /// wants to verify the new credentials straight away. If either step fails this connection is
+    /// handed back alongside the error, still open and usable, so the caller is never left without
+    /// a session.
+    ///
+    /// * `context` - the context in which the new connection is created.
+    /// * `connect_string` - the connect string identifying the database to which the new
+    /// connection is to be established.
+    /// * `username` - the name of the user whose password is to be changed.
+    /// * `old_password` - the old password of the user.
+    /// * `new_password` - the new password of the user, used to create the new connection.
+    /// * `common_create_params` - the common parameters used to create the new connection, as with
+    /// `create()`, so that e.g. the encoding of the original connection can be kept. None uses the
+    /// defaults of the context.
+    /// * `conn_create_params` - the connection specific parameters used to create the new
+    /// connection, as with `create()`. None uses the defaults of the context.
+    pub fn change_password_and_reconnect(self,
+                                         context: &Context,
+                                         connect_string: &str,
+                                         username: &str,
+                                         old_password: &str,
+                                         new_password: &str,
+                                         common_create_params: Option<CommonCreate>,
+                                         conn_create_params: Option<ConnCreate>)
+                                         -> ::std::result::Result<Connection, (Connection, Error)> {
+        if let Err(e) = self.change_password(username, old_password, new_password) {
+            return Err((self, e));
+        }
+        let conn = match Connection::create(context,
+                                            Some(username),
+                                            Some(new_password),
+                                            Some(connect_string),
+                                            common_create_params,
+                                            conn_create_params) {
+            Ok(conn) => conn,
+            Err(e) => return Err((self, e)),
+        };
+        if let Err(e) = self.release() {
+            try_warn!(self.stderr, "Failed to release the old connection: {}", e);
+        }
+        Ok(conn)
+    }
+
+    /// Replace the given error with a more specific one based on the Oracle error code of the last
+    /// ODPI-C error. This must be called before any other ODPI-C call is made on this thread. The
+    /// error is returned unchanged if the context of the connection is not known.
+    fn classify_error(&self, err: Error) -> Error {
+        let info = match self.context {
+            Some(ref context) => context.get_error(),
+            None => return err,
+        };
+
+        match info.code() {
+            ORA_NO_DATA_FOUND => ErrorKind::PlsqlNoDataFound(info).into(),
+            ORA_RESOURCE_BUSY => ErrorKind::ResourceBusy(info).into(),
+            _ => err,
+        }
+    }
+
+    /// Fill the given collection with the values and bind it to a new object variable of the
+    /// collection type. The collection and its type are left to the caller to release, whether or
+    /// not this succeeds.
+    fn collection_var(&self,
+                      coll_type: &ObjectType,
+                      coll: &Object,
+                      values: &[OwnedData])
+                      -> Result<Var> {
+        for value in values {
+            coll.append_element(value)?;
+        }
+        let var = self.new_object_var(coll_type, 1, false)?;
+        var.set_from_object(0, coll)?;
+        Ok(var)
+    }
+
     /// Closes the connection and makes it unusable for further activity.
     ///
     /// * `mode` - one or more of the values from the enumeration `ODPIConnCloseMode`, OR'ed
@@ -128,6 +301,7 @@ impl Connection {
     pub fn close(&self, mode: flags::ODPIConnCloseMode, tag: Option<&str>) -> Result<()> {
         let tag_s = ODPIStr::from(tag);
 
+        self.closed.set(true);
         try_dpi!(externs::dpiConn_close(self.inner, mode, tag_s.ptr(), tag_s.len()),
                  Ok(()),
                  ErrorKind::Connection("dpiConn_close".to_string()))
@@ -163,6 +337,10 @@ impl Connection {
     /// * `conn_create_params` - An optional `ConnCreate` structure which is used to specify
     /// parameters for connection creation. None is also acceptable in which case all default
     /// parameters will be used when creating the connection.
+    ///
+    /// Creating a connection waits for as long as the network allows when the listener cannot be
+    /// reached. To bound the wait, build the connect string with `ConnectDescriptor` and set its
+    /// `connect_timeout()` and `transport_connect_timeout()`.
     pub fn create(context: &Context,
                   username: Option<&str>,
                   password: Option<&str>,
@@ -197,7 +375,12 @@ impl Connection {
                                          &comm_cp.inner(),
                                          &mut conn_cp.inner(),
                                          &mut inner),
-                 Ok(inner.into()),
+                 {
+                     let mut conn: Connection = inner.into();
+                     conn.context = Some(context.handle());
+                     conn.client_version = context.get_client_version().ok();
+                     Ok(conn)
+                 },
                  ErrorKind::Connection("dpiConn_create".to_string()))
     }
 
@@ -231,6 +414,38 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_deqObject".to_string()))
     }
 
+    /// Starts a background thread that pings the database every `interval`, so that idle
+    /// connections are not dropped by firewalls or the server. The thread holds its own reference
+    /// to the connection and is stopped when the connection is dropped or when this function is
+    /// called again. Errors from the pings are ignored; the next use of the connection will report
+    /// them. The connection must have been created in threaded mode, which ODPI-C always uses, as
+    /// the pings are made concurrently with any other use of the connection.
+    ///
+    /// * `interval` - the time to wait between pings.
+    pub fn enable_keepalive(&self, interval: Duration) -> Result<()> {
+        self.add_handle_ref()?;
+        let conn = KeepaliveConn(self.inner);
+        let (stop, rx) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            let conn = conn;
+            while let Err(RecvTimeoutError::Timeout) = rx.recv_timeout(interval) {
+                unsafe {
+                    externs::dpiConn_ping(conn.0);
+                }
+            }
+            unsafe {
+                externs::dpiConn_release(conn.0);
+            }
+        });
+
+        *self.keepalive.borrow_mut() = Some(Keepalive {
+                                                stop: Some(stop),
+                                                handle: Some(handle),
+                                            });
+        Ok(())
+    }
+
     /// Enqueues a message to a queue.
     ///
     /// * `queue_name` - the name of the queue to which the message is to be enqueued, as a byte
@@ -261,6 +476,32 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_enqObject".to_string()))
     }
 
+    /// Executes a DDL statement, retrying it when the objects it needs to lock are in use by other
+    /// sessions (ORA-00054). This is useful for `alter table` and similar statements on a live
+    /// system. The wait between attempts starts at `backoff` and doubles after each attempt. If
+    /// the statement still fails after all the retries an `ErrorKind::ResourceBusy` error is
+    /// returned. Any other error is returned immediately.
+    ///
+    /// The error code is read from the context the connection was created with, which is known
+    /// for connections from `create()`, `ConnectionBuilder` and pools. A connection wrapped
+    /// directly from an ODPI-C pointer has no context, so its errors cannot be classified and the
+    /// statement is never retried.
+    ///
+    /// * `sql` - the DDL statement to execute.
+    /// * `retries` - the maximum number of times to retry the statement.
+    /// * `backoff` - the time to wait before the first retry.
+    pub fn execute_ddl_retry(&self, sql: &str, retries: u32, backoff: Duration) -> Result<()> {
+        retry_resource_busy(retries, backoff, || self.execute_sql(sql))
+    }
+
+    /// Prepare and execute a statement that returns no rows, closing it afterwards.
+    fn execute_sql(&self, sql: &str) -> Result<()> {
+        let stmt = self.prepare_stmt(Some(sql), None, false)?;
+        let res = stmt.execute(flags::EXEC_DEFAULT).map_err(|e| self.classify_error(e));
+        stmt.close(None)?;
+        res.map(|_| ())
+    }
+
     /// Get the current schema.
     pub fn get_current_schema(&self) -> Result<String> {
         let mut pdst = ptr::null();
@@ -271,13 +512,21 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_getCurrentSchema".to_string()))
     }
 
-    /// Returns the edition that is being used by the connection.
-    pub fn get_edition(&self) -> Result<String> {
+    /// Returns the edition that is being used by the connection, or `None` if no edition was set
+    /// when the connection was created or with `set_edition()`.
+    pub fn get_edition(&self) -> Result<Option<String>> {
+        if let Some(ref edition) = *self.edition.borrow() {
+            return Ok(Some(edition.clone()));
+        }
+
         let mut pdst = ptr::null();
         let mut dstlen = 0;
 
         try_dpi!(externs::dpiConn_getEdition(self.inner, &mut pdst, &mut dstlen),
-                 Ok(ODPIStr::new(pdst, dstlen).into()),
+                 {
+                     let edition: String = ODPIStr::new(pdst, dstlen).into();
+                     Ok(if edition.is_empty() { None } else { Some(edition) })
+                 },
                  ErrorKind::Connection("dpiConn_getEdition".to_string()))
     }
 
@@ -293,33 +542,50 @@ impl Connection {
     }
 
     /// Returns the external name that is being used by the connection. This value is used when
-    /// logging distributed transactions.
+    /// logging distributed transactions. The name is cached after the first call, until it is
+    /// changed with `set_external_name()`.
     pub fn get_external_name(&self) -> Result<String> {
+        if let Some(ref name) = *self.external_name.borrow() {
+            return Ok(name.clone());
+        }
         let mut pdst = ptr::null();
         let mut dstlen = 0;
 
         try_dpi!(externs::dpiConn_getExternalName(self.inner, &mut pdst, &mut dstlen),
-                 Ok(ODPIStr::new(pdst, dstlen).into()),
-                 ErrorKind::Connection("dpiConn_getEdition".to_string()))
+                 {
+                     let name: String = ODPIStr::new(pdst, dstlen).into();
+                     *self.external_name.borrow_mut() = Some(name.clone());
+                     Ok(name)
+                 },
+                 ErrorKind::Connection("dpiConn_getExternalName".to_string()))
     }
 
-    /// Returns the OCI service context handle in use by the connection.
-    // TODO: What is this a handle for?  Connection probably? Don't expose c_void.
-    // pub fn get_handle(&self) -> Result<*mut ::std::os::raw::c_void> {
-    //     let mut pdst = ptr::null_mut();
+    /// Returns the OCI service context handle in use by the connection, for use with OCI functions
+    /// that are not wrapped by ODPI-C. The handle is owned by the connection and must not be freed.
+    pub fn get_handle(&self) -> Result<ServiceContextHandle> {
+        let mut pdst = ptr::null_mut();
+
+        try_dpi!(externs::dpiConn_getHandle(self.inner, &mut pdst),
+                 Ok(ServiceContextHandle(pdst)),
+                 ErrorKind::Connection("dpiConn_getHandle".to_string()))
+    }
 
-    //     try_dpi!(externs::dpiConn_getHandle(self.inner, &mut pdst),
-    //              Ok(pdst),
-    //              ErrorKind::Connection("dpiConn_getHandle".to_string()))
-    // }
     /// Returns the internal name that is being used by the connection. This value is used when
-    /// logging distributed transactions.
+    /// logging distributed transactions. The name is cached after the first call, until it is
+    /// changed with `set_internal_name()`.
     pub fn get_internal_name(&self) -> Result<String> {
+        if let Some(ref name) = *self.internal_name.borrow() {
+            return Ok(name.clone());
+        }
         let mut pdst = ptr::null();
         let mut dstlen = 0;
 
         try_dpi!(externs::dpiConn_getInternalName(self.inner, &mut pdst, &mut dstlen),
-                 Ok(ODPIStr::new(pdst, dstlen).into()),
+                 {
+                     let name: String = ODPIStr::new(pdst, dstlen).into();
+                     *self.internal_name.borrow_mut() = Some(name.clone());
+                     Ok(name)
+                 },
                  ErrorKind::Connection("dpiConn_getInternalName".to_string()))
     }
 
@@ -350,8 +616,12 @@ impl Connection {
     }
 
     /// Returns the version information of the Oracle Database to which the connection has been
-    /// made.
+    /// made. The version is cached after the first successful call.
     pub fn get_server_version(&self) -> Result<version::Info> {
+        if let Some(ref ver_info) = *self.server_version.borrow() {
+            return Ok(ver_info.clone());
+        }
+
         let mut pdst = ptr::null();
         let mut dstlen = 0;
         let mut version_info: ODPIVersionInfo = Default::default();
@@ -364,6 +634,7 @@ impl Connection {
                      let mut ver_info: version::Info = version_info.into();
                      let release_s = ODPIStr::new(pdst, dstlen);
                      ver_info.set_release(Some(release_s.into()));
+                     *self.server_version.borrow_mut() = Some(ver_info.clone());
                      Ok(ver_info)
                  },
                  ErrorKind::Connection("dpiConn_getServerVersion".to_string()))
@@ -378,6 +649,102 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_getStmtCacheSize".to_string()))
     }
 
+    /// Checks that the connection is usable, for readiness probes. The database is pinged and the
+    /// round trip timed, and the server version is looked up. Errors are not returned; instead the
+    /// report is marked as unhealthy.
+    ///
+    /// ODPI-C 2.0 has no `dpiConn_getIsHealthy()`, so there is no cheap local check to try first:
+    /// every call costs a round trip to the database for the ping, and another for the server
+    /// version the first time it is looked up. Probes should be spaced accordingly.
+    pub fn health_check(&self) -> HealthReport {
+        let start = Instant::now();
+        let ping = self.ping();
+        let latency = start.elapsed();
+        let server_version = self.get_server_version();
+
+        HealthReport {
+            healthy: ping.is_ok() && server_version.is_ok(),
+            latency: latency,
+            server_version: server_version.map(|v| v.version().to_string()).unwrap_or_default(),
+        }
+    }
+
+    /// Removes the results cached by `query_cached()` under the given key, so that the next call
+    /// with that key runs its query again.
+    ///
+    /// * `key` - the key the results were cached under.
+    pub fn invalidate_cached(&self, key: &str) {
+        self.query_cache.borrow_mut().remove(key);
+    }
+
+    /// Executes the given non-query statement, such as an INSERT, UPDATE, DELETE or MERGE, and
+    /// returns the number of rows it affected. This is the write counterpart to `query()`. Queries
+    /// are rejected with `ErrorKind::UnexpectedQuery` before they are executed. A PL/SQL block that
+    /// raises NO_DATA_FOUND (ORA-01403) fails with `ErrorKind::PlsqlNoDataFound`.
+    ///
+    /// * `sql` - the statement to execute, as a string in the encoding used for CHAR data.
+    /// * `binds` - the values bound to the placeholders of the statement, by position.
+    pub fn modify(&self, sql: &str, binds: &[&ToData]) -> Result<u64> {
+        let stmt = self.prepare_stmt(Some(sql), None, false)?;
+        if stmt.get_info()?.is_query() {
+            stmt.close(None)?;
+            return Err(ErrorKind::UnexpectedQuery.into());
+        }
+
+        for (idx, value) in binds.iter().enumerate() {
+            stmt.bind_data_by_pos(idx as u32 + 1, &value.to_data())?;
+        }
+        stmt.execute(flags::EXEC_DEFAULT).map_err(|e| self.classify_error(e))?;
+        let count = stmt.get_row_count()?;
+        stmt.close(None)?;
+        Ok(count)
+    }
+
+    /// Returns true if a transaction is currently open on the connection, i.e. changes have been
+    /// made that have not yet been committed or rolled back. This reads the state OCI keeps for the
+    /// session, which ODPI-C 2 does not expose, so no round trip to the database is made. It
+    /// requires Oracle Client 12.1 or later.
+    pub fn in_transaction(&self) -> Result<bool> {
+        let svc_ctx = self.get_handle()?.as_ptr();
+        let mut env: *mut c_void = ptr::null_mut();
+        let mut err: *mut c_void = ptr::null_mut();
+        let mut session: *mut c_void = ptr::null_mut();
+        let mut in_progress: c_int = 0;
+
+        unsafe {
+            // Every service context has an environment, so this cannot fail and needs no error
+            // handle. The error handle for the calls that can fail is allocated from it.
+            oci::OCIAttrGet(svc_ctx,
+                            oci::OCI_HTYPE_SVCCTX,
+                            &mut env as *mut *mut c_void as *mut c_void,
+                            ptr::null_mut(),
+                            oci::OCI_ATTR_ENV,
+                            ptr::null_mut());
+            if oci::OCIHandleAlloc(env, &mut err, oci::OCI_HTYPE_ERROR, 0, ptr::null_mut()) !=
+               oci::OCI_SUCCESS {
+                return Err(ErrorKind::Connection("OCIHandleAlloc".to_string()).into());
+            }
+            let res = if oci::OCIAttrGet(svc_ctx,
+                                         oci::OCI_HTYPE_SVCCTX,
+                                         &mut session as *mut *mut c_void as *mut c_void,
+                                         ptr::null_mut(),
+                                         oci::OCI_ATTR_SESSION,
+                                         err) != oci::OCI_SUCCESS ||
+                         oci::OCIAttrGet(session,
+                                         oci::OCI_HTYPE_SESSION,
+                                         &mut in_progress as *mut c_int as *mut c_void,
+                                         ptr::null_mut(),
+                                         oci::OCI_ATTR_TRANSACTION_IN_PROGRESS,
+                                         err) != oci::OCI_SUCCESS {
+                Err(ErrorKind::Connection("OCIAttrGet".to_string()).into())
+            } else {
+                Ok(in_progress != 0)
+            };
+            oci::OCIHandleFree(err, oci::OCI_HTYPE_ERROR);
+            res
+        }
+    }
+
     /// Returns a reference to a new set of dequeue options, used in dequeuing objects from a queue.
     /// The reference should be released as soon as it is no longer needed.
     pub fn new_deq_options(&self) -> Result<dequeue::Options> {
@@ -407,6 +774,42 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_newMsgProps".to_string()))
     }
 
+    /// Returns a reference to a new variable of the given object type, which can be used for
+    /// binding objects and collections to a statement. PL/SQL index-by tables of records are
+    /// described by a collection object type such as `PKG.REC_TAB_T`: build the collection with
+    /// `ObjectType::create_object()`, append one record object per element with
+    /// `Object::append_object()` and set it on the variable with `Var::set_from_object()`. The
+    /// reference should be released as soon as it is no longer needed.
+    ///
+    /// * `object_type` - the type of the objects held by the variable.
+    /// * `max_array_size` - the maximum number of rows that can be fetched or bound at one time
+    /// from the database.
+    /// * `is_array` - boolean value indicating if the variable refers to a PL/SQL array or simply
+    /// to buffers used for binding or fetching data. When true, the number of elements bound is set
+    /// with `Var::set_num_elements_in_array()`.
+    pub fn new_object_var(&self,
+                          object_type: &ObjectType,
+                          max_array_size: u32,
+                          is_array: bool)
+                          -> Result<Var> {
+        let mut var_ptr = ptr::null_mut();
+        let mut data_ptr = ptr::null_mut();
+        let ia = if is_array { 1 } else { 0 };
+
+        try_dpi!(externs::dpiConn_newVar(self.inner,
+                                         flags::ODPIOracleTypeNum::Object,
+                                         flags::ODPINativeTypeNum::Object,
+                                         max_array_size,
+                                         0,
+                                         0,
+                                         ia,
+                                         object_type.inner(),
+                                         &mut var_ptr,
+                                         &mut data_ptr),
+                 Ok(Var::new(var_ptr, flags::ODPINativeTypeNum::Object)),
+                 ErrorKind::Connection("dpiConn_newVar".to_string()))
+    }
+
     /// Returns a reference to a subscription which is used for requesting notifications of changes
     /// on tables or queries that are made in the database. The reference should be released as soon
     /// as it is no longer needed.
@@ -465,6 +868,8 @@ impl Connection {
     /// bytes. This flag is only used if the variable refers to character data.
     /// * `is_array` - boolean value indicating if the variable refers to a PL/SQL array or simply
     /// to buffers used for binding or fetching data.
+    ///
+    /// Variables holding objects are created with `new_object_var()` instead.
     pub fn new_var(&self,
                    oracle_type_num: flags::ODPIOracleTypeNum,
                    native_type_num: flags::ODPINativeTypeNum,
@@ -480,7 +885,6 @@ impl Connection {
         let sib = if size_is_bytes { 1 } else { 0 };
         let ia = if is_array { 1 } else { 0 };
 
-        /// TODO: Fix object_type when Object is implemented fully.
         try_dpi!(externs::dpiConn_newVar(self.inner,
                                          oracle_type_num,
                                          native_type_num,
@@ -491,10 +895,24 @@ impl Connection {
                                          object_type,
                                          &mut var_ptr,
                                          &mut data_ptr),
-                 Ok(var_ptr.into()),
+                 Ok(Var::new(var_ptr, native_type_num)),
                  ErrorKind::Connection("dpiConn_newVar".to_string()))
     }
 
+    /// Returns the number of cursors currently open in the session, from the session statistic
+    /// "opened cursors current". Statements kept in the statement cache hold their cursors open, so
+    /// the count is only expected to be stable, not zero, while statements are reused. This is
+    /// intended for detecting statement leaks in tests and long running services.
+    pub fn open_cursor_count(&self) -> Result<u32> {
+        let rows = self.query("select s.value from v$mystat s join v$statname n on \
+                               n.statistic# = s.statistic# where n.name = 'opened cursors \
+                               current'")?;
+        match rows.first().and_then(|row| row.first()) {
+            Some(value) => Ok(u64::from_data(value)? as u32),
+            None => Err(ErrorKind::NoDataFound.into()),
+        }
+    }
+
     /// Pings the database to verify that the connection is still alive.
     pub fn ping(&self) -> Result<()> {
         try_dpi!(externs::dpiConn_ping(self.inner),
@@ -530,8 +948,9 @@ impl Connection {
                         -> Result<Statement> {
         let sql_s = ODPIStr::from(sql);
         let tag_s = ODPIStr::from(tag);
-        let scroll_i = if scrollable { 0 } else { 1 };
+        let scroll_i = if scrollable { 1 } else { 0 };
         let mut stmt_ptr = ptr::null_mut();
+        let start = Instant::now();
 
         try_dpi!(externs::dpiConn_prepareStmt(self.inner,
                                               scroll_i,
@@ -540,15 +959,191 @@ impl Connection {
                                               tag_s.ptr(),
                                               tag_s.len(),
                                               &mut stmt_ptr),
-                 Ok(Statement::new(stmt_ptr)),
+                 {
+                     let mut stmt = Statement::new(stmt_ptr);
+                     stmt.set_conn(self.inner);
+                     if let Some(sql) = sql {
+                         stmt.set_sql(sql);
+                     }
+                     if let Some(ref sink) = *self.metrics_sink.borrow() {
+                         stmt.set_metrics(sink.clone(), start.elapsed());
+                     }
+                     if let Ok(server) = self.get_server_version() {
+                         stmt.set_versions(self.client_version.clone(), server);
+                     }
+                     let fetch_array_size = self.default_fetch_array_size.get();
+                     if fetch_array_size > 0 {
+                         stmt.set_fetch_array_size(fetch_array_size)?;
+                     }
+                     Ok(stmt)
+                 },
                  ErrorKind::Connection("dpiConn_prepareStmt".to_string()))
     }
 
+    /// Returns a reference to a one-off statement that should not be kept in the statement cache.
+    /// The statement is prepared without a tag and `Statement::close()` will ignore any tag that is
+    /// passed to it, so the statement can never be found again by tag.
+    ///
+    /// The statement cache itself is sized with `set_statement_cache_size()`, which only sets the
+    /// number of statements the cache may hold. ODPI-C does not expose a way to ask OCI to discard
+    /// a statement from the cache when it is released, so OCI may still keep the cursor, keyed on
+    /// its SQL text, until it is aged out by newer statements. Set the statement cache size to 0 to
+    /// disable caching for the connection entirely.
+    ///
+    /// * `sql` - the SQL that is to be prepared for execution, as a string in the encoding used for
+    ///  CHAR data.
+    pub fn prepare_uncached(&self, sql: &str) -> Result<Statement> {
+        let stmt = self.prepare_stmt(Some(sql), None, false)?;
+        stmt.set_uncached();
+        Ok(stmt)
+    }
+
+    /// Executes the given query and fetches all of its rows.
+    ///
+    /// * `sql` - the query to execute, as a string in the encoding used for CHAR data.
+    pub fn query(&self, sql: &str) -> Result<Vec<Vec<OwnedData>>> {
+        let stmt = self.prepare_stmt(Some(sql), None, false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let mut rows = Vec::new();
+        while let Some(row) = stmt.fetch_row()? {
+            rows.push(row.into_values());
+        }
+        stmt.close(None)?;
+        Ok(rows)
+    }
+
+    /// Executes the given query and caches all of its rows in-process for the given time to live.
+    /// Calls made with the same key before the time to live expires return the cached rows without
+    /// accessing the database. This is intended for reference data that rarely changes, such as
+    /// code tables. Results are keyed by `key` rather than by the SQL text, so callers control
+    /// invalidation, either by choosing a new key or by calling `invalidate_cached()`.
+    ///
+    /// * `key` - the key to cache the results under.
+    /// * `ttl` - how long the results remain valid.
+    /// * `sql` - the query to execute, as a string in the encoding used for CHAR data.
+    pub fn query_cached(&self,
+                        key: &str,
+                        ttl: Duration,
+                        sql: &str)
+                        -> Result<Vec<Vec<OwnedData>>> {
+        if let Some(&(loaded, ref rows)) = self.query_cache.borrow().get(key) {
+            if loaded.elapsed() < ttl {
+                return Ok(rows.clone());
+            }
+        }
+
+        let rows = self.query(sql)?;
+        self.query_cache_loads.set(self.query_cache_loads.get() + 1);
+        self.query_cache.borrow_mut().insert(key.to_string(), (Instant::now(), rows.clone()));
+        Ok(rows)
+    }
+
+    /// Executes the given query with a list of values bound as a single collection, rather than
+    /// generating one placeholder per value, so the statement text is the same regardless of the
+    /// number of values and is not limited to the 1000 expressions Oracle allows in an IN list. The
+    /// values are bound to the first placeholder as one of the collection types `SYS` provides for
+    /// the purpose, which the query expands with `TABLE()`, for example
+    /// `select name from keyed where id in (select column_value from table(:ids))`. Numbers are
+    /// bound as `SYS.ODCINUMBERLIST`, strings as `SYS.ODCIVARCHAR2LIST` and dates and timestamps
+    /// as `SYS.ODCIDATELIST`. Other values are rejected with `ErrorKind::TypeMismatch`.
+    ///
+    /// * `sql` - the query to execute, as a string in the encoding used for CHAR data.
+    /// * `values` - the values bound as a collection to the first placeholder of the query.
+    pub fn query_in<T: ToData>(&self, sql: &str, values: &[T]) -> Result<RowIterator> {
+        let values: Vec<OwnedData> = values.iter().map(|value| value.to_data()).collect();
+        let type_name = {
+            let sample = values.iter().find(|value| !value.is_null());
+            match sample {
+                None |
+                Some(&OwnedData::Int64(_)) |
+                Some(&OwnedData::Uint64(_)) |
+                Some(&OwnedData::Float(_)) |
+                Some(&OwnedData::Double(_)) => "SYS.ODCINUMBERLIST",
+                Some(&OwnedData::String(_)) => "SYS.ODCIVARCHAR2LIST",
+                Some(&OwnedData::Date(_)) |
+                Some(&OwnedData::Timestamp(_)) => "SYS.ODCIDATELIST",
+                Some(other) => {
+                    return Err(ErrorKind::TypeMismatch("collection element".to_string(),
+                                                       other.type_name().to_string())
+                                       .into())
+                }
+            }
+        };
+
+        let coll_type = self.get_object_type(type_name)?;
+        let coll = match coll_type.create_object() {
+            Ok(coll) => coll,
+            Err(e) => {
+                let _ = coll_type.release();
+                return Err(e);
+            }
+        };
+        let var = self.collection_var(&coll_type, &coll, &values);
+        let released = coll.release().and(coll_type.release());
+        let var = var?;
+        released?;
+
+        let stmt = self.prepare_stmt(Some(sql), None, false)?;
+        stmt.bind_by_pos(1, &var)?;
+        stmt.execute(flags::EXEC_DEFAULT).map_err(|e| self.classify_error(e))?;
+        Ok(RowIterator::new(stmt))
+    }
+
+    /// Executes the given query and returns the value of the first column of its first row,
+    /// converted to the requested type. `None` is returned when the query returns no rows or the
+    /// value is NULL, as aggregates such as `max()` do over no rows, so callers can tell an empty
+    /// result from a failure. NO_DATA_FOUND (ORA-01403) raised by PL/SQL while the statement is
+    /// executed is returned as `ErrorKind::PlsqlNoDataFound` rather than as a generic error.
+    ///
+    /// * `sql` - the query to execute, as a string in the encoding used for CHAR data.
+    pub fn query_scalar<T: FromData>(&self, sql: &str) -> Result<Option<T>> {
+        let stmt = self.prepare_stmt(Some(sql), None, false)?;
+        stmt.execute(flags::EXEC_DEFAULT).map_err(|e| self.classify_error(e))?;
+        let row = stmt.fetch_row()?;
+        stmt.close(None)?;
+
+        match row {
+            Some(row) => row.get_opt::<T>(1),
+            None => Ok(None),
+        }
+    }
+
+    /// Executes the given query and converts its first row into the requested type, usually a
+    /// tuple such as `(i64, String)`. `None` is returned when the query returns no rows. Any
+    /// further rows are ignored.
+    ///
+    /// * `sql` - the query to execute, as a string in the encoding used for CHAR data.
+    /// * `binds` - the values bound to the placeholders of the query, by position.
+    pub fn query_row<T: FromRow>(&self, sql: &str, binds: &[&ToData]) -> Result<Option<T>> {
+        let stmt = self.prepare_stmt(Some(sql), None, false)?;
+        for (idx, value) in binds.iter().enumerate() {
+            stmt.bind_data_by_pos(idx as u32 + 1, &value.to_data())?;
+        }
+        stmt.execute(flags::EXEC_DEFAULT).map_err(|e| self.classify_error(e))?;
+        let row = stmt.fetch_row()?;
+        stmt.close(None)?;
+
+        match row {
+            Some(row) => T::from_row(&row).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Begins a read only transaction, as with `begin_read_only()`, returning a guard that ends the
+    /// transaction with a commit when it is dropped.
+    pub fn read_only<'a>(&'a self) -> Result<ReadOnlyTransaction<'a>> {
+        self.begin_read_only()?;
+        Ok(ReadOnlyTransaction { conn: self })
+    }
+
     /// Releases a reference to the connection. A count of the references to the connection is
     /// maintained and when this count reaches zero, the memory associated with the connection is
     /// freed and the connection is closed or released back to the session pool if that has not
-    /// already taken place using the function `close()`.
+    /// already taken place using the function `close()`. The reference given up is no longer
+    /// released when the connection is dropped.
     pub fn release(&self) -> Result<()> {
+        self.refs.set(self.refs.get().saturating_sub(1));
         try_dpi!(externs::dpiConn_release(self.inner),
                  Ok(()),
                  ErrorKind::Connection("dpiConn_release".to_string()))
@@ -561,6 +1156,59 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_rollback".to_string()))
     }
 
+    /// Sets whether an open transaction is rolled back when the connection is dropped. This is
+    /// enabled by default, so that uncommitted changes are discarded explicitly rather than left
+    /// for Oracle to roll back when the session ends. The rollback is only issued when
+    /// `in_transaction()` reports an open transaction, or cannot tell. Connections on which
+    /// `close()` or `release()` has been called are never used when they are dropped.
+    ///
+    /// * `enabled` - whether to roll back on drop.
+    pub fn rollback_on_drop(&self, enabled: bool) {
+        self.rollback_on_drop.set(enabled);
+    }
+
+    /// Rolls back the current transaction to the given savepoint, undoing the work done since the
+    /// savepoint was created while keeping the transaction active.
+    ///
+    /// * `name` - the name of the savepoint, as given to `savepoint()`.
+    pub fn rollback_to(&self, name: &str) -> Result<()> {
+        self.execute_sql(&format!("rollback to savepoint {}", quote_ident(name)?))
+    }
+
+    /// Runs a script made up of several statements, executing each in turn and stopping at the
+    /// first that fails. The script is split as SQL*Plus would split it, with
+    /// `util::split_script()`: SQL statements end with a ';', and PL/SQL blocks and stored unit
+    /// definitions end with a line holding only a '/'. Queries are executed but their rows are not
+    /// fetched. No commit is performed other than those in the script itself or implied by DDL.
+    ///
+    /// * `script` - the statements to run, as a string in the encoding used for CHAR data.
+    pub fn run_script(&self, script: &str) -> Result<()> {
+        for stmt in split_script(script) {
+            self.execute_sql(&stmt)?;
+        }
+        Ok(())
+    }
+
+    /// Creates a savepoint in the current transaction, which can later be rolled back to with
+    /// `rollback_to()`.
+    ///
+    /// * `name` - the name of the savepoint. Simple identifiers are used as is, and any other name
+    /// is quoted, as with `util::quote_ident()`. Names containing control characters are rejected
+    /// with `ErrorKind::InvalidIdentifier`.
+    pub fn savepoint(&self, name: &str) -> Result<()> {
+        self.execute_sql(&format!("savepoint {}", quote_ident(name)?))
+    }
+
+    /// Returns the time zone of the session, as with `SELECT SESSIONTIMEZONE FROM DUAL`. This is
+    /// either an offset such as "+02:00" or a region name such as "Europe/Paris", and is the time
+    /// zone in which TIMESTAMP WITH LOCAL TIME ZONE values are returned.
+    pub fn session_timezone(&self) -> Result<String> {
+        match self.query_scalar::<String>("select sessiontimezone from dual")? {
+            Some(tz) => Ok(tz),
+            None => Err(ErrorKind::NoDataFound.into()),
+        }
+    }
+
     /// Sets the action attribute on the connection. This is one of the end-to-end tracing
     /// attributes that can be tracked in database views, shown in audit trails and seen in tools
     /// such as Enterprise Manager.
@@ -603,6 +1251,12 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_setClientInfo".to_string()))
     }
 
+    /// Set the ODPI-C context used to read the details of errors, for connections that were not
+    /// created by `create()`, such as those acquired from a pool.
+    pub(crate) fn set_context(&mut self, context: Option<Arc<Handle>>) {
+        self.context = context;
+    }
+
     /// Sets the current schema to be used on the connection. This has the same effect as the SQL
     /// statement ALTER SESSION SET CURRENT_SCHEMA. The value be changed when the next call
     /// requiring a round trip to the server is performed. If the new schema name does not exist,
@@ -635,6 +1289,30 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_setDbOp".to_string()))
     }
 
+    /// Sets the fetch array size given to every statement prepared on the connection from now on,
+    /// so that fetch batching can be tuned once instead of calling
+    /// `Statement::set_fetch_array_size()` on each statement. Statements that have already been
+    /// prepared are not changed, and each statement may still override the size.
+    ///
+    /// * `array_size` - the fetch array size to use. A value of zero restores the ODPI-C default of
+    /// DPI_DEFAULT_FETCH_ARRAY_SIZE.
+    pub fn set_default_fetch_array_size(&self, array_size: u32) {
+        self.default_fetch_array_size.set(array_size);
+    }
+
+    /// Changes the edition used by the connection for the rest of the session, as with
+    /// `ALTER SESSION SET EDITION`. Objects in the database are resolved using the new edition,
+    /// which is then returned by `get_edition()`.
+    ///
+    /// * `edition` - the name of the edition. It is quoted as with `util::quote_ident()`, so names
+    /// containing control characters are rejected with `ErrorKind::InvalidIdentifier`.
+    pub fn set_edition(&self, edition: &str) -> Result<()> {
+        self.execute_sql(&format!("alter session set edition = {}", quote_ident(edition)?))?;
+        let current = self.query_scalar::<String>("select sys_context('USERENV', \
+                                                   'CURRENT_EDITION_NAME') from dual")?;
+        *self.edition.borrow_mut() = current;
+        Ok(())
+    }
 
     /// Sets the external name that is being used by the connection. This value is used when logging
     /// distributed transactions.
@@ -643,6 +1321,7 @@ impl Connection {
     /// the external name.
     pub fn set_external_name(&self, external_name: &str) -> Result<()> {
         let ext_name_s = ODPIStr::from(external_name);
+        *self.external_name.borrow_mut() = None;
 
         try_dpi!(externs::dpiConn_setExternalName(self.inner, ext_name_s.ptr(), ext_name_s.len()),
                  Ok(()),
@@ -656,12 +1335,26 @@ impl Connection {
     /// the internal name.
     pub fn set_internal_name(&self, internal_name: &str) -> Result<()> {
         let int_name_s = ODPIStr::from(internal_name);
+        *self.internal_name.borrow_mut() = None;
 
         try_dpi!(externs::dpiConn_setInternalName(self.inner, int_name_s.ptr(), int_name_s.len()),
                  Ok(()),
                  ErrorKind::Connection("dpiConn_setInternalName".to_string()))
     }
 
+    /// Sets the sink that is given a `StmtMetric` for every statement prepared on the connection
+    /// from now on, once the statement is closed or dropped. The metric reports the time spent
+    /// preparing, executing and fetching from the statement, and the number of rows it fetched or
+    /// affected, which can be forwarded to a monitoring system such as Prometheus or statsd.
+    /// Statements prepared before the sink is set are not reported. The sink must be `Send` and
+    /// `Sync` because a statement, and its sink with it, can be moved to another thread by
+    /// `Statement::spawn_stream()`.
+    ///
+    /// * `sink` - the function to call with the metrics of each completed statement.
+    pub fn set_metrics_sink(&self, sink: Box<Fn(StmtMetric) + Send + Sync>) {
+        *self.metrics_sink.borrow_mut() = Some(Arc::new(sink));
+    }
+
     /// Sets the module attribute on the connection. This is one of the end-to-end tracing
     /// attributes that can be tracked in database views, shown in audit trails and seen in tools
     /// such as Enterprise Manager.
@@ -676,6 +1369,19 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_setModule".to_string()))
     }
 
+    /// Sets an NLS parameter for the rest of the session, as with `ALTER SESSION SET`, for example
+    /// `NLS_DATE_FORMAT` or `NLS_NUMERIC_CHARACTERS`.
+    ///
+    /// * `parameter` - the name of the NLS parameter. It is quoted as with `util::quote_ident()`,
+    /// so names containing control characters are rejected with `ErrorKind::InvalidIdentifier`.
+    /// * `value` - the new value of the parameter. It is written as a string literal, with any
+    /// single quotes doubled.
+    pub fn set_nls_parameter(&self, parameter: &str, value: &str) -> Result<()> {
+        self.execute_sql(&format!("alter session set {} = '{}'",
+                                  quote_ident(parameter)?,
+                                  value.replace('\'', "''")))
+    }
+
     /// Sets the size of the statement cache.
     ///
     /// * `size` - the new size of the statement cache, in number of statements.
@@ -685,6 +1391,37 @@ impl Connection {
                  ErrorKind::Connection("dpiConn_setStmtCacheSize".to_string()))
     }
 
+    /// Sets any of the end-to-end tracing attributes that are provided: the module, action, client
+    /// info and client identifier. Fields that are None are left unchanged. As with the individual
+    /// setters, ODPI-C sends these values to the database with the next round trip rather than
+    /// immediately, so this is a convenience over calling `set_module()`, `set_action()`,
+    /// `set_client_info()` and `set_client_identifier()` in turn.
+    ///
+    /// * `module` - the value to set the module attribute to.
+    /// * `action` - the value to set the action attribute to.
+    /// * `client_info` - the value to set the client info attribute to.
+    /// * `client_id` - the value to set the client identifier attribute to.
+    pub fn set_tracing(&self,
+                       module: Option<&str>,
+                       action: Option<&str>,
+                       client_info: Option<&str>,
+                       client_id: Option<&str>)
+                       -> Result<()> {
+        if let Some(module) = module {
+            self.set_module(module)?;
+        }
+        if let Some(action) = action {
+            self.set_action(action)?;
+        }
+        if let Some(client_info) = client_info {
+            self.set_client_info(client_info)?;
+        }
+        if let Some(client_id) = client_id {
+            self.set_client_identifier(client_id)?;
+        }
+        Ok(())
+    }
+
     /// Shuts down the database. This function must be called twice for the database to be shut down
     /// successfully. After calling this function the first time, the SQL statements "alter database
     /// close normal" and "alter database dismount" must be executed. Once that is complete this
@@ -706,121 +1443,818 @@ impl Connection {
                  Ok(()),
                  ErrorKind::Connection("dpiConn_startupDatabase".to_string()))
     }
+
+    /// Returns whether the given feature is available on this connection, based on the versions of
+    /// the Oracle Client and the Oracle Database. The client version is only known for standalone
+    /// connections created with `create()`; for connections acquired from a pool only the server
+    /// version is checked.
+    ///
+    /// * `feature` - the feature to check for.
+    pub fn supports_feature(&self, feature: Feature) -> Result<bool> {
+        let server = self.get_server_version()?;
+        Ok(feature.is_supported(self.client_version.as_ref(), &server))
+    }
+
+    /// Deregisters a subscription created by `new_subscription()`, so that notifications are no
+    /// longer sent to it. ODPI-C 2 deregisters a subscription by closing it, so this is the same as
+    /// calling `Subscription::close()`. The subscription must still be released with
+    /// `Subscription::release()`.
+    ///
+    /// * `subscr` - the subscription to deregister.
+    pub fn unsubscribe(&self, subscr: &Subscription) -> Result<()> {
+        subscr.close()
+    }
+
+    /// Inserts a row, or updates the row with the same key if one exists, with a single MERGE
+    /// statement, and returns the number of rows affected. The key columns identify the row and
+    /// are only written when the row is inserted; the value columns are written either way.
+    ///
+    /// * `table` - the table to write to, optionally qualified by its schema as `schema.table`.
+    /// * `key_cols` - the columns identifying the row. At least one is required, or
+    /// `ErrorKind::MissingKeyColumns` is returned.
+    /// * `value_cols` - the other columns to write.
+    /// * `values` - one value for each key column followed by one for each value column, in the
+    /// order given, or `ErrorKind::ValueCount` is returned.
+    ///
+    /// The table and column names are quoted with `util::quote_ident()`, so invalid names are
+    /// rejected with `ErrorKind::InvalidIdentifier`.
+    pub fn upsert(&self,
+                  table: &str,
+                  key_cols: &[&str],
+                  value_cols: &[&str],
+                  values: &[&ToData])
+                  -> Result<u64> {
+        let expected = (key_cols.len() + value_cols.len()) as u32;
+        if values.len() as u32 != expected {
+            return Err(ErrorKind::ValueCount(expected, values.len() as u32).into());
+        }
+        self.modify(&merge_sql(table, key_cols, value_cols)?, values)
+    }
+}
+
+/// Build the MERGE statement used by `Connection::upsert()`, with one positional placeholder for
+/// each key column followed by one for each value column.
+fn merge_sql(table: &str, key_cols: &[&str], value_cols: &[&str]) -> Result<String> {
+    if key_cols.is_empty() {
+        return Err(ErrorKind::MissingKeyColumns.into());
+    }
+
+    let mut table_parts = Vec::new();
+    for part in table.splitn(2, '.') {
+        table_parts.push(quote_ident(part)?);
+    }
+    let keys = key_cols.iter().map(|col| quote_ident(col)).collect::<Result<Vec<String>>>()?;
+    let vals = value_cols.iter().map(|col| quote_ident(col)).collect::<Result<Vec<String>>>()?;
+    let all: Vec<&String> = keys.iter().chain(vals.iter()).collect();
+
+    let source = all.iter()
+        .enumerate()
+        .map(|(idx, col)| format!(":{} {}", idx + 1, col))
+        .collect::<Vec<String>>()
+        .join(", ");
+    let on = keys.iter()
+        .map(|col| format!("t.{} = s.{}", col, col))
+        .collect::<Vec<String>>()
+        .join(" and ");
+    let insert_cols = all.iter().map(|col| col.to_string()).collect::<Vec<String>>().join(", ");
+    let insert_vals = all.iter()
+        .map(|col| format!("s.{}", col))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let mut sql = format!("merge into {} t using (select {} from dual) s on ({})",
+                          table_parts.join("."),
+                          source,
+                          on);
+    if !vals.is_empty() {
+        let set = vals.iter()
+            .map(|col| format!("t.{} = s.{}", col, col))
+            .collect::<Vec<String>>()
+            .join(", ");
+        sql.push_str(&format!(" when matched then update set {}", set));
+    }
+    sql.push_str(&format!(" when not matched then insert ({}) values ({})",
+                          insert_cols,
+                          insert_vals));
+    Ok(sql)
+}
+
+/// Call `f` until it succeeds, retrying up to `retries` times while it fails with
+/// `ErrorKind::ResourceBusy`. The wait between attempts starts at `backoff` and doubles after
+/// each attempt.
+fn retry_resource_busy<F>(retries: u32, backoff: Duration, mut f: F) -> Result<()>
+    where F: FnMut() -> Result<()>
+{
+    let mut wait = backoff;
+    let mut attempt = 0;
+
+    loop {
+        match f() {
+            Err(Error(ErrorKind::ResourceBusy(_), _)) if attempt < retries => {
+                thread::sleep(wait);
+                wait = wait * 2;
+                attempt += 1;
+            }
+            res => return res,
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if self.inner.is_null() || self.refs.get() == 0 {
+            return;
+        }
+
+        if !self.closed.get() && self.rollback_on_drop.get() &&
+           self.in_transaction().unwrap_or(true) && self.rollback().is_err() {
+            try_error!(self.stderr, "Failed to roll back the transaction on drop");
+        }
+
+        for _ in 0..self.refs.get() {
+            if unsafe { externs::dpiConn_release(self.inner) } != constants::DPI_SUCCESS {
+                try_error!(self.stderr, "Failed to release the connection on drop");
+                break;
+            }
+        }
+        self.refs.set(0);
+    }
 }
 
+/// Wrap an ODPI-C connection, taking ownership of one reference to it. The reference is released
+/// when the connection is dropped, so the pointer must not be released by the caller as well.
 impl From<*mut ODPIConn> for Connection {
     fn from(inner: *mut ODPIConn) -> Connection {
         Connection {
             inner: inner,
+            context: None,
+            client_version: None,
+            query_cache: RefCell::new(HashMap::new()),
+            query_cache_loads: Cell::new(0),
+            default_fetch_array_size: Cell::new(0),
+            edition: RefCell::new(None),
+            external_name: RefCell::new(None),
+            internal_name: RefCell::new(None),
+            server_version: RefCell::new(None),
+            keepalive: RefCell::new(None),
+            rollback_on_drop: Cell::new(true),
+            metrics_sink: RefCell::new(None),
+            refs: Cell::new(1),
+            closed: Cell::new(false),
             stdout: None,
             stderr: None,
         }
     }
 }
 
-#[cfg(test)]
-mod test {
-    use test::{ContextResult, CREDS, CTXT, ENC};
-    use connection::Connection;
-    use context::Context;
-    use error;
-    use odpi::flags::ODPIDeqMode::*;
-    use odpi::flags::ODPIVisibility::*;
-    use odpi::flags::ODPIMessageDeliveryMode::*;
-    use odpi::flags::ODPINativeTypeNum::*;
-    use odpi::flags::ODPIOracleTypeNum::*;
-    use odpi::structs::ODPISubscrMessage;
-    use rand::{self, Rng};
+/// Builder for the parameters of `Connection::create()`.
+#[derive(Clone, Default)]
+pub struct ConnectionBuilder {
+    /// The name of the user used for authenticating the user.
+    username: Option<String>,
+    /// The password used for authenticating the user.
+    password: Option<String>,
+    /// The connect string identifying the database to which a connection is to be established.
+    connect_string: Option<String>,
+    /// The edition to use for the connection, for edition-based redefinition.
+    edition: Option<String>,
+    /// The encoding to use for CHAR data.
+    encoding: Option<String>,
+    /// The encoding to use for NCHAR data.
+    nchar_encoding: Option<String>,
+    /// The mode used for authorizing the connection.
+    auth_mode: Option<flags::ODPIAuthMode>,
+    /// The connection class to use with Database Resident Connection Pooling.
+    connection_class: Option<String>,
+    /// The purity to use with Database Resident Connection Pooling.
+    purity: Option<flags::ODPIPurity>,
+    /// The password to change the password of the user to while connecting.
+    new_password: Option<String>,
+    /// Is external authentication used instead of a username and password?
+    external_auth: bool,
+}
 
-    enum ConnResult {
-        Ok(Connection),
-        Err(error::Error),
+impl ConnectionBuilder {
+    /// Create a new builder with no parameters set.
+    pub fn new() -> ConnectionBuilder {
+        Default::default()
     }
 
-    unsafe impl Sync for ConnResult {}
+    /// Set the name of the user used for authenticating the user.
+    pub fn username(&mut self, username: &str) -> &mut ConnectionBuilder {
+        self.username = Some(username.to_string());
+        self
+    }
 
-    lazy_static! {
-        static ref CONN: ConnResult = {
-            let ctxt = match *CTXT {
-                ContextResult::Ok(ref ctxt) => ctxt,
-                ContextResult::Err(ref _e) => return ConnResult::Err(
-                    error::ErrorKind::Connection("CONTEXT".to_string()).into()
-                ),
-            };
-            let ccp = match ctxt.init_common_create_params() {
-                Ok(mut ccp) => {
-                    ccp.set_encoding(ENC.as_ptr());
-                    ccp.set_nchar_encoding(ENC.as_ptr());
-                    ccp
-                },
-                Err(e) => return ConnResult::Err(e),
-            };
-            match Connection::create(ctxt,
-                                     Some(&CREDS[0]),
-                                     Some(&CREDS[1]),
-                                     Some("//oic.cbsnae86d3iv.us-east-2.rds.amazonaws.com/ORCL"),
-                                     Some(ccp),
-                                     None) {
-                Ok(conn) => ConnResult::Ok(conn),
-                Err(e) => ConnResult::Err(e),
-            }
-        };
+    /// Set the password used for authenticating the user.
+    pub fn password(&mut self, password: &str) -> &mut ConnectionBuilder {
+        self.password = Some(password.to_string());
+        self
     }
 
+    /// Set the connect string identifying the database to which a connection is to be
+    /// established.
+    pub fn connect_string(&mut self, connect_string: &str) -> &mut ConnectionBuilder {
+        self.connect_string = Some(connect_string.to_string());
+        self
+    }
 
-    fn context_error_info(ctxt: &Context) {
-        use std::io::{self, Write};
-        let ctxt_error = ctxt.get_error();
-        writeln!(io::stderr(), "{}", ctxt_error).expect("badness");
-        assert!(false);
+    /// Set the connect string to the descriptor generated by the given `ConnectDescriptor`.
+    pub fn connect_descriptor(&mut self, descriptor: &ConnectDescriptor) -> &mut ConnectionBuilder {
+        self.connect_string = Some(descriptor.to_string());
+        self
     }
 
-    #[test]
-    fn create() {
-        match *CONN {
-            ConnResult::Ok(ref _conn) => assert!(true),
-            ConnResult::Err(ref _e) => assert!(false),
-        }
+    /// Set the connection class and purity used when connecting through Database Resident
+    /// Connection Pooling (DRCP). The connect string must also request a pooled server, for
+    /// example by ending with `:pooled`.
+    ///
+    /// * `connection_class` - the name of the connection class. Pooled servers are only shared
+    /// between connections using the same class.
+    /// * `purity` - `DPI_PURITY_SELF` if the session may have prior session state, or
+    /// `DPI_PURITY_NEW` if a session with no prior state is required.
+    pub fn drcp(&mut self,
+                connection_class: &str,
+                purity: flags::ODPIPurity)
+                -> &mut ConnectionBuilder {
+        self.connection_class = Some(connection_class.to_string());
+        self.purity = Some(purity);
+        self
     }
 
-    #[test]
-    fn add_ref_release() {
-        let conn = match *CONN {
-            ConnResult::Ok(ref conn) => conn,
-            ConnResult::Err(ref _e) => return assert!(false),
-        };
+    /// Set the edition to use for the connection. Objects in the database are resolved using this
+    /// edition, as with `ALTER SESSION SET EDITION`.
+    pub fn edition(&mut self, edition: &str) -> &mut ConnectionBuilder {
+        self.edition = Some(edition.to_string());
+        self
+    }
 
-        match conn.add_ref() {
-            Ok(_) => {
-                match conn.release() {
-                    Ok(_) => assert!(true),
-                    Err(_) => assert!(false),
-                }
-            }
-            Err(_) => assert!(false),
-        }
+    /// Set the encoding to use for CHAR data, as an IANA or Oracle specific character set name
+    /// such as `UTF-8`. The NLS_LANG environment variable is used if it is not set.
+    pub fn encoding(&mut self, encoding: &str) -> &mut ConnectionBuilder {
+        self.encoding = Some(encoding.to_string());
+        self
     }
 
-    #[test]
-    #[ignore]
-    fn break_execution() {
-        let conn = match *CONN {
-            ConnResult::Ok(ref conn) => conn,
-            ConnResult::Err(ref _e) => return assert!(false),
-        };
+    /// Set the encoding to use for NCHAR data, as an IANA or Oracle specific character set name
+    /// such as `UTF-8`. The NLS_NCHAR environment variable is used if it is not set.
+    pub fn nchar_encoding(&mut self, nchar_encoding: &str) -> &mut ConnectionBuilder {
+        self.nchar_encoding = Some(nchar_encoding.to_string());
+        self
+    }
 
-        match conn.break_execution() {
-            Ok(_) => assert!(true),
-            Err(_) => assert!(false),
-        }
+    /// Set the mode used for authorizing the connection, such as `DPI_MODE_AUTH_SYSDBA`.
+    pub fn auth_mode(&mut self, auth_mode: flags::ODPIAuthMode) -> &mut ConnectionBuilder {
+        self.auth_mode = Some(auth_mode);
+        self
     }
 
-    #[test]
-    fn begin_tx_prepare_commit() {
-        let conn = match *CONN {
-            ConnResult::Ok(ref conn) => conn,
-            ConnResult::Err(ref _e) => return assert!(false),
-        };
+    /// Set the connection class used when connecting through Database Resident Connection
+    /// Pooling, leaving the purity at its default. Use `drcp()` to set both.
+    pub fn connection_class(&mut self, connection_class: &str) -> &mut ConnectionBuilder {
+        self.connection_class = Some(connection_class.to_string());
+        self
+    }
+
+    /// Set a new password for the user. The password of the user is changed to this value while
+    /// the connection is established, which allows connecting with an expired password.
+    pub fn new_password(&mut self, new_password: &str) -> &mut ConnectionBuilder {
+        self.new_password = Some(new_password.to_string());
+        self
+    }
+
+    /// Set whether external authentication, such as an Oracle wallet or the operating system user,
+    /// is used instead of a username and password. No username or password should be set when it
+    /// is.
+    pub fn external_auth(&mut self, external_auth: bool) -> &mut ConnectionBuilder {
+        self.external_auth = external_auth;
+        self
+    }
+
+    /// Create a standalone connection using the parameters that have been set, by calling
+    /// `Connection::create()`.
+    ///
+    /// * `context` - the context in which the connection is created.
+    pub fn build(&self, context: &Context) -> Result<Connection> {
+        let mut common_create_params = context.init_common_create_params()?;
+        if let Some(ref edition) = self.edition {
+            common_create_params.set_edition(edition);
+        }
+        // The encodings are only read while the connection is created, so they must outlive the
+        // call to `Connection::create()`.
+        let encoding = match self.encoding {
+            Some(ref encoding) => Some(CString::new(&encoding[..])?),
+            None => None,
+        };
+        if let Some(ref encoding) = encoding {
+            common_create_params.set_encoding(encoding.as_ptr());
+        }
+        let nchar_encoding = match self.nchar_encoding {
+            Some(ref nchar_encoding) => Some(CString::new(&nchar_encoding[..])?),
+            None => None,
+        };
+        if let Some(ref nchar_encoding) = nchar_encoding {
+            common_create_params.set_nchar_encoding(nchar_encoding.as_ptr());
+        }
+
+        Connection::create(context,
+                           self.username.as_ref().map(|s| &s[..]),
+                           self.password.as_ref().map(|s| &s[..]),
+                           self.connect_string.as_ref().map(|s| &s[..]),
+                           Some(common_create_params),
+                           self.conn_create_params(context)?)
+    }
+
+    /// Initialize the connection creation parameters for the authorization mode, connection class,
+    /// purity, new password and external authentication, if any of them have been set.
+    fn conn_create_params(&self, context: &Context) -> Result<Option<ConnCreate>> {
+        if self.auth_mode.is_none() && self.connection_class.is_none() && self.purity.is_none() &&
+           self.new_password.is_none() && !self.external_auth {
+            return Ok(None);
+        }
+
+        let mut conn_create_params = context.init_conn_create_params()?;
+        if let Some(auth_mode) = self.auth_mode {
+            conn_create_params.set_auth_mode(auth_mode);
+        }
+        if let Some(ref connection_class) = self.connection_class {
+            conn_create_params.set_connection_class(connection_class);
+        }
+        if let Some(purity) = self.purity {
+            conn_create_params.set_purity(purity);
+        }
+        if let Some(ref new_password) = self.new_password {
+            conn_create_params.set_new_password(new_password);
+        }
+        if self.external_auth {
+            conn_create_params.set_external_auth(1);
+        }
+        Ok(Some(conn_create_params))
+    }
+}
+
+/// Passwords are redacted so that builders can be logged safely.
+impl fmt::Debug for ConnectionBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ConnectionBuilder")
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("connect_string", &self.connect_string)
+            .field("edition", &self.edition)
+            .field("encoding", &self.encoding)
+            .field("nchar_encoding", &self.nchar_encoding)
+            .field("auth_mode", &self.auth_mode)
+            .field("connection_class", &self.connection_class)
+            .field("purity", &self.purity)
+            .field("new_password", &self.new_password.as_ref().map(|_| "<redacted>"))
+            .field("external_auth", &self.external_auth)
+            .finish()
+    }
+}
+
+/// Builder for a connect descriptor, the long form of an easy connect string, which allows the
+/// timeouts of establishing a connection to be set. The descriptor is generated by `to_string()`
+/// and is used as the connect string of `Connection::create()`, `ConnectionBuilder` or a pool.
+#[derive(Clone, Debug)]
+pub struct ConnectDescriptor {
+    /// The host name or IP address of the listener.
+    host: String,
+    /// The port of the listener.
+    port: u16,
+    /// The service name of the database.
+    service_name: String,
+    /// The number of seconds allowed to establish a connection, including authentication.
+    connect_timeout: Option<u32>,
+    /// The number of seconds allowed to establish the TCP connection to the listener.
+    transport_connect_timeout: Option<u32>,
+}
+
+impl ConnectDescriptor {
+    /// Create a new descriptor for the given service on a listener of the default port, 1521. The
+    /// values are written into the descriptor as is, so `ErrorKind::InvalidDescriptorValue` is
+    /// returned if either contains `(`, `)` or `=`, which would change its structure.
+    ///
+    /// * `host` - the host name or IP address of the listener.
+    /// * `service_name` - the service name of the database.
+    pub fn new(host: &str, service_name: &str) -> Result<ConnectDescriptor> {
+        for value in &[host, service_name] {
+            if value.contains(|c| c == '(' || c == ')' || c == '=') {
+                return Err(ErrorKind::InvalidDescriptorValue(value.to_string()).into());
+            }
+        }
+
+        Ok(ConnectDescriptor {
+               host: host.to_string(),
+               port: 1521,
+               service_name: service_name.to_string(),
+               connect_timeout: None,
+               transport_connect_timeout: None,
+           })
+    }
+
+    /// Set the `CONNECT_TIMEOUT`, the number of seconds allowed to establish a connection to the
+    /// database, including the TCP connection and authentication. An error, usually ORA-12170, is
+    /// returned once it has passed. This requires an 11.2 or higher client.
+    pub fn connect_timeout(&mut self, seconds: u32) -> &mut ConnectDescriptor {
+        self.connect_timeout = Some(seconds);
+        self
+    }
+
+    /// Set the port of the listener.
+    pub fn port(&mut self, port: u16) -> &mut ConnectDescriptor {
+        self.port = port;
+        self
+    }
+
+    /// Set the `TRANSPORT_CONNECT_TIMEOUT`, the number of seconds allowed to establish the TCP
+    /// connection to the listener. This bounds the wait when the host does not answer at all, and
+    /// requires an 11.2 or higher client.
+    pub fn transport_connect_timeout(&mut self, seconds: u32) -> &mut ConnectDescriptor {
+        self.transport_connect_timeout = Some(seconds);
+        self
+    }
+}
+
+impl fmt::Display for ConnectDescriptor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(DESCRIPTION=")?;
+        if let Some(seconds) = self.connect_timeout {
+            write!(f, "(CONNECT_TIMEOUT={})", seconds)?;
+        }
+        if let Some(seconds) = self.transport_connect_timeout {
+            write!(f, "(TRANSPORT_CONNECT_TIMEOUT={})", seconds)?;
+        }
+        write!(f,
+               "(ADDRESS=(PROTOCOL=TCP)(HOST={})(PORT={}))(CONNECT_DATA=(SERVICE_NAME={})))",
+               self.host,
+               self.port,
+               self.service_name)
+    }
+}
+
+/// The OCI service context handle (`OCISvcCtx *`) of a connection, returned by
+/// `Connection::get_handle()`. The handle belongs to the connection, so it must not be freed, and
+/// it is only valid until the connection is closed or released.
+#[derive(Clone, Copy, Debug)]
+pub struct ServiceContextHandle(*mut ::std::os::raw::c_void);
+
+impl ServiceContextHandle {
+    /// Returns the raw OCI service context pointer.
+    pub fn as_ptr(&self) -> *mut ::std::os::raw::c_void {
+        self.0
+    }
+}
+
+/// Guard for a read only transaction started by `Connection::read_only()`. The transaction is
+/// committed, which ends it, when the guard is dropped.
+pub struct ReadOnlyTransaction<'a> {
+    /// The connection the transaction was started on.
+    conn: &'a Connection,
+}
+
+impl<'a> Drop for ReadOnlyTransaction<'a> {
+    fn drop(&mut self) {
+        let _ = self.conn.commit();
+    }
+}
+
+/// The result of `Connection::health_check()`.
+#[derive(Clone, Debug)]
+pub struct HealthReport {
+    /// Whether the ping and the server version lookup both succeeded.
+    healthy: bool,
+    /// The round trip time of the ping.
+    latency: Duration,
+    /// The version of the Oracle Database, empty if it could not be determined.
+    server_version: String,
+}
+
+impl HealthReport {
+    /// Get the `healthy` value.
+    pub fn healthy(&self) -> bool {
+        self.healthy
+    }
+
+    /// Get the `latency` value.
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+
+    /// Get the `server_version` value.
+    pub fn server_version(&self) -> &str {
+        &self.server_version
+    }
+}
+
+/// A handle for interrupting the operations running on a connection from another thread, returned
+/// by `Connection::cancel_token()`.
+#[derive(Clone)]
+pub struct CancelToken {
+    /// The connection reference held by the token, shared by all of its clones. Cancels are
+    /// serialized through the mutex.
+    conn: Arc<Mutex<CancelConn>>,
+}
+
+impl CancelToken {
+    /// Interrupts the operation currently running on the connection, which then fails with
+    /// ORA-01013. It may be called at any time, including while no operation is running.
+    pub fn cancel(&self) -> Result<()> {
+        let conn = match self.conn.lock() {
+            Ok(conn) => conn,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        try_dpi!(externs::dpiConn_breakExecution(conn.0),
+                 Ok(()),
+                 ErrorKind::Connection("dpiConn_breakExecution".to_string()))
+    }
+}
+
+/// The connection reference held by a `CancelToken`, released when the last clone of the token is
+/// dropped.
+struct CancelConn(*mut ODPIConn);
+
+unsafe impl Send for CancelConn {}
+
+impl Drop for CancelConn {
+    fn drop(&mut self) {
+        unsafe {
+            externs::dpiConn_release(self.0);
+        }
+    }
+}
+
+/// A connection pointer that can be moved to the keepalive thread. ODPI-C creates all handles in
+/// threaded mode, and the thread holds its own reference to the connection.
+struct KeepaliveConn(*mut ODPIConn);
+
+unsafe impl Send for KeepaliveConn {}
+
+/// Guard for the keepalive thread. Dropping the sender wakes the thread, which releases its
+/// connection reference and exits.
+struct Keepalive {
+    /// Dropped to signal the thread to stop.
+    stop: Option<Sender<()>>,
+    /// The keepalive thread.
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for Keepalive {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use test::{ConnResult, ContextResult, CONN, CONN_STR, CREDS, CTXT, ENC};
+    use common::version::Feature;
+    use super::{merge_sql, retry_resource_busy, ORA_RESOURCE_BUSY};
+    use common;
+    use connection::{ConnectDescriptor, Connection, ConnectionBuilder};
+    use context::Context;
+    use chrono::Timelike;
+    use data::{Data, FromData, OwnedData};
+    use error;
+    use odpi::flags;
+    use odpi::flags::ODPIDeqMode::*;
+    use odpi::flags::ODPIVisibility::*;
+    use odpi::flags::ODPIMessageDeliveryMode::*;
+    use odpi::flags::ODPINativeTypeNum::*;
+    use odpi::flags::ODPIOracleTypeNum::*;
+    use odpi::opaque::ODPIConn;
+    use odpi::structs::ODPISubscrMessage;
+    use rand::{self, Rng};
+    use std::ptr;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+
+    fn context_error_info(ctxt: &Context) {
+        use std::io::{self, Write};
+        let ctxt_error = ctxt.get_error();
+        writeln!(io::stderr(), "{}", ctxt_error).expect("badness");
+        assert!(false);
+    }
+
+    #[test]
+    fn create() {
+        match *CONN {
+            ConnResult::Ok(ref _conn) => assert!(true),
+            ConnResult::Err(ref _e) => assert!(false),
+        }
+    }
+
+    fn busy() -> error::Error {
+        let info = common::error::Info::new(ORA_RESOURCE_BUSY,
+                                            0,
+                                            "ORA-00054: resource busy".to_string(),
+                                            "dpiStmt_execute".to_string(),
+                                            "execute".to_string(),
+                                            "61000".to_string(),
+                                            false);
+        error::ErrorKind::ResourceBusy(info).into()
+    }
+
+    #[test]
+    fn retry_resource_busy_succeeds() {
+        let mut calls = 0;
+        let res = retry_resource_busy(3, Duration::from_millis(1), || {
+            calls += 1;
+            if calls < 3 { Err(busy()) } else { Ok(()) }
+        });
+
+        assert!(res.is_ok());
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn retry_resource_busy_gives_up() {
+        let mut calls = 0;
+        let res = retry_resource_busy(2, Duration::from_millis(1), || {
+            calls += 1;
+            Err(busy())
+        });
+
+        match res {
+            Err(error::Error(error::ErrorKind::ResourceBusy(ref info), _)) => {
+                assert_eq!(info.code(), ORA_RESOURCE_BUSY)
+            }
+            _ => assert!(false),
+        }
+        assert_eq!(calls, 3);
+
+        calls = 0;
+        let res = retry_resource_busy(2, Duration::from_millis(1), || {
+            calls += 1;
+            Err(error::ErrorKind::Connection("dpiStmt_execute".to_string()).into())
+        });
+        assert!(res.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn builder_debug_redacts_passwords() {
+        let mut builder = ConnectionBuilder::new();
+        builder.username("jozias").password("s3cret").new_password("n3w_s3cret");
+        let debug = format!("{:?}", builder);
+        assert!(debug.contains("jozias"));
+        assert!(!debug.contains("s3cret"));
+        assert!(debug.contains("<redacted>"));
+    }
+
+    #[test]
+    fn connect_descriptor_string() {
+        let mut descriptor = match ConnectDescriptor::new("db.example.com", "ORCL") {
+            Ok(descriptor) => descriptor,
+            Err(_) => return assert!(false),
+        };
+        assert_eq!(descriptor.to_string(),
+                   "(DESCRIPTION=(ADDRESS=(PROTOCOL=TCP)(HOST=db.example.com)(PORT=1521))\
+                    (CONNECT_DATA=(SERVICE_NAME=ORCL)))");
+
+        descriptor.port(1522).connect_timeout(5).transport_connect_timeout(2);
+        assert_eq!(descriptor.to_string(),
+                   "(DESCRIPTION=(CONNECT_TIMEOUT=5)(TRANSPORT_CONNECT_TIMEOUT=2)\
+                    (ADDRESS=(PROTOCOL=TCP)(HOST=db.example.com)(PORT=1522))\
+                    (CONNECT_DATA=(SERVICE_NAME=ORCL)))");
+
+        match ConnectDescriptor::new("db.example.com", "ORCL)(SERVER=DEDICATED") {
+            Err(error::Error(error::ErrorKind::InvalidDescriptorValue(ref value), _)) => {
+                assert_eq!(value, "ORCL)(SERVER=DEDICATED")
+            }
+            _ => assert!(false),
+        }
+        assert!(ConnectDescriptor::new("host=evil", "ORCL").is_err());
+    }
+
+    #[test]
+    fn connect_timeout() {
+        let ctxt = match *CTXT {
+            ContextResult::Ok(ref ctxt) => ctxt,
+            ContextResult::Err(ref _e) => return assert!(false),
+        };
+        // A non-routable address, so that the connection attempt is never answered.
+        let mut descriptor = match ConnectDescriptor::new("10.255.255.1", "ORCL") {
+            Ok(descriptor) => descriptor,
+            Err(_) => return assert!(false),
+        };
+        descriptor.connect_timeout(2).transport_connect_timeout(2);
+
+        let start = Instant::now();
+        let res = ConnectionBuilder::new()
+            .username(&CREDS[0])
+            .password(&CREDS[1])
+            .connect_descriptor(&descriptor)
+            .build(ctxt);
+        assert!(res.is_err());
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn merge_sql_quotes() {
+        match merge_sql("hr.keyed", &["id"], &["name", "Mixed Case"]) {
+            Ok(sql) => {
+                assert_eq!(sql,
+                           "merge into hr.keyed t using (select :1 id, :2 name, :3 \"Mixed Case\" \
+                            from dual) s on (t.id = s.id) when matched then update set t.name = \
+                            s.name, t.\"Mixed Case\" = s.\"Mixed Case\" when not matched then \
+                            insert (id, name, \"Mixed Case\") values (s.id, s.name, \
+                            s.\"Mixed Case\")")
+            }
+            Err(e) => ::test::error_info(e),
+        }
+
+        match merge_sql("keyed", &["id"], &[]) {
+            Ok(sql) => {
+                assert_eq!(sql,
+                           "merge into keyed t using (select :1 id from dual) s on (t.id = s.id) \
+                            when not matched then insert (id) values (s.id)")
+            }
+            Err(e) => ::test::error_info(e),
+        }
+
+        match merge_sql("keyed", &[], &["name"]) {
+            Err(error::Error(error::ErrorKind::MissingKeyColumns, _)) => assert!(true),
+            _ => assert!(false),
+        }
+        match merge_sql("keyed", &["id\n"], &["name"]) {
+            Err(error::Error(error::ErrorKind::InvalidIdentifier(_), _)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn add_ref_release() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+
+        match conn.add_ref() {
+            Ok(_) => {
+                match conn.release() {
+                    Ok(_) => assert!(true),
+                    Err(_) => assert!(false),
+                }
+            }
+            Err(_) => assert!(false),
+        }
+    }
+
+    fn drop_releases_res() -> error::Result<()> {
+        {
+            // Every reference has already been released, so the drop must not release again.
+            let conn = ::test::connect()?;
+            conn.add_ref()?;
+            conn.release()?;
+            conn.release()?;
+        }
+        {
+            // Closing does not release the reference, which is released by the drop instead.
+            let conn = ::test::connect()?;
+            conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        }
+        {
+            let conn = ::test::connect()?;
+            conn.add_ref()?;
+            conn.ping()?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn drop_releases() {
+        match drop_releases_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn break_execution() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+
+        match conn.break_execution() {
+            Ok(_) => assert!(true),
+            Err(_) => assert!(false),
+        }
+    }
+
+    #[test]
+    fn begin_tx_prepare_commit() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
 
         let mut rng = rand::thread_rng();
         match conn.begin_distrib_trans(rng.gen::<i64>(), "One", "Two") {
@@ -866,11 +2300,24 @@ mod test {
         };
 
         match conn.get_edition() {
-            Ok(edition) => assert!(edition == ""),
+            Ok(edition) => assert!(edition.is_none()),
             Err(_) => assert!(false),
         }
     }
 
+    #[test]
+    fn get_handle() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+
+        match conn.get_handle() {
+            Ok(handle) => assert!(!handle.as_ptr().is_null()),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
     #[test]
     fn get_encoding_info() {
         let conn = match *CONN {
@@ -889,6 +2336,47 @@ mod test {
         }
     }
 
+    #[test]
+    fn bytes_for_charset() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+        let multibyte = "żółw ☃";
+
+        assert_eq!(conn.bytes_for_charset(multibyte), multibyte.as_bytes().to_vec());
+
+        let stmt = match conn.prepare_stmt(Some("select :val from dual"), None, false) {
+            Ok(stmt) => stmt,
+            Err(e) => return ::test::error_info(e),
+        };
+        match stmt.bind_data_by_name(":val", &OwnedData::String(multibyte.to_string())) {
+            Ok(_) => assert!(true),
+            Err(e) => return ::test::error_info(e),
+        }
+        match stmt.execute(flags::EXEC_DEFAULT) {
+            Ok(_) => assert!(true),
+            Err(e) => return ::test::error_info(e),
+        }
+        match stmt.fetch_row() {
+            Ok(Some(row)) => assert_eq!(row.get::<String>(1).ok(), Some(multibyte.to_string())),
+            Ok(None) => assert!(false),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    fn get_external_name_error() {
+        let conn: Connection = ptr::null_mut::<ODPIConn>().into();
+
+        match conn.get_external_name() {
+            Err(error::Error(error::ErrorKind::Connection(ref fn_name), _)) => {
+                assert_eq!(fn_name, "dpiConn_getExternalName")
+            }
+            _ => assert!(false),
+        }
+    }
+
     #[test]
     fn set_get_external_name() {
         let conn = match *CONN {
@@ -958,29 +2446,171 @@ mod test {
     }
 
     #[test]
-    fn set_get_statement_cache() {
+    fn prepare_uncached() {
         let conn = match *CONN {
             ConnResult::Ok(ref conn) => conn,
             ConnResult::Err(ref _e) => return assert!(false),
         };
 
-        match conn.set_statement_cache_size(40) {
-            Ok(_) => {
-                match conn.get_statement_cache_size() {
-                    Ok(cache_size) => assert!(cache_size == 40),
-                    Err(_) => assert!(false),
+        match conn.prepare_uncached("select 'uncached' from dual") {
+            Ok(stmt) => {
+                match stmt.execute(flags::EXEC_DEFAULT) {
+                    Ok(_) => assert!(true),
+                    Err(e) => return ::test::error_info(e),
+                }
+                match stmt.close(Some("uncached")) {
+                    Ok(_) => assert!(true),
+                    Err(e) => return ::test::error_info(e),
                 }
             }
-            Err(_) => assert!(false),
+            Err(e) => return ::test::error_info(e),
         }
-    }
 
-    #[test]
-    fn new_deq_options() {
-        let conn = match *CONN {
-            ConnResult::Ok(ref conn) => conn,
-            ConnResult::Err(ref _e) => return assert!(false),
-        };
+        // A statement can only be prepared from its tag alone if it is in the statement cache.
+        match conn.prepare_stmt(None, Some("uncached"), false) {
+            Ok(_) => assert!(false),
+            Err(_) => assert!(true),
+        }
+
+        match conn.prepare_stmt(Some("select 'cached' from dual"), None, false) {
+            Ok(stmt) => {
+                match stmt.execute(flags::EXEC_DEFAULT) {
+                    Ok(_) => assert!(true),
+                    Err(e) => return ::test::error_info(e),
+                }
+                match stmt.close(Some("cached")) {
+                    Ok(_) => assert!(true),
+                    Err(e) => return ::test::error_info(e),
+                }
+            }
+            Err(e) => return ::test::error_info(e),
+        }
+
+        match conn.prepare_stmt(None, Some("cached"), false) {
+            Ok(stmt) => {
+                match stmt.close(None) {
+                    Ok(_) => assert!(true),
+                    Err(e) => ::test::error_info(e),
+                }
+            }
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    fn open_cursor_count() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+        let sql = "select * from username where username = 'jozias'";
+
+        let run = || -> error::Result<()> {
+            let stmt = conn.prepare_stmt(Some(sql), None, false)?;
+            stmt.execute(flags::EXEC_DEFAULT)?;
+            stmt.fetch()?;
+            stmt.close(None)
+        };
+
+        match run() {
+            Ok(_) => assert!(true),
+            Err(e) => return ::test::error_info(e),
+        }
+        let before = match conn.open_cursor_count() {
+            Ok(count) => count,
+            Err(e) => return ::test::error_info(e),
+        };
+
+        for _ in 0..50 {
+            match run() {
+                Ok(_) => assert!(true),
+                Err(e) => return ::test::error_info(e),
+            }
+        }
+
+        match conn.open_cursor_count() {
+            Ok(after) => assert_eq!(after, before),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    fn query_scalar() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+
+        match conn.query_scalar::<i64>("select max(id) from username where 1=0") {
+            Ok(max) => assert_eq!(max, None),
+            Err(e) => ::test::error_info(e),
+        }
+        match conn.query_scalar::<i64>("select id from username where 1=0") {
+            Ok(id) => assert_eq!(id, None),
+            Err(e) => ::test::error_info(e),
+        }
+        match conn.query_scalar::<i64>("select 42 from dual") {
+            Ok(value) => assert_eq!(value, Some(42)),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    fn query_cached() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+        let sql = "select username from username where username = 'jozias'";
+        let ttl = Duration::from_secs(60);
+        let loads = conn.query_cache_loads.get();
+
+        let first = match conn.query_cached("jozias", ttl, sql) {
+            Ok(rows) => rows,
+            Err(e) => return ::test::error_info(e),
+        };
+        assert_eq!(conn.query_cache_loads.get(), loads + 1);
+        assert!(!first.is_empty());
+        assert_eq!(first[0][0], OwnedData::String("jozias".to_string()));
+
+        match conn.query_cached("jozias", ttl, sql) {
+            Ok(second) => assert_eq!(second, first),
+            Err(e) => return ::test::error_info(e),
+        }
+        assert_eq!(conn.query_cache_loads.get(), loads + 1);
+
+        conn.invalidate_cached("jozias");
+        match conn.query_cached("jozias", ttl, sql) {
+            Ok(third) => assert_eq!(third, first),
+            Err(e) => return ::test::error_info(e),
+        }
+        assert_eq!(conn.query_cache_loads.get(), loads + 2);
+    }
+
+    #[test]
+    fn set_get_statement_cache() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+
+        match conn.set_statement_cache_size(40) {
+            Ok(_) => {
+                match conn.get_statement_cache_size() {
+                    Ok(cache_size) => assert!(cache_size == 40),
+                    Err(_) => assert!(false),
+                }
+            }
+            Err(_) => assert!(false),
+        }
+    }
+
+    #[test]
+    fn new_deq_options() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
 
         match conn.new_deq_options() {
             Ok(deq_opts) => {
@@ -1133,6 +2763,819 @@ mod test {
         }
     }
 
+    fn new_var_flags_res() -> error::Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+
+        // A size given in bytes is not scaled by the maximum bytes per character.
+        let bytes = conn.new_var(Varchar, Bytes, 5, 256, true, false)?;
+        assert_eq!(bytes.get_size_in_bytes()?, 256);
+        let chars = conn.new_var(Varchar, Bytes, 5, 256, false, false)?;
+        assert!(chars.get_size_in_bytes()? > 256);
+
+        // An array starts out empty, while any other variable always holds max_array_size
+        // elements.
+        let array = conn.new_var(Varchar, Bytes, 5, 256, false, true)?;
+        assert_eq!(array.get_num_elements_in_array()?, 0);
+        array.set_num_elements_in_array(3)?;
+        assert_eq!(array.get_num_elements_in_array()?, 3);
+        assert_eq!(chars.get_num_elements_in_array()?, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn new_var_flags() {
+        match new_var_flags_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    #[ignore]
+    fn enable_keepalive() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+
+        match conn.enable_keepalive(Duration::from_secs(1)) {
+            Ok(_) => assert!(true),
+            Err(e) => return ::test::error_info(e),
+        }
+
+        thread::sleep(Duration::from_millis(3500));
+
+        match conn.ping() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn modify_res() -> error::Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+        let mut rng = rand::thread_rng();
+        let marker = format!("modify_{}", rng.gen::<u32>());
+
+        for id in 0..3i64 {
+            let count = conn.modify("insert into username values (:id, :username)",
+                                    &[&id, &marker])?;
+            assert_eq!(count, 1);
+        }
+        assert_eq!(conn.modify("delete from username where username = :username and id > :id",
+                               &[&marker, &0i64])?,
+                   2);
+        assert_eq!(conn.modify("delete from username where username = :username", &[&marker])?,
+                   1);
+        match conn.modify("select 1 from dual", &[]) {
+            Err(error::Error(error::ErrorKind::UnexpectedQuery, _)) => assert!(true),
+            _ => assert!(false),
+        }
+        conn.rollback()?;
+        Ok(())
+    }
+
+    #[test]
+    fn modify() {
+        match modify_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn no_data_found_res() -> error::Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+
+        match conn.modify("declare n number; begin select 1 into n from dual where 1 = 0; end;",
+                          &[]) {
+            Err(error::Error(error::ErrorKind::PlsqlNoDataFound(ref info), _)) => {
+                assert_eq!(info.code(), 1403)
+            }
+            _ => assert!(false),
+        }
+        match conn.run_script("begin raise no_data_found; end;\n/\n") {
+            Err(error::Error(error::ErrorKind::PlsqlNoDataFound(ref info), _)) => {
+                assert_eq!(info.code(), 1403)
+            }
+            _ => assert!(false),
+        }
+        assert_eq!(conn.query_scalar::<i64>("select 1 from dual where 1 = 0")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn no_data_found() {
+        match no_data_found_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn classify_after_context_dropped_res() -> error::Result<()> {
+        let conn = {
+            let ctxt = Context::create()?;
+            Connection::create(&ctxt,
+                               Some(&CREDS[0]),
+                               Some(&CREDS[1]),
+                               Some(CONN_STR),
+                               None,
+                               None)?
+        };
+
+        // The connection keeps the ODPI-C context alive, so its errors can still be read.
+        match conn.modify("begin raise no_data_found; end;", &[]) {
+            Err(error::Error(error::ErrorKind::PlsqlNoDataFound(ref info), _)) => {
+                assert_eq!(info.code(), 1403)
+            }
+            _ => assert!(false),
+        }
+        conn.release()
+    }
+
+    #[test]
+    fn classify_after_context_dropped() {
+        match classify_after_context_dropped_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn query_row_res() -> error::Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        ::test::ensure_test_schema()?;
+        let sql = "select id, username from username where id = :1";
+
+        let row = conn.query_row::<(i64, String)>(sql, &[&1i64])?;
+        assert_eq!(row, Some((1, "jozias".to_string())));
+        assert!(conn.query_row::<(i64, String)>(sql, &[&-1i64])?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn query_row() {
+        match query_row_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn query_in_res() -> error::Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        ::test::ensure_test_schema()?;
+        let ids: Vec<i64> = (1..501).collect();
+
+        let mut found = Vec::new();
+        for row in conn.query_in("select column_value from table(:1)", &ids)? {
+            found.push(row?.get::<i64>(1)?);
+        }
+        found.sort();
+        assert_eq!(found, ids);
+
+        let mut rows = conn.query_in("select id, username from username \
+                                      where username = 'jozias' \
+                                      and id in (select column_value from table(:1))",
+                                     &ids)?;
+        let row = rows.next().expect("missing row")?;
+        assert_eq!(row.get::<String>(2)?, "jozias");
+        assert!(rows.next().is_none());
+
+        match conn.query_in("select column_value from table(:1)", &[vec![1u8]]) {
+            Err(error::Error(error::ErrorKind::TypeMismatch(..), _)) => assert!(true),
+            _ => assert!(false),
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn query_in() {
+        match query_in_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn new_object_var_res() -> error::Result<()> {
+        // DDL commits, so it must not share a connection with other tests.
+        let conn = ::test::connect()?;
+        conn.execute_sql("create or replace package mimir_rec_pkg as \
+                            type rec_t is record (id number, name varchar2(30)); \
+                            type rec_tab_t is table of rec_t index by binary_integer; \
+                            function describe(recs rec_tab_t) return varchar2; \
+                          end;")?;
+        conn.execute_sql("create or replace package body mimir_rec_pkg as \
+                            function describe(recs rec_tab_t) return varchar2 is \
+                              res varchar2(200); \
+                            begin \
+                              for i in 1 .. recs.count loop \
+                                res := res || recs(i).id || '=' || recs(i).name || ';'; \
+                              end loop; \
+                              return res; \
+                            end; \
+                          end;")?;
+
+        let tab_type = conn.get_object_type("MIMIR_REC_PKG.REC_TAB_T")?;
+        let rec_type = conn.get_object_type("MIMIR_REC_PKG.REC_T")?;
+        let attrs = rec_type.get_attributes()?;
+        assert_eq!(attrs.len(), 2);
+
+        let recs = tab_type.create_object()?;
+        for &(id, name) in &[(1, "jozias"), (2, "mimir")] {
+            let rec = rec_type.create_object()?;
+            rec.set_attribute_value(&attrs[0], &OwnedData::Int64(id))?;
+            rec.set_attribute_value(&attrs[1], &OwnedData::String(name.to_string()))?;
+            recs.append_object(&rec)?;
+            rec.release()?;
+        }
+        assert_eq!(recs.get_size()?, 2);
+
+        let recs_var = conn.new_object_var(&tab_type, 1, false)?;
+        recs_var.set_from_object(0, &recs)?;
+        let res_var = conn.new_var(Varchar, Bytes, 1, 200, true, false)?;
+
+        let stmt = conn.prepare_stmt(Some("begin :res := mimir_rec_pkg.describe(:recs); end;"),
+                          None,
+                          false)?;
+        stmt.bind_by_name(":recs", recs_var)?;
+        stmt.bind_by_name(":res", res_var)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        assert_eq!(stmt.get_out::<String, _>(":res")?, "1=jozias;2=mimir;");
+
+        stmt.close(None)?;
+        recs.release()?;
+        for attr in &attrs {
+            attr.release()?;
+        }
+        rec_type.release()?;
+        tab_type.release()?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn new_object_var() {
+        match new_object_var_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn change_password_and_reconnect_res() -> error::Result<()> {
+        let ctxt = ::test::context()?;
+        let temp_password = "mimir_Temp_01";
+
+        let conn = ::test::connect()?;
+        let conn = match conn.change_password_and_reconnect(ctxt,
+                                                            CONN_STR,
+                                                            &CREDS[0],
+                                                            "not the password",
+                                                            temp_password,
+                                                            None,
+                                                            None) {
+            Ok(_) => {
+                return Err(error::ErrorKind::Connection("change_password_and_reconnect"
+                                                            .to_string())
+                                   .into())
+            }
+            Err((conn, _)) => conn,
+        };
+        conn.ping()?;
+        let conn = conn.change_password_and_reconnect(ctxt,
+                                                      CONN_STR,
+                                                      &CREDS[0],
+                                                      &CREDS[1],
+                                                      temp_password,
+                                                      None,
+                                                      None)
+            .map_err(|(_, e)| e)?;
+        conn.ping()?;
+        let conn = conn.change_password_and_reconnect(ctxt,
+                                                      CONN_STR,
+                                                      &CREDS[0],
+                                                      temp_password,
+                                                      &CREDS[1],
+                                                      None,
+                                                      None)
+            .map_err(|(_, e)| e)?;
+        conn.ping()?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn change_password_and_reconnect() {
+        match change_password_and_reconnect_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn cancel_token_res() -> error::Result<()> {
+        let ctxt = ::test::context()?;
+        let conn = ::test::connect()?;
+        let token = conn.cancel_token()?;
+
+        let canceller = thread::spawn(move || {
+                                          thread::sleep(Duration::from_secs(1));
+                                          token.cancel()
+                                      });
+        let res = conn.query("select count(*) from all_objects a, all_objects b, all_objects c");
+        assert!(res.is_err());
+        assert_eq!(ctxt.get_error().code(), 1013);
+        canceller.join().expect("canceller thread panicked")?;
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn cancel_token() {
+        match cancel_token_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn rollback_on_drop_res() -> error::Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+        let mut rng = rand::thread_rng();
+        let marker = format!("dropped_{}", rng.gen::<u32>());
+
+        assert!(!conn.in_transaction()?);
+        conn.modify("insert into username values (1, :username)", &[&marker])?;
+        assert!(conn.in_transaction()?);
+        drop(conn);
+
+        let conn = ::test::connect()?;
+        let count = conn.modify("delete from username where username = :username", &[&marker])?;
+        assert_eq!(count, 0);
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn rollback_on_drop() {
+        match rollback_on_drop_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn session_timezone_res() -> error::Result<()> {
+        let conn = ::test::connect()?;
+        conn.execute_sql("alter session set time_zone = '+02:00'")?;
+        let tz = conn.session_timezone()?;
+        assert_eq!(tz, "+02:00");
+
+        let stmt = conn.prepare_stmt(Some("select cast(timestamp '2017-06-01 12:34:56 +00:00' \
+                                           as timestamp with local time zone) from dual"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        let (found, _) = stmt.fetch()?;
+        assert!(found);
+        let (_, data) = stmt.get_query_value(1)?;
+        let local = Data::from(data).as_local_timestamp(&tz)?;
+        assert_eq!(local.hour(), 14);
+        assert_eq!(local.naive_utc().hour(), 12);
+        stmt.close(None)?;
+
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn session_timezone() {
+        match session_timezone_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn set_default_fetch_array_size_res() -> error::Result<()> {
+        let conn = ::test::connect()?;
+        let before = conn.prepare_stmt(Some("select 1 from dual"), None, false)?;
+
+        conn.set_default_fetch_array_size(200);
+        let stmt = conn.prepare_stmt(Some("select 1 from dual"), None, false)?;
+        assert_eq!(stmt.get_fetch_array_size()?, 200);
+        assert_ne!(before.get_fetch_array_size()?, 200);
+
+        stmt.close(None)?;
+        before.close(None)?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn set_default_fetch_array_size() {
+        match set_default_fetch_array_size_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn run_script_res() -> error::Result<()> {
+        let conn = ::test::connect()?;
+        conn.run_script("begin\n\
+                           execute immediate 'drop table mimir_script';\n\
+                         exception\n\
+                           when others then null;\n\
+                         end;\n\
+                         /\n\
+                         create table mimir_script (id number, name varchar2(30));\n\
+                         -- the literals below contain semicolons; they must not split\n\
+                         insert into mimir_script values (1, 'a;b');\n\
+                         insert into mimir_script values (2, 'it''s;');\n\
+                         commit;\n")?;
+
+        let sql = "select id, name from mimir_script where id = :1";
+        assert_eq!(conn.query_row::<(i64, String)>(sql, &[&1i64])?,
+                   Some((1, "a;b".to_string())));
+        assert_eq!(conn.query_row::<(i64, String)>(sql, &[&2i64])?,
+                   Some((2, "it's;".to_string())));
+        assert_eq!(conn.query_scalar::<i64>("select count(*) from mimir_script")?,
+                   Some(2));
+
+        conn.run_script("drop table mimir_script;")?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn run_script() {
+        match run_script_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn unsubscribe_res() -> error::Result<()> {
+        let ctxt = ::test::context()?;
+        let mut ccp = ctxt.init_common_create_params()?;
+        ccp.set_encoding(ENC.as_ptr());
+        ccp.set_nchar_encoding(ENC.as_ptr());
+        ccp.set_create_mode(flags::DPI_MODE_CREATE_EVENTS);
+        let conn = Connection::create(ctxt,
+                                      Some(&CREDS[0]),
+                                      Some(&CREDS[1]),
+                                      Some(CONN_STR),
+                                      Some(ccp),
+                                      None)?;
+
+        let mut scp = ctxt.init_subscr_create_params()?;
+        scp.set_name("unsubscribe");
+        scp.set_qos(flags::DPI_SUBSCR_QOS_QUERY);
+        scp.set_callback(Some(subscr_callback));
+        let subscription = conn.new_subscription(scp)?;
+
+        let stmt = subscription.prepare_stmt("select id from username")?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        stmt.close(None)?;
+
+        conn.unsubscribe(&subscription)?;
+        subscription.release()?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn unsubscribe() {
+        match unsubscribe_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn upsert_res() -> error::Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+        let mut rng = rand::thread_rng();
+        let id = rng.gen::<i32>().abs() as i64;
+        let sql = "select name from keyed where id = :1";
+
+        assert_eq!(conn.upsert("keyed", &["id"], &["name"], &[&id, &"first"])?, 1);
+        assert_eq!(conn.query_row::<(String,)>(sql, &[&id])?,
+                   Some(("first".to_string(),)));
+        assert_eq!(conn.upsert("keyed", &["id"], &["name"], &[&id, &"second"])?, 1);
+        assert_eq!(conn.query_row::<(String,)>(sql, &[&id])?,
+                   Some(("second".to_string(),)));
+        assert_eq!(conn.query_scalar::<i64>(&format!("select count(*) from keyed where id = {}",
+                                                     id))?,
+                   Some(1));
+
+        match conn.upsert("keyed", &["id"], &["name"], &[&id]) {
+            Err(error::Error(error::ErrorKind::ValueCount(2, 1), _)) => assert!(true),
+            _ => assert!(false),
+        }
+        conn.rollback()?;
+        Ok(())
+    }
+
+    #[test]
+    fn upsert() {
+        match upsert_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn set_metrics_sink_res() -> error::Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+        let metrics = Arc::new(Mutex::new(Vec::new()));
+        let sink_metrics = metrics.clone();
+        conn.set_metrics_sink(Box::new(move |metric| {
+                                           sink_metrics.lock().expect("badness").push(metric)
+                                       }));
+
+        let sql = "select id, username from username";
+        let rows = conn.query(sql)?;
+        {
+            let metrics = metrics.lock().expect("badness");
+            assert_eq!(metrics.len(), 1);
+            assert_eq!(metrics[0].sql(), sql);
+            assert!(metrics[0].execute_time() > Duration::new(0, 0));
+            assert_eq!(metrics[0].rows(), rows.len() as u64);
+        }
+
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn set_metrics_sink() {
+        match set_metrics_sink_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn set_nls_parameter_res() -> error::Result<()> {
+        let conn = ::test::connect()?;
+
+        conn.set_nls_parameter("nls_date_format", "YYYY\"x\"MM")?;
+        let formatted = conn.query_scalar::<String>("select to_char(date '2017-06-01') \
+                                                     from dual")?;
+        assert_eq!(formatted, Some("2017x06".to_string()));
+        match conn.set_nls_parameter("nls_date_format = 'x'; --\n", "YYYY") {
+            Err(error::Error(error::ErrorKind::InvalidIdentifier(_), _)) => assert!(true),
+            _ => assert!(false),
+        }
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn set_nls_parameter() {
+        match set_nls_parameter_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn set_edition_res() -> error::Result<()> {
+        let conn = ::test::connect()?;
+
+        conn.set_edition("ora$base")?;
+        assert_eq!(conn.get_edition()?, Some("ORA$BASE".to_string()));
+        match conn.set_edition("bad\nedition") {
+            Err(error::Error(error::ErrorKind::InvalidIdentifier(_), _)) => assert!(true),
+            _ => assert!(false),
+        }
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn set_edition() {
+        match set_edition_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn read_only_res() -> error::Result<()> {
+        let ctxt = ::test::context()?;
+        let conn = ::test::connect()?;
+
+        {
+            let _txn = conn.read_only()?;
+            let rows = conn.query("select username from username where username = 'jozias'")?;
+            assert!(!rows.is_empty());
+
+            let stmt = conn.prepare_stmt(Some("insert into username values (-1, 'read_only')"),
+                              None,
+                              false)?;
+            assert!(stmt.execute(flags::EXEC_DEFAULT).is_err());
+            assert_eq!(ctxt.get_error().code(), 1456);
+            stmt.close(None)?;
+        }
+
+        let stmt = conn.prepare_stmt(Some("insert into username values (-1, 'read_only')"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        conn.rollback()?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn read_only() {
+        match read_only_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn savepoint_res() -> error::Result<()> {
+        let conn = ::test::connect()?;
+        let mut rng = rand::thread_rng();
+        let a = rng.gen::<i32>().abs() as i64;
+        let b = a + 1;
+
+        let insert = |id: i64| -> error::Result<()> {
+            let stmt = conn.prepare_stmt(Some("insert into username values (:id, 'savepoint')"),
+                              None,
+                              false)?;
+            stmt.bind_data_by_name(":id", &OwnedData::Int64(id))?;
+            stmt.execute(flags::EXEC_DEFAULT)?;
+            stmt.close(None)
+        };
+
+        insert(a)?;
+        conn.savepoint("after_a")?;
+        insert(b)?;
+        conn.rollback_to("after_a")?;
+        conn.commit()?;
+
+        let rows = conn.query(&format!("select id from username where id in ({}, {})", a, b))?;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(i64::from_data(&rows[0][0])?, a);
+
+        conn.savepoint("after b")?;
+        match conn.savepoint("x\n; drop table username") {
+            Err(error::Error(error::ErrorKind::InvalidIdentifier(_), _)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        let cleanup = conn.prepare_stmt(Some("delete from username where id = :id"), None, false)?;
+        cleanup.bind_data_by_name(":id", &OwnedData::Int64(a))?;
+        cleanup.execute(flags::EXEC_DEFAULT)?;
+        conn.commit()?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn savepoint() {
+        match savepoint_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn connection_builder_edition_res() -> error::Result<()> {
+        let ctxt = ::test::context()?;
+
+        let conn = ConnectionBuilder::new()
+            .username(&CREDS[0])
+            .password(&CREDS[1])
+            .CONN_STR(CONN_STR)
+            .edition("ORA$BASE")
+            .build(ctxt)?;
+        assert_eq!(conn.get_edition()?, Some("ORA$BASE".to_string()));
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn connection_builder_edition() {
+        match connection_builder_edition_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn connection_builder_encoding_res() -> error::Result<()> {
+        let ctxt = ::test::context()?;
+
+        let conn = ConnectionBuilder::new()
+            .username(&CREDS[0])
+            .password(&CREDS[1])
+            .CONN_STR(CONN_STR)
+            .encoding("UTF-8")
+            .nchar_encoding("UTF-8")
+            .build(ctxt)?;
+        let encoding_info = conn.get_encoding_info()?;
+        assert_eq!(encoding_info.encoding(), "UTF-8");
+        assert_eq!(encoding_info.nchar_encoding(), "UTF-8");
+        assert_eq!(conn.query_scalar::<i64>("select 1 from dual")?, Some(1));
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+
+        match ConnectionBuilder::new().encoding("UTF\0-8").build(ctxt) {
+            Err(error::Error(error::ErrorKind::Nul(_), _)) => assert!(true),
+            _ => assert!(false),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn connection_builder_encoding() {
+        match connection_builder_encoding_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    fn connection_builder_conn_params() {
+        let ctxt = match *CTXT {
+            ContextResult::Ok(ref ctxt) => ctxt,
+            ContextResult::Err(ref _e) => return assert!(false),
+        };
+
+        let mut builder = ConnectionBuilder::new();
+        builder.auth_mode(flags::DPI_MODE_AUTH_SYSDBA)
+            .connection_class("MIMIR")
+            .new_password("changed")
+            .external_auth(true);
+        let ccp = builder.conn_create_params(ctxt).expect("badness").expect("badness");
+        assert_eq!(ccp.get_auth_mode(), flags::DPI_MODE_AUTH_SYSDBA);
+        assert_eq!(ccp.get_connection_class(), "MIMIR");
+        assert_eq!(ccp.get_new_password(), "changed");
+        assert_eq!(ccp.get_external_auth(), 1);
+    }
+
+    #[test]
+    fn connection_builder_drcp_params() {
+        let ctxt = match *CTXT {
+            ContextResult::Ok(ref ctxt) => ctxt,
+            ContextResult::Err(ref _e) => return assert!(false),
+        };
+
+        let mut builder = ConnectionBuilder::new();
+        assert!(builder.conn_create_params(ctxt).expect("badness").is_none());
+
+        builder.drcp("MIMIR", flags::DPI_PURITY_SELF);
+        let ccp = builder.conn_create_params(ctxt).expect("badness").expect("badness");
+        assert_eq!(ccp.get_connection_class(), "MIMIR");
+        assert_eq!(ccp.get_purity(), flags::DPI_PURITY_SELF);
+        assert_eq!(ccp.get_purity().bits(), 2);
+
+        builder.drcp("MIMIR", flags::DPI_PURITY_NEW);
+        let ccp = builder.conn_create_params(ctxt).expect("badness").expect("badness");
+        assert_eq!(ccp.get_purity(), flags::DPI_PURITY_NEW);
+        assert_eq!(ccp.get_purity().bits(), 1);
+    }
+
+    fn connection_builder_drcp_res() -> error::Result<()> {
+        let ctxt = ::test::context()?;
+
+        let conn = ConnectionBuilder::new()
+            .username(&CREDS[0])
+            .password(&CREDS[1])
+            .CONN_STR(&format!("{}:pooled", CONN_STR))
+            .drcp("MIMIR", flags::DPI_PURITY_SELF)
+            .build(ctxt)?;
+        conn.ping()?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn connection_builder_drcp() {
+        match connection_builder_drcp_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
     #[test]
     fn ping() {
         let conn = match *CONN {
@@ -1198,6 +3641,19 @@ mod test {
         }
     }
 
+    #[test]
+    fn health_check() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+
+        let report = conn.health_check();
+        assert!(report.healthy());
+        assert!(!report.server_version().is_empty());
+        assert!(report.latency() < Duration::from_secs(30));
+    }
+
     #[test]
     fn set_db_op() {
         let conn = match *CONN {
@@ -1223,4 +3679,37 @@ mod test {
             Err(_e) => assert!(false),
         }
     }
+
+    #[test]
+    fn supports_feature() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+
+        match conn.supports_feature(Feature::ImplicitResults) {
+            Ok(supported) => assert!(supported),
+            Err(e) => ::test::error_info(e),
+        }
+        match conn.supports_feature(Feature::JsonType) {
+            Ok(supported) => assert!(!supported),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    fn set_tracing() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+
+        match conn.set_tracing(Some("module"),
+                               Some("action"),
+                               Some("client_info"),
+                               Some("client_id")) {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
 }
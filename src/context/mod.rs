@@ -18,15 +18,35 @@ use odpi::opaque::ODPIContext;
 use odpi::structs::{ODPICommonCreateParams, ODPIConnCreateParams, ODPIErrorInfo,
                     ODPIPoolCreateParams, ODPISubscrCreateParams, ODPIVersionInfo};
 use slog::Logger;
+use std::ffi::CString;
 use std::ptr;
+use std::sync::Arc;
 use util::ODPIStr;
 
 pub mod params;
 
 use self::params::{CommonCreate, ConnCreate, PoolCreate, SubscrCreate};
 
+/// Returns the major and minor version of ODPI-C the crate was compiled against, which is the
+/// version requested when a context is created. ODPI-C is compiled into the crate, so there is no
+/// separately loaded ODPI-C library whose version could differ. The version of the Oracle Client
+/// libraries loaded at runtime is available from `Context::get_client_version()`.
+pub fn odpi_version() -> (u32, u32) {
+    (DPI_MAJOR_VERSION, DPI_MINOR_VERSION)
+}
+
 /// This structure represents the context in which all activity in the library takes place.
 pub struct Context {
+    /// The ODPI-C context, shared with the connections and pools created from this context.
+    handle: Arc<Handle>,
+    /// The encoding applied to CHAR and NCHAR data by `init_common_create_params()`, if set.
+    default_encoding: Option<CString>,
+}
+
+/// Owns the ODPI-C dpiContext. Connections and pools read the details of their errors from the
+/// context they were created with, so they share it with the `Context`, and the ODPI-C context is
+/// only destroyed once the last of them is dropped.
+pub(crate) struct Handle {
     /// A pointer the the ODPI-C dpiContext struct.
     context: *mut ODPIContext,
     /// Optional stdout logger.
@@ -35,6 +55,28 @@ pub struct Context {
     stderr: Option<Logger>,
 }
 
+impl Handle {
+    /// Returns error information for the last error that was raised by the library on the calling
+    /// thread, as `Context::get_error()` does.
+    pub(crate) fn get_error(&self) -> error::Info {
+        let mut error_info: ODPIErrorInfo = Default::default();
+        unsafe {
+            externs::dpiContext_getError(self.handle.context, &mut error_info);
+            error_info.into()
+        }
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        if unsafe { externs::dpiContext_destroy(self.context) } == DPI_FAILURE {
+            try_error!(self.stderr, "Failed to destroy context");
+        } else {
+            try_info!(self.stdout, "Successfully destroyed context");
+        }
+    }
+}
+
 impl Context {
     /// Create a new `Context` struct.
     pub fn create() -> Result<Context> {
@@ -46,9 +88,12 @@ impl Context {
                                             &mut ctxt,
                                             &mut err),
                  Ok(Context {
-                        context: ctxt,
-                        stdout: None,
-                        stderr: None,
+                        handle: Arc::new(Handle {
+                                             context: ctxt,
+                                             stdout: None,
+                                             stderr: None,
+                                         }),
+                        default_encoding: None,
                     }),
                  ErrorKind::Context("dpiContext_create".to_string()))
     }
@@ -56,13 +101,18 @@ impl Context {
     /// Get the pointer to the inner ODPI struct.
     #[doc(hidden)]
     pub fn inner(&self) -> *mut ODPIContext {
-        self.context
+        self.handle.context
+    }
+
+    /// Get a reference to the ODPI-C context that keeps it alive for as long as it is held.
+    pub(crate) fn handle(&self) -> Arc<Handle> {
+        self.handle.clone()
     }
 
     /// Return information about the version of the Oracle Client that is being used.
     pub fn get_client_version(&self) -> Result<version::Info> {
         let mut version_info: ODPIVersionInfo = Default::default();
-        try_dpi!(externs::dpiContext_getClientVersion(self.context, &mut version_info),
+        try_dpi!(externs::dpiContext_getClientVersion(self.handle.context, &mut version_info),
                  Ok(version_info.into()),
                  ErrorKind::Connection("dpiContext_getClientVersion".to_string()))
     }
@@ -72,24 +122,24 @@ impl Context {
     /// any other ODPI-C library calls are made on the calling thread since the error information
     /// specific to that thread is cleared at the start of every ODPI-C function call.
     pub fn get_error(&self) -> error::Info {
-        let mut error_info: ODPIErrorInfo = Default::default();
-        unsafe {
-            externs::dpiContext_getError(self.context, &mut error_info);
-            error_info.into()
-        }
+        self.handle.get_error()
     }
 
     /// Initializes the `CommonCreate` structure to default values.
     pub fn init_common_create_params(&self) -> Result<CommonCreate> {
         let mut ccp: ODPICommonCreateParams = Default::default();
 
-        try_dpi!(externs::dpiContext_initCommonCreateParams(self.context, &mut ccp),
+        try_dpi!(externs::dpiContext_initCommonCreateParams(self.handle.context, &mut ccp),
                  {
                      let driver_name = "Rust Oracle: 0.1.0";
                      let driver_name_s = ODPIStr::from(driver_name);
                      ccp.driver_name = driver_name_s.ptr();
                      ccp.driver_name_length = driver_name_s.len();
-                     Ok(CommonCreate::new(ccp))
+                     let mut common_create = CommonCreate::new(ccp);
+                     if let Some(ref encoding) = self.default_encoding {
+                         common_create.set_owned_encoding(encoding.clone());
+                     }
+                     Ok(common_create)
                  },
                  ErrorKind::Context("dpiContext_initCommonCreateParams".to_string()))
     }
@@ -98,7 +148,7 @@ impl Context {
     pub fn init_conn_create_params(&self) -> Result<ConnCreate> {
         let mut conn: ODPIConnCreateParams = Default::default();
 
-        try_dpi!(externs::dpiContext_initConnCreateParams(self.context, &mut conn),
+        try_dpi!(externs::dpiContext_initConnCreateParams(self.handle.context, &mut conn),
                  Ok(ConnCreate::new(conn)),
                  ErrorKind::Context("dpiContext_initConnCreateParams".to_string()))
     }
@@ -106,7 +156,7 @@ impl Context {
     /// Initializes the `PoolCreate` structure to default values.
     pub fn init_pool_create_params(&self) -> Result<PoolCreate> {
         let mut pool: ODPIPoolCreateParams = Default::default();
-        try_dpi!(externs::dpiContext_initPoolCreateParams(self.context, &mut pool),
+        try_dpi!(externs::dpiContext_initPoolCreateParams(self.handle.context, &mut pool),
                  Ok(PoolCreate::new(pool)),
                  ErrorKind::Context("dpiContext_initPoolCreateParams".to_string()))
     }
@@ -114,30 +164,41 @@ impl Context {
     /// Initializes the `SubscrCreate` struct to default values.
     pub fn init_subscr_create_params(&self) -> Result<SubscrCreate> {
         let mut subscr: ODPISubscrCreateParams = Default::default();
-        try_dpi!(externs::dpiContext_initSubscrCreateParams(self.context, &mut subscr),
+        try_dpi!(externs::dpiContext_initSubscrCreateParams(self.handle.context, &mut subscr),
                  Ok(SubscrCreate::new(subscr)),
                  ErrorKind::Context("dpiContext_initSubscrCreateParams".to_string()))
     }
-}
 
-impl Drop for Context {
-    fn drop(&mut self) {
-        if unsafe { externs::dpiContext_destroy(self.context) } == DPI_FAILURE {
-            try_error!(self.stderr, "Failed to destroy context");
-        } else {
-            try_info!(self.stdout, "Successfully destroyed context");
-        }
+    /// Sets the encoding used for CHAR and NCHAR data by every `CommonCreate` returned from
+    /// `init_common_create_params()`, including the one used implicitly by `Connection::create()`.
+    /// Each `CommonCreate` keeps its own copy of the encoding, so it remains valid even if the
+    /// default is changed again or the parameters outlive the context.
+    ///
+    /// * `enc` - the IANA or Oracle specific name of the character set, e.g. "UTF-8".
+    pub fn set_default_encoding(&mut self, enc: &str) -> Result<()> {
+        self.default_encoding = Some(CString::new(enc)?);
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::Context;
+    use super::{odpi_version, Context};
     use super::params::AppContext;
-    use odpi::{flags, structs};
+    use connection::Connection;
+    use odpi::{constants, flags, structs};
     use odpi::flags::ODPISubscrNamespace::*;
     use odpi::flags::ODPISubscrProtocol::*;
     use std::ffi::CString;
+    use test::{CONN_STR, CREDS};
+
+    #[test]
+    fn odpi_version_matches() {
+        let (major, minor) = odpi_version();
+        assert_eq!(major, constants::DPI_MAJOR_VERSION);
+        assert_eq!(minor, constants::DPI_MINOR_VERSION);
+        assert_eq!(major, 2);
+    }
 
     #[test]
     fn create() {
@@ -176,6 +237,53 @@ mod test {
         }
     }
 
+    #[test]
+    fn set_default_encoding() {
+        let mut ctxt = match Context::create() {
+            Ok(ctxt) => ctxt,
+            Err(e) => return ::test::error_info(e),
+        };
+
+        match ctxt.set_default_encoding("UTF-8") {
+            Ok(_) => assert!(true),
+            Err(e) => return ::test::error_info(e),
+        }
+        match ctxt.init_common_create_params() {
+            Ok(ccp) => {
+                // The parameters keep their own copy, so changing the default leaves them valid.
+                match ctxt.set_default_encoding("AL32UTF8") {
+                    Ok(_) => assert!(true),
+                    Err(e) => return ::test::error_info(e),
+                }
+                assert!(ccp.get_encoding() == "UTF-8");
+                assert!(ccp.get_nchar_encoding() == "UTF-8");
+            }
+            Err(e) => return ::test::error_info(e),
+        }
+        match ctxt.set_default_encoding("UTF-8") {
+            Ok(_) => assert!(true),
+            Err(e) => return ::test::error_info(e),
+        }
+
+        match Connection::create(&ctxt,
+                                 Some(&CREDS[0]),
+                                 Some(&CREDS[1]),
+                                 Some(CONN_STR),
+                                 None,
+                                 None) {
+            Ok(conn) => {
+                match conn.get_encoding_info() {
+                    Ok(ei) => {
+                        assert!(ei.encoding() == "UTF-8");
+                        assert!(ei.nchar_encoding() == "UTF-8");
+                    }
+                    Err(e) => ::test::error_info(e),
+                }
+            }
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
     #[test]
     fn init_conn_create_params() {
         match Context::create() {
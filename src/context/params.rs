@@ -8,11 +8,12 @@
 
 //! These structs are used for initializing parameters used during connection creation, pool
 //! creation, or subscription creation.
+use error::{ErrorKind, Result};
 use odpi::{externs, flags};
 use odpi::structs::{ODPIAppContext, ODPICommonCreateParams, ODPIConnCreateParams,
                     ODPIPoolCreateParams, ODPISubscrCreateParams};
 use pool::Pool;
-use std::ffi::CStr;
+use std::ffi::{CStr, CString};
 use util::ODPIStr;
 
 /// This structure is used for passing application context to the database during the process of
@@ -83,13 +84,19 @@ impl AppContext {
 pub struct CommonCreate {
     /// The ODPI-C dpiCommonCreateParams struct.
     ccp: ODPICommonCreateParams,
+    /// The encoding set by `set_owned_encoding()`, kept here so that the pointers to it in `ccp`
+    /// remain valid for as long as the parameters do.
+    owned_encoding: Option<CString>,
 }
 
 impl CommonCreate {
     /// Create a new `Create` struct.
     #[doc(hidden)]
     pub fn new(ccp: ODPICommonCreateParams) -> CommonCreate {
-        CommonCreate { ccp: ccp }
+        CommonCreate {
+            ccp: ccp,
+            owned_encoding: None,
+        }
     }
 
     /// Get the inner FFI struct.
@@ -129,6 +136,16 @@ impl CommonCreate {
         self
     }
 
+    /// Set both the `encoding` and `nchar_encoding` values to the given encoding, which is owned by
+    /// the parameters rather than borrowed from the caller.
+    #[doc(hidden)]
+    pub fn set_owned_encoding(&mut self, encoding: CString) -> &mut CommonCreate {
+        self.ccp.encoding = encoding.as_ptr();
+        self.ccp.nchar_encoding = encoding.as_ptr();
+        self.owned_encoding = Some(encoding);
+        self
+    }
+
     /// Get the `nchar_encoding` value.
     ///
     /// Specifies the encoding to use for NCHAR data, as a null-terminated ASCII string. Either an
@@ -354,12 +371,23 @@ impl ConnCreate {
     ///
     /// Specifies the session pool from which to acquire a connection or NULL if a standalone
     /// connection should be created. The default value is NULL.
-    pub fn get_pool(&self) -> Pool {
-        self.conn.pool.into()
+    ///
+    /// A reference is added to the pool, so the returned `Pool` can be dropped independently of
+    /// the one that was set.
+    pub fn get_pool(&self) -> Result<Pool> {
+        let pool_ptr = self.conn.pool;
+        if pool_ptr.is_null() {
+            return Ok(pool_ptr.into());
+        }
+
+        try_dpi!(externs::dpiPool_addRef(pool_ptr),
+                 Ok(pool_ptr.into()),
+                 ErrorKind::Pool("dpiPool_addRef".to_string()))
     }
 
-    /// Set the `pool` value.
-    pub fn set_pool(&mut self, pool: Pool) -> &mut ConnCreate {
+    /// Set the `pool` value. The pool must outlive the connection creation that uses these
+    /// parameters.
+    pub fn set_pool(&mut self, pool: &Pool) -> &mut ConnCreate {
         self.conn.pool = pool.inner();
         self
     }
@@ -12,12 +12,23 @@
 //! (such as Go) do not have the ability to manipulate structures containing unions or the ability
 //! to process macros. For this reason, none of these functions perform any error checking. They are
 //! assumed to be replacements for direct manipulation of the various members of the structure.
-use chrono::{DateTime, Duration, TimeZone, UTC};
-use odpi::structs::{ODPIData, ODPIDataValueUnion};
+use chrono::{DateTime, Datelike, Duration, FixedOffset, NaiveDate, NaiveDateTime, TimeZone,
+             Timelike, UTC};
+use error::{ErrorKind, Result};
+use odpi::flags::{ODPINativeTypeNum, ODPIOracleTypeNum};
+use odpi::structs::{ODPIBytes, ODPIData, ODPIDataValueUnion, ODPIIntervalDS, ODPITimestamp};
+use std::ptr;
 use util::ODPIStr;
 
 /// This structure is used for passing data to and from the database for variables and for
 /// manipulating object attributes and collection values.
+///
+/// The `as_*` accessors read the value union without checking which member is active, so the
+/// caller must know the native type of the data. Reading the wrong member is undefined behavior:
+/// `as_bytes()` and `as_string()` in particular dereference whatever the union holds as a pointer
+/// and length. Prefer the checked `try_as_*` accessors, which take the native type reported by
+/// ODPI-C, for example by `Statement::get_query_value()`, and return an error instead when it does
+/// not match or the value is NULL.
 pub struct Data {
     /// The ODPI-C data pointer.
     data: *mut ODPIData,
@@ -40,9 +51,15 @@ impl Data {
         self.data
     }
 
-    /// Get the value as a boolean when the native type is DPI_NATIVE_TYPE_BOOLEAN.
+    /// Does the data refer to a null value?
+    pub fn is_null(&self) -> bool {
+        unsafe { (*self.data).is_null == 1 }
+    }
+
+    /// Get the value as a boolean when the native type is DPI_NATIVE_TYPE_BOOLEAN. Any non-zero
+    /// value is true.
     pub fn as_boolean(&self) -> bool {
-        unsafe { (*self.data).value.as_boolean == 1 }
+        unsafe { (*self.data).value.as_boolean != 0 }
     }
 
     /// Get the value as an `i64` when the native type is DPI_NATIVE_TYPE_INT64.
@@ -74,8 +91,19 @@ impl Data {
         }
     }
 
-    /// Get the value as a `UTC` when the native type is DPI_NATIVE_TYPE_TIMESTAMP.
-    pub fn as_utc(&self) -> DateTime<UTC> {
+    /// Get the value as raw bytes when the native type is DPI_NATIVE_TYPE_BYTES, without any
+    /// character set conversion.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        unsafe {
+            let odpi_bytes = (*self.data).value.as_bytes;
+            let odpi_s = ODPIStr::new(odpi_bytes.ptr, odpi_bytes.length);
+            odpi_s.to_bytes()
+        }
+    }
+
+    /// Get the value as a `UTC` when the native type is DPI_NATIVE_TYPE_TIMESTAMP. Returns `None`
+    /// if the timestamp is not a valid date and time, e.g. a zeroed timestamp with month 0.
+    pub fn as_utc(&self) -> Option<DateTime<UTC>> {
         let odpi_ts = unsafe { (*self.data).value.as_timestamp };
         let y = odpi_ts.year as i32;
         let m = odpi_ts.month as u32;
@@ -83,7 +111,49 @@ impl Data {
         let h = odpi_ts.hour as u32;
         let mi = odpi_ts.minute as u32;
         let s = odpi_ts.second as u32;
-        UTC.ymd(y, m, d).and_hms_nano(h, mi, s, odpi_ts.fsecond)
+        UTC.ymd_opt(y, m, d)
+            .single()
+            .and_then(|date| date.and_hms_nano_opt(h, mi, s, odpi_ts.fsecond))
+    }
+
+    /// Get the value as a `NaiveDateTime` when the native type is DPI_NATIVE_TYPE_TIMESTAMP and the
+    /// Oracle type is DPI_ORACLE_TYPE_DATE. Oracle DATE values have no time zone and only second
+    /// precision, so any fractional seconds are discarded. Returns `None` if the value is not a
+    /// valid date and time.
+    pub fn as_naive_date(&self) -> Option<NaiveDateTime> {
+        let odpi_ts = unsafe { (*self.data).value.as_timestamp };
+        NaiveDate::from_ymd_opt(odpi_ts.year as i32, odpi_ts.month as u32, odpi_ts.day as u32)
+            .and_then(|date| {
+                          date.and_hms_opt(odpi_ts.hour as u32,
+                                           odpi_ts.minute as u32,
+                                           odpi_ts.second as u32)
+                      })
+    }
+
+    /// Get the value as a `DateTime` in the session time zone when the native type is
+    /// DPI_NATIVE_TYPE_TIMESTAMP and the Oracle type is DPI_ORACLE_TYPE_TIMESTAMP_LTZ. Oracle
+    /// returns TIMESTAMP WITH LOCAL TIME ZONE values as wall clock times in the session time zone,
+    /// as returned by `Connection::session_timezone()`, so that zone is attached to the value.
+    /// Only fixed offsets such as "+02:00", and "UTC", are supported; named regions return
+    /// `ErrorKind::InvalidTimezone` as no time zone database is available.
+    ///
+    /// * `session_tz` - the session time zone.
+    pub fn as_local_timestamp(&self, session_tz: &str) -> Result<DateTime<FixedOffset>> {
+        let offset = match parse_offset(session_tz) {
+            Some(offset) => offset,
+            None => return Err(ErrorKind::InvalidTimezone(session_tz.to_string()).into()),
+        };
+        let odpi_ts = unsafe { (*self.data).value.as_timestamp };
+
+        NaiveDate::from_ymd_opt(odpi_ts.year as i32, odpi_ts.month as u32, odpi_ts.day as u32)
+            .and_then(|date| {
+                          date.and_hms_nano_opt(odpi_ts.hour as u32,
+                                                odpi_ts.minute as u32,
+                                                odpi_ts.second as u32,
+                                                odpi_ts.fsecond)
+                      })
+            .and_then(|local| offset.from_local_datetime(&local).single())
+            .ok_or_else(|| ErrorKind::InvalidTimestamp.into())
     }
 
     /// Get the value as a `Duration` when the native type is DPI_NATIVE_TYPE_INTERVAL_DS.
@@ -96,6 +166,185 @@ impl Data {
         dur = dur + Duration::nanoseconds(odpi_int_ds.fseconds as i64);
         dur
     }
+
+    /// Set the data to refer to a null value.
+    pub fn set_null(&self) {
+        unsafe { (*self.data).is_null = 1 }
+    }
+
+    /// Set the value when the native type is DPI_NATIVE_TYPE_BOOLEAN.
+    pub fn set_boolean(&self, value: bool) {
+        self.set_value(ODPIDataValueUnion { as_boolean: if value { 1 } else { 0 } })
+    }
+
+    /// Set the value when the native type is DPI_NATIVE_TYPE_INT64.
+    pub fn set_int64(&self, value: i64) {
+        self.set_value(ODPIDataValueUnion { as_int_64: value })
+    }
+
+    /// Set the value when the native type is DPI_NATIVE_TYPE_UINT64.
+    pub fn set_uint64(&self, value: u64) {
+        self.set_value(ODPIDataValueUnion { as_uint_64: value })
+    }
+
+    /// Set the value when the native type is DPI_NATIVE_TYPE_FLOAT.
+    pub fn set_float(&self, value: f32) {
+        self.set_value(ODPIDataValueUnion { as_float: value })
+    }
+
+    /// Set the value when the native type is DPI_NATIVE_TYPE_DOUBLE.
+    pub fn set_double(&self, value: f64) {
+        self.set_value(ODPIDataValueUnion { as_double: value })
+    }
+
+    /// Set the value when the native type is DPI_NATIVE_TYPE_BYTES. Only the pointer to the string
+    /// is stored, so it must outlive any use of the data. This should not be used for the data of
+    /// variables, which own their buffers; use `Var::set_from_bytes()` for those instead.
+    pub fn set_bytes(&self, value: &str) {
+        self.set_value(ODPIDataValueUnion { as_bytes: to_odpi_bytes(value.as_bytes()) })
+    }
+
+    /// Set the value when the native type is DPI_NATIVE_TYPE_TIMESTAMP.
+    pub fn set_timestamp(&self, value: DateTime<UTC>) {
+        self.set_value(OwnedData::Timestamp(value).to_odpi().1.value)
+    }
+
+    /// Set the value when the native type is DPI_NATIVE_TYPE_INTERVAL_DS.
+    pub fn set_interval_ds(&self, value: Duration) {
+        self.set_value(OwnedData::IntervalDS(value).to_odpi().1.value)
+    }
+
+    /// Set the value union and clear the null flag.
+    fn set_value(&self, value: ODPIDataValueUnion) {
+        unsafe {
+            (*self.data).is_null = 0;
+            (*self.data).value = value;
+        }
+    }
+
+    /// Check that the data holds a non-NULL value of the expected native type.
+    fn check_type(&self,
+                  native_type: ODPINativeTypeNum,
+                  expected: ODPINativeTypeNum)
+                  -> Result<()> {
+        if native_type != expected {
+            Err(ErrorKind::TypeMismatch(format!("{:?}", expected), format!("{:?}", native_type))
+                    .into())
+        } else if self.is_null() {
+            Err(ErrorKind::NullValue.into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Get the value as a boolean, checking that the native type is DPI_NATIVE_TYPE_BOOLEAN.
+    ///
+    /// * `native_type` - the native type of the data.
+    pub fn try_as_boolean(&self, native_type: ODPINativeTypeNum) -> Result<bool> {
+        self.check_type(native_type, ODPINativeTypeNum::Boolean)?;
+        Ok(self.as_boolean())
+    }
+
+    /// Get the value as raw bytes, checking that the native type is DPI_NATIVE_TYPE_BYTES.
+    ///
+    /// * `native_type` - the native type of the data.
+    pub fn try_as_bytes(&self, native_type: ODPINativeTypeNum) -> Result<Vec<u8>> {
+        self.check_type(native_type, ODPINativeTypeNum::Bytes)?;
+        Ok(self.as_bytes())
+    }
+
+    /// Get the value as a `f64`, checking that the native type is DPI_NATIVE_TYPE_DOUBLE.
+    ///
+    /// * `native_type` - the native type of the data.
+    pub fn try_as_double(&self, native_type: ODPINativeTypeNum) -> Result<f64> {
+        self.check_type(native_type, ODPINativeTypeNum::Double)?;
+        Ok(self.as_double())
+    }
+
+    /// Get the value as a `Duration`, checking that the native type is
+    /// DPI_NATIVE_TYPE_INTERVAL_DS.
+    ///
+    /// * `native_type` - the native type of the data.
+    pub fn try_as_duration(&self, native_type: ODPINativeTypeNum) -> Result<Duration> {
+        self.check_type(native_type, ODPINativeTypeNum::IntervalDS)?;
+        Ok(self.as_duration())
+    }
+
+    /// Get the value as a `f32`, checking that the native type is DPI_NATIVE_TYPE_FLOAT.
+    ///
+    /// * `native_type` - the native type of the data.
+    pub fn try_as_float(&self, native_type: ODPINativeTypeNum) -> Result<f32> {
+        self.check_type(native_type, ODPINativeTypeNum::Float)?;
+        Ok(self.as_float())
+    }
+
+    /// Get the value as an `i64`, checking that the native type is DPI_NATIVE_TYPE_INT64.
+    ///
+    /// * `native_type` - the native type of the data.
+    pub fn try_as_int64(&self, native_type: ODPINativeTypeNum) -> Result<i64> {
+        self.check_type(native_type, ODPINativeTypeNum::Int64)?;
+        Ok(self.as_int64())
+    }
+
+    /// Get the value as a `NaiveDateTime`, checking that the native type is
+    /// DPI_NATIVE_TYPE_TIMESTAMP and that the value is a valid date and time.
+    ///
+    /// * `native_type` - the native type of the data.
+    pub fn try_as_naive_date(&self, native_type: ODPINativeTypeNum) -> Result<NaiveDateTime> {
+        self.check_type(native_type, ODPINativeTypeNum::Timestamp)?;
+        self.as_naive_date().ok_or_else(|| ErrorKind::InvalidTimestamp.into())
+    }
+
+    /// Get the value as a `String`, checking that the native type is DPI_NATIVE_TYPE_BYTES.
+    ///
+    /// * `native_type` - the native type of the data.
+    pub fn try_as_string(&self, native_type: ODPINativeTypeNum) -> Result<String> {
+        self.check_type(native_type, ODPINativeTypeNum::Bytes)?;
+        Ok(self.as_string())
+    }
+
+    /// Get the value as a `u64`, checking that the native type is DPI_NATIVE_TYPE_UINT64.
+    ///
+    /// * `native_type` - the native type of the data.
+    pub fn try_as_uint64(&self, native_type: ODPINativeTypeNum) -> Result<u64> {
+        self.check_type(native_type, ODPINativeTypeNum::Uint64)?;
+        Ok(self.as_uint64())
+    }
+
+    /// Get the value as a `UTC`, checking that the native type is DPI_NATIVE_TYPE_TIMESTAMP and
+    /// that the value is a valid date and time.
+    ///
+    /// * `native_type` - the native type of the data.
+    pub fn try_as_utc(&self, native_type: ODPINativeTypeNum) -> Result<DateTime<UTC>> {
+        self.check_type(native_type, ODPINativeTypeNum::Timestamp)?;
+        self.as_utc().ok_or_else(|| ErrorKind::InvalidTimestamp.into())
+    }
+}
+
+/// Parse a fixed time zone offset of the form "+HH:MM" or "-HH:MM", as returned for SESSIONTIMEZONE
+/// and DBTIMEZONE. "UTC", "GMT" and "Z" are accepted as a zero offset.
+fn parse_offset(tz: &str) -> Option<FixedOffset> {
+    let tz = tz.trim();
+    match tz {
+        "UTC" | "GMT" | "Z" => return FixedOffset::east_opt(0),
+        _ => {}
+    }
+
+    let sign = match tz.chars().next() {
+        Some('+') => 1,
+        Some('-') => -1,
+        _ => return None,
+    };
+    let mut parts = tz[1..].splitn(2, ':');
+    let hours = parts.next().and_then(|h| h.parse::<i32>().ok());
+    let minutes = parts.next().and_then(|m| m.parse::<i32>().ok());
+
+    match (hours, minutes) {
+        (Some(h), Some(m)) if h >= 0 && h <= 14 && m >= 0 && m < 60 => {
+            FixedOffset::east_opt(sign * (h * 3600 + m * 60))
+        }
+        _ => None,
+    }
 }
 
 impl From<*mut ODPIData> for Data {
@@ -103,3 +352,593 @@ impl From<*mut ODPIData> for Data {
         Data { data: data }
     }
 }
+
+/// An owned copy of a single value transferred to or from the database. Unlike `Data`, which
+/// points into memory managed by ODPI-C that is overwritten by the next fetch, an `OwnedData`
+/// value remains valid for as long as it is kept around.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OwnedData {
+    /// SQL NULL.
+    Null,
+    /// A value of native type DPI_NATIVE_TYPE_BOOLEAN.
+    Boolean(bool),
+    /// A value of native type DPI_NATIVE_TYPE_INT64.
+    Int64(i64),
+    /// A value of native type DPI_NATIVE_TYPE_UINT64.
+    Uint64(u64),
+    /// A value of native type DPI_NATIVE_TYPE_FLOAT.
+    Float(f32),
+    /// A value of native type DPI_NATIVE_TYPE_DOUBLE.
+    Double(f64),
+    /// Character data of native type DPI_NATIVE_TYPE_BYTES.
+    String(String),
+    /// Raw data of native type DPI_NATIVE_TYPE_BYTES.
+    Bytes(Vec<u8>),
+    /// A value of Oracle type DPI_ORACLE_TYPE_DATE, which has second precision and no time zone.
+    Date(NaiveDateTime),
+    /// A value of native type DPI_NATIVE_TYPE_TIMESTAMP.
+    Timestamp(DateTime<UTC>),
+    /// A value of native type DPI_NATIVE_TYPE_INTERVAL_DS.
+    IntervalDS(Duration),
+}
+
+impl OwnedData {
+    /// Copy the value out of the given `Data`, interpreting it as the given native type.
+    ///
+    /// * `native_type` - the native type of the data, as returned by
+    /// `Statement::get_query_value()`.
+    /// * `data` - the data to copy.
+    pub fn from_data(native_type: ODPINativeTypeNum, data: &Data) -> Result<OwnedData> {
+        if data.is_null() {
+            return Ok(OwnedData::Null);
+        }
+
+        match native_type {
+            ODPINativeTypeNum::Boolean => Ok(OwnedData::Boolean(data.as_boolean())),
+            ODPINativeTypeNum::Int64 => Ok(OwnedData::Int64(data.as_int64())),
+            ODPINativeTypeNum::Uint64 => Ok(OwnedData::Uint64(data.as_uint64())),
+            ODPINativeTypeNum::Float => Ok(OwnedData::Float(data.as_float())),
+            ODPINativeTypeNum::Double => Ok(OwnedData::Double(data.as_double())),
+            ODPINativeTypeNum::Bytes => Ok(OwnedData::String(data.as_string())),
+            ODPINativeTypeNum::Timestamp => {
+                data.as_utc()
+                    .map(OwnedData::Timestamp)
+                    .ok_or_else(|| ErrorKind::InvalidTimestamp.into())
+            }
+            ODPINativeTypeNum::IntervalDS => Ok(OwnedData::IntervalDS(data.as_duration())),
+            _ => Err(ErrorKind::TypeMismatch("OwnedData".to_string(),
+                                             format!("{:?}", native_type))
+                             .into()),
+        }
+    }
+
+    /// Copy the value of a query column out of the given `Data`. This is the same as `from_data()`
+    /// except that the Oracle type of the column is used to tell DATE columns, which are decoded
+    /// as `OwnedData::Date`, apart from TIMESTAMP columns, and RAW columns, which are decoded as
+    /// `OwnedData::Bytes`, apart from character columns.
+    ///
+    /// * `oracle_type` - the Oracle type of the column, as returned by `query::Info`.
+    /// * `native_type` - the native type of the data.
+    /// * `data` - the data to copy.
+    pub fn from_column(oracle_type: ODPIOracleTypeNum,
+                       native_type: ODPINativeTypeNum,
+                       data: &Data)
+                       -> Result<OwnedData> {
+        match (oracle_type, native_type) {
+            (ODPIOracleTypeNum::Date, ODPINativeTypeNum::Timestamp) if !data.is_null() => {
+                data.as_naive_date()
+                    .map(OwnedData::Date)
+                    .ok_or_else(|| ErrorKind::InvalidTimestamp.into())
+            }
+            (ODPIOracleTypeNum::Raw, ODPINativeTypeNum::Bytes) |
+            (ODPIOracleTypeNum::LongRaw, ODPINativeTypeNum::Bytes) if !data.is_null() => {
+                Ok(OwnedData::Bytes(data.as_bytes()))
+            }
+            _ => OwnedData::from_data(native_type, data),
+        }
+    }
+
+    /// Get the native type and an `ODPIData` structure describing this value, suitable for binding
+    /// with `dpiStmt_bindValueByName()` and friends. Byte strings point into `self`, so the
+    /// returned structure must not outlive it.
+    #[doc(hidden)]
+    pub fn to_odpi(&self) -> (ODPINativeTypeNum, ODPIData) {
+        let (native_type, value) = match *self {
+            OwnedData::Null => {
+                return (ODPINativeTypeNum::Bytes,
+                        ODPIData {
+                            is_null: 1,
+                            value: ODPIDataValueUnion { as_int_64: 0 },
+                        })
+            }
+            OwnedData::Boolean(b) => {
+                (ODPINativeTypeNum::Boolean,
+                 ODPIDataValueUnion { as_boolean: if b { 1 } else { 0 } })
+            }
+            OwnedData::Int64(i) => (ODPINativeTypeNum::Int64, ODPIDataValueUnion { as_int_64: i }),
+            OwnedData::Uint64(u) => {
+                (ODPINativeTypeNum::Uint64, ODPIDataValueUnion { as_uint_64: u })
+            }
+            OwnedData::Float(f) => (ODPINativeTypeNum::Float, ODPIDataValueUnion { as_float: f }),
+            OwnedData::Double(d) => {
+                (ODPINativeTypeNum::Double, ODPIDataValueUnion { as_double: d })
+            }
+            OwnedData::String(ref s) => {
+                (ODPINativeTypeNum::Bytes,
+                 ODPIDataValueUnion { as_bytes: to_odpi_bytes(s.as_bytes()) })
+            }
+            OwnedData::Bytes(ref b) => {
+                (ODPINativeTypeNum::Bytes, ODPIDataValueUnion { as_bytes: to_odpi_bytes(b) })
+            }
+            OwnedData::Date(dt) => {
+                let odpi_ts = ODPITimestamp {
+                    year: dt.year() as i16,
+                    month: dt.month() as u8,
+                    day: dt.day() as u8,
+                    hour: dt.hour() as u8,
+                    minute: dt.minute() as u8,
+                    second: dt.second() as u8,
+                    fsecond: 0,
+                    tz_hour_offset: 0,
+                    tz_minute_offset: 0,
+                };
+                (ODPINativeTypeNum::Timestamp, ODPIDataValueUnion { as_timestamp: odpi_ts })
+            }
+            OwnedData::Timestamp(ts) => {
+                let odpi_ts = ODPITimestamp {
+                    year: ts.year() as i16,
+                    month: ts.month() as u8,
+                    day: ts.day() as u8,
+                    hour: ts.hour() as u8,
+                    minute: ts.minute() as u8,
+                    second: ts.second() as u8,
+                    fsecond: ts.nanosecond(),
+                    tz_hour_offset: 0,
+                    tz_minute_offset: 0,
+                };
+                (ODPINativeTypeNum::Timestamp, ODPIDataValueUnion { as_timestamp: odpi_ts })
+            }
+            OwnedData::IntervalDS(dur) => {
+                let secs = dur.num_seconds();
+                let nanos = (dur - Duration::seconds(secs)).num_nanoseconds().unwrap_or(0);
+                let odpi_int_ds = ODPIIntervalDS {
+                    days: (secs / 86_400) as i32,
+                    hours: ((secs % 86_400) / 3600) as i32,
+                    minutes: ((secs % 3600) / 60) as i32,
+                    seconds: (secs % 60) as i32,
+                    fseconds: nanos as i32,
+                };
+                (ODPINativeTypeNum::IntervalDS, ODPIDataValueUnion { as_interval_ds: odpi_int_ds })
+            }
+        };
+
+        (native_type,
+         ODPIData {
+             is_null: 0,
+             value: value,
+         })
+    }
+
+    /// Is this value SQL NULL?
+    pub fn is_null(&self) -> bool {
+        *self == OwnedData::Null
+    }
+
+    /// The name of the variant holding the value, used when reporting type mismatches.
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            OwnedData::Null => "Null",
+            OwnedData::Boolean(_) => "Boolean",
+            OwnedData::Int64(_) => "Int64",
+            OwnedData::Uint64(_) => "Uint64",
+            OwnedData::Float(_) => "Float",
+            OwnedData::Double(_) => "Double",
+            OwnedData::String(_) => "String",
+            OwnedData::Bytes(_) => "Bytes",
+            OwnedData::Date(_) => "Date",
+            OwnedData::Timestamp(_) => "Timestamp",
+            OwnedData::IntervalDS(_) => "IntervalDS",
+        }
+    }
+}
+
+/// Build an `ODPIBytes` structure pointing at the given bytes.
+fn to_odpi_bytes(bytes: &[u8]) -> ODPIBytes {
+    ODPIBytes {
+        ptr: bytes.as_ptr() as *mut ::std::os::raw::c_char,
+        length: bytes.len() as u32,
+        encoding: ptr::null(),
+    }
+}
+
+/// Conversion from an `OwnedData` value into a Rust type.
+///
+/// Conversions to non-`Option` types fail with `ErrorKind::NullValue` when the value is SQL NULL.
+/// Use `Option<T>` to accept NULL.
+pub trait FromData: Sized {
+    /// Convert the given value.
+    fn from_data(data: &OwnedData) -> Result<Self>;
+}
+
+/// Build the error returned when a value cannot be converted to the requested type.
+fn mismatch<T>(expected: &str, data: &OwnedData) -> Result<T> {
+    match *data {
+        OwnedData::Null => Err(ErrorKind::NullValue.into()),
+        _ => {
+            Err(ErrorKind::TypeMismatch(expected.to_string(), data.type_name().to_string()).into())
+        }
+    }
+}
+
+impl<T: FromData> FromData for Option<T> {
+    fn from_data(data: &OwnedData) -> Result<Option<T>> {
+        match *data {
+            OwnedData::Null => Ok(None),
+            _ => T::from_data(data).map(Some),
+        }
+    }
+}
+
+impl FromData for OwnedData {
+    fn from_data(data: &OwnedData) -> Result<OwnedData> {
+        Ok(data.clone())
+    }
+}
+
+impl FromData for bool {
+    fn from_data(data: &OwnedData) -> Result<bool> {
+        match *data {
+            OwnedData::Boolean(b) => Ok(b),
+            _ => mismatch("bool", data),
+        }
+    }
+}
+
+/// NUMBER columns are fetched as DPI_NATIVE_TYPE_DOUBLE unless their precision is small enough for
+/// a 64-bit integer, so integral doubles are also accepted for the integer types.
+impl FromData for i64 {
+    fn from_data(data: &OwnedData) -> Result<i64> {
+        match *data {
+            OwnedData::Int64(i) => Ok(i),
+            OwnedData::Uint64(u) if u <= i64::max_value() as u64 => Ok(u as i64),
+            OwnedData::Double(d) if d.fract() == 0.0 && d.abs() < 9.2e18 => Ok(d as i64),
+            _ => mismatch("i64", data),
+        }
+    }
+}
+
+impl FromData for u64 {
+    fn from_data(data: &OwnedData) -> Result<u64> {
+        match *data {
+            OwnedData::Uint64(u) => Ok(u),
+            OwnedData::Int64(i) if i >= 0 => Ok(i as u64),
+            OwnedData::Double(d) if d.fract() == 0.0 && d >= 0.0 && d < 1.8e19 => Ok(d as u64),
+            _ => mismatch("u64", data),
+        }
+    }
+}
+
+impl FromData for f32 {
+    fn from_data(data: &OwnedData) -> Result<f32> {
+        match *data {
+            OwnedData::Float(f) => Ok(f),
+            _ => mismatch("f32", data),
+        }
+    }
+}
+
+impl FromData for f64 {
+    fn from_data(data: &OwnedData) -> Result<f64> {
+        match *data {
+            OwnedData::Double(d) => Ok(d),
+            OwnedData::Float(f) => Ok(f as f64),
+            OwnedData::Int64(i) => Ok(i as f64),
+            OwnedData::Uint64(u) => Ok(u as f64),
+            _ => mismatch("f64", data),
+        }
+    }
+}
+
+impl FromData for String {
+    fn from_data(data: &OwnedData) -> Result<String> {
+        match *data {
+            OwnedData::String(ref s) => Ok(s.clone()),
+            _ => mismatch("String", data),
+        }
+    }
+}
+
+impl FromData for Vec<u8> {
+    fn from_data(data: &OwnedData) -> Result<Vec<u8>> {
+        match *data {
+            OwnedData::Bytes(ref b) => Ok(b.clone()),
+            OwnedData::String(ref s) => Ok(s.clone().into_bytes()),
+            _ => mismatch("Vec<u8>", data),
+        }
+    }
+}
+
+impl FromData for DateTime<UTC> {
+    fn from_data(data: &OwnedData) -> Result<DateTime<UTC>> {
+        match *data {
+            OwnedData::Timestamp(ts) => Ok(ts),
+            _ => mismatch("DateTime<UTC>", data),
+        }
+    }
+}
+
+impl FromData for NaiveDateTime {
+    fn from_data(data: &OwnedData) -> Result<NaiveDateTime> {
+        match *data {
+            OwnedData::Date(dt) => Ok(dt),
+            OwnedData::Timestamp(ts) => Ok(ts.naive_utc()),
+            _ => mismatch("NaiveDateTime", data),
+        }
+    }
+}
+
+impl FromData for Duration {
+    fn from_data(data: &OwnedData) -> Result<Duration> {
+        match *data {
+            OwnedData::IntervalDS(dur) => Ok(dur),
+            _ => mismatch("Duration", data),
+        }
+    }
+}
+
+/// Conversion from a Rust type into an `OwnedData` value, for binding.
+///
+/// Byte slices and vectors become `OwnedData::Bytes` and are never treated as text.
+pub trait ToData {
+    /// Convert the value into an `OwnedData`.
+    fn to_data(&self) -> OwnedData;
+}
+
+impl<T: ToData> ToData for Option<T> {
+    fn to_data(&self) -> OwnedData {
+        match *self {
+            Some(ref value) => value.to_data(),
+            None => OwnedData::Null,
+        }
+    }
+}
+
+impl ToData for OwnedData {
+    fn to_data(&self) -> OwnedData {
+        self.clone()
+    }
+}
+
+impl ToData for bool {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::Boolean(*self)
+    }
+}
+
+impl ToData for i64 {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::Int64(*self)
+    }
+}
+
+impl ToData for u64 {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::Uint64(*self)
+    }
+}
+
+impl ToData for f32 {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::Float(*self)
+    }
+}
+
+impl ToData for f64 {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::Double(*self)
+    }
+}
+
+impl<'a> ToData for &'a str {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::String(self.to_string())
+    }
+}
+
+impl ToData for String {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::String(self.clone())
+    }
+}
+
+impl<'a> ToData for &'a [u8] {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::Bytes(self.to_vec())
+    }
+}
+
+impl ToData for Vec<u8> {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::Bytes(self.clone())
+    }
+}
+
+impl ToData for DateTime<UTC> {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::Timestamp(*self)
+    }
+}
+
+impl ToData for NaiveDateTime {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::Date(*self)
+    }
+}
+
+impl ToData for Duration {
+    fn to_data(&self) -> OwnedData {
+        OwnedData::IntervalDS(*self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_offset, Data, FromData, OwnedData};
+    use chrono::{Duration, FixedOffset, TimeZone, Timelike, UTC};
+    use error::{self, ErrorKind};
+    use odpi::flags::{ODPINativeTypeNum, ODPIOracleTypeNum};
+    use odpi::structs::{ODPIData, ODPIDataValueUnion, ODPITimestamp};
+
+    #[test]
+    fn boolean() {
+        let mut odpi_data = ODPIData {
+            is_null: 0,
+            value: ODPIDataValueUnion { as_boolean: 1 },
+        };
+        let data: Data = (&mut odpi_data as *mut ODPIData).into();
+
+        let value = OwnedData::from_column(ODPIOracleTypeNum::Boolean,
+                                           ODPINativeTypeNum::Boolean,
+                                           &data)
+                .expect("badness");
+        assert_eq!(value, OwnedData::Boolean(true));
+        assert!(bool::from_data(&value).expect("badness"));
+
+        odpi_data.value.as_boolean = 0;
+        let data: Data = (&mut odpi_data as *mut ODPIData).into();
+        assert!(!data.as_boolean());
+    }
+
+    #[test]
+    fn checked_access() {
+        let mut odpi_data = ODPIData {
+            is_null: 0,
+            value: ODPIDataValueUnion { as_int_64: 42 },
+        };
+        let data: Data = (&mut odpi_data as *mut ODPIData).into();
+
+        assert_eq!(data.try_as_int64(ODPINativeTypeNum::Int64).expect("badness"), 42);
+        match data.try_as_double(ODPINativeTypeNum::Int64) {
+            Err(error::Error(ErrorKind::TypeMismatch(..), _)) => assert!(true),
+            _ => assert!(false),
+        }
+        match data.try_as_string(ODPINativeTypeNum::Int64) {
+            Err(error::Error(ErrorKind::TypeMismatch(..), _)) => assert!(true),
+            _ => assert!(false),
+        }
+        match data.try_as_int64(ODPINativeTypeNum::Double) {
+            Err(error::Error(ErrorKind::TypeMismatch(..), _)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        odpi_data.is_null = 1;
+        let data: Data = (&mut odpi_data as *mut ODPIData).into();
+        match data.try_as_int64(ODPINativeTypeNum::Int64) {
+            Err(error::Error(ErrorKind::NullValue, _)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn setters() {
+        let mut odpi_data = ODPIData {
+            is_null: 1,
+            value: ODPIDataValueUnion { as_int_64: 0 },
+        };
+        let data: Data = (&mut odpi_data as *mut ODPIData).into();
+        assert!(data.is_null());
+
+        data.set_int64(-7);
+        assert!(!data.is_null());
+        assert_eq!(data.as_int64(), -7);
+        data.set_uint64(7);
+        assert_eq!(data.as_uint64(), 7);
+        data.set_boolean(true);
+        assert!(data.as_boolean());
+        data.set_float(0.5);
+        assert_eq!(data.as_float(), 0.5);
+        data.set_double(2.25);
+        assert_eq!(data.as_double(), 2.25);
+
+        let name = "jozias";
+        data.set_bytes(name);
+        assert_eq!(data.as_string(), "jozias");
+
+        let ts = UTC.ymd(2017, 6, 1).and_hms_nano(12, 34, 56, 789);
+        data.set_timestamp(ts);
+        assert_eq!(data.as_utc(), Some(ts));
+        data.set_interval_ds(Duration::seconds(90061));
+        assert_eq!(data.as_duration(), Duration::seconds(90061));
+
+        data.set_null();
+        assert!(data.is_null());
+        assert_eq!(OwnedData::from_data(ODPINativeTypeNum::Int64, &data).expect("badness"),
+                   OwnedData::Null);
+    }
+
+    #[test]
+    fn offsets() {
+        assert_eq!(parse_offset("+02:00"), FixedOffset::east_opt(7200));
+        assert_eq!(parse_offset("-05:30"), FixedOffset::east_opt(-19800));
+        assert_eq!(parse_offset("UTC"), FixedOffset::east_opt(0));
+        assert_eq!(parse_offset("America/New_York"), None);
+        assert_eq!(parse_offset("+25:00"), None);
+    }
+
+    #[test]
+    fn local_timestamp() {
+        let mut odpi_data = ODPIData {
+            is_null: 0,
+            value: ODPIDataValueUnion {
+                as_timestamp: ODPITimestamp {
+                    year: 2017,
+                    month: 6,
+                    day: 1,
+                    hour: 12,
+                    minute: 34,
+                    second: 56,
+                    fsecond: 0,
+                    tz_hour_offset: 0,
+                    tz_minute_offset: 0,
+                },
+            },
+        };
+        let data: Data = (&mut odpi_data as *mut ODPIData).into();
+
+        let local = data.as_local_timestamp("+02:00").expect("badness");
+        assert_eq!(local.hour(), 12);
+        assert_eq!(local.naive_utc().hour(), 10);
+        match data.as_local_timestamp("Europe/Paris") {
+            Err(error::Error(ErrorKind::InvalidTimezone(_), _)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn zeroed_timestamp() {
+        let mut odpi_data = ODPIData {
+            is_null: 0,
+            value: ODPIDataValueUnion {
+                as_timestamp: ODPITimestamp {
+                    year: 0,
+                    month: 0,
+                    day: 0,
+                    hour: 0,
+                    minute: 0,
+                    second: 0,
+                    fsecond: 0,
+                    tz_hour_offset: 0,
+                    tz_minute_offset: 0,
+                },
+            },
+        };
+        let data: Data = (&mut odpi_data as *mut ODPIData).into();
+
+        assert!(data.as_utc().is_none());
+        assert!(data.as_naive_date().is_none());
+        match OwnedData::from_data(ODPINativeTypeNum::Timestamp, &data) {
+            Err(error::Error(ErrorKind::InvalidTimestamp, _)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+}
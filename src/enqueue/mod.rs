@@ -11,16 +11,21 @@
 //! are destroyed by releasing the last reference by calling the function
 //! `enqueue::Options::release()`.
 use error::{ErrorKind, Result};
-use odpi::{externs, flags};
+use odpi::{constants, externs, flags};
 use odpi::opaque::ODPIEnqOptions;
+use std::cell::Cell;
 use std::ptr;
-use util::ODPIStr;
+use util::{warn_on_drop, ODPIStr};
 
-/// Enqueue option handles are used to represent the options specified when enqueuing messages.
-#[derive(Clone)]
+/// Enqueue option handles are used to represent the options specified when enqueuing messages. An
+/// `Options` owns the reference to the ODPI-C enqueue options it was created with, and any added
+/// with `add_ref()`, and releases whatever references it still holds when it is dropped.
 pub struct Options {
     /// The ODPI-C EnqOptions pointer.
     inner: *mut ODPIEnqOptions,
+    /// The number of references to `inner` held by this handle: the one it was created with plus
+    /// those added by `add_ref()`, less those given up by `release()`.
+    refs: Cell<u32>,
 }
 
 impl Options {
@@ -34,7 +39,10 @@ impl Options {
     /// the handle was created.
     pub fn add_ref(&self) -> Result<()> {
         try_dpi!(externs::dpiEnqOptions_addRef(self.inner),
-                 Ok(()),
+                 {
+                     self.refs.set(self.refs.get() + 1);
+                     Ok(())
+                 },
                  ErrorKind::EnqOptions("dpiEnqOptions_addRef".to_string()))
     }
 
@@ -71,6 +79,7 @@ impl Options {
     /// options is maintained and when this count reaches zero, the memory associated with the
     /// options is freed.
     pub fn release(&self) -> Result<()> {
+        self.refs.set(self.refs.get().saturating_sub(1));
         try_dpi!(externs::dpiEnqOptions_release(self.inner),
                  Ok(()),
                  ErrorKind::EnqOptions("dpiEnqOptions_release".to_string()))
@@ -103,9 +112,43 @@ impl Options {
     }
 }
 
+impl Clone for Options {
+    /// Create another handle to the same ODPI-C enqueue options, owning a reference of its own.
+    fn clone(&self) -> Options {
+        let added = !self.inner.is_null() &&
+                    unsafe { externs::dpiEnqOptions_addRef(self.inner) } == constants::DPI_SUCCESS;
+        let refs = if added { 1 } else { 0 };
+        Options {
+            inner: self.inner,
+            refs: Cell::new(refs),
+        }
+    }
+}
+
+impl Drop for Options {
+    fn drop(&mut self) {
+        if self.inner.is_null() {
+            return;
+        }
+
+        for _ in 0..self.refs.get() {
+            if unsafe { externs::dpiEnqOptions_release(self.inner) } != constants::DPI_SUCCESS {
+                warn_on_drop("Failed to release the enqueue options on drop");
+                break;
+            }
+        }
+        self.refs.set(0);
+    }
+}
+
+/// Wrap an ODPI-C enqueue options, taking ownership of one reference to it. The reference is
+/// released when the handle is dropped, so the pointer must not be released by the caller as well.
 impl From<*mut ODPIEnqOptions> for Options {
     fn from(inner: *mut ODPIEnqOptions) -> Options {
-        Options { inner: inner }
+        Options {
+            inner: inner,
+            refs: Cell::new(1),
+        }
     }
 }
 
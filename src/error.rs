@@ -8,14 +8,20 @@
 
 //! `oci` errors
 use common::error::Info;
+use common::version::Feature;
 
 error_chain! {
     foreign_links {
         Nul(::std::ffi::NulError);
         EnvVar(::std::env::VarError);
+        Utf8(::std::string::FromUtf8Error);
     }
 
     errors {
+        ArrayPosition(pos: u32, num_elements: u32) {
+            description("The array position exceeds the number of elements allocated!")
+            display("Array position {} exceeds the {} elements allocated!", pos, num_elements)
+        }
         BranchId {
             description("The given batch id is longer than 64 bytes!")
             display("The given batch id is longer than 64 bytes!")
@@ -32,6 +38,14 @@ error_chain! {
             description("Failed to create the ODPI-C context!")
             display("Failed to create the ODPI-C context!")
         }
+        CredentialsRequired {
+            description("A heterogeneous pool requires credentials to acquire a connection!")
+            display("A heterogeneous pool requires credentials to acquire a connection!")
+        }
+        CursorColumn(pos: u32) {
+            description("The column holds a cursor, which cannot be copied into a row!")
+            display("Column {} is a cursor, read it with 'Statement::fetch_cursor()'!", pos)
+        }
         DeqOptions(fn_name: String) {
             description("DeqOptions: call to ODPI-C function failed!")
             display("DeqOptions: call to '{}' function failed!", fn_name)
@@ -48,10 +62,63 @@ error_chain! {
             description("LOB: call to ODPI-C function failed!")
             display("LOB: call to '{}' function failed!", fn_name)
         }
+        InvalidAttribute(name: String) {
+            description("The object type has no attribute with the given name!")
+            display("The object type has no attribute named '{}'!", name)
+        }
+        InvalidColumn(pos: u32) {
+            description("The given column position is not part of the row!")
+            display("Column {} is not part of the row!", pos)
+        }
+        InvalidDescriptorValue(value: String) {
+            description("The value cannot be used in a connect descriptor!")
+            display("'{}' cannot be used in a connect descriptor, as it contains '(', ')' or '='!",
+                    value)
+        }
+        InvalidFetchArraySize(size: u32, max: u32) {
+            description("The fetch array size is too large!")
+            display("A fetch array size of {} is larger than the maximum of {}!", size, max)
+        }
+        InvalidIdentifier(name: String) {
+            description("The name is not a valid identifier!")
+            display("'{}' is not a valid identifier!", name)
+        }
+        InvalidTimestamp {
+            description("The timestamp is not a valid date and time!")
+            display("The timestamp is not a valid date and time!")
+        }
+        InvalidTimezone(name: String) {
+            description("The time zone is not a fixed offset from UTC!")
+            display("'{}' is not a fixed offset from UTC!", name)
+        }
+        MissingKeyColumns {
+            description("At least one key column is required!")
+            display("At least one key column is required!")
+        }
         MsgProps(fn_name: String) {
             description("Context: call to ODPI-C function failed!")
             display("Context: call to '{}' function failed!", fn_name)
         }
+        NoDataFound {
+            description("The query returned no rows!")
+            display("The query returned no rows!")
+        }
+        NoRowidVar {
+            description("No variable of native type Rowid is bound to the statement!")
+            display("No variable of native type Rowid is bound to the statement!")
+        }
+        NullValue {
+            description("Expected a value but found NULL!")
+            display("Expected a value but found NULL!")
+        }
+        Object(fn_name: String) {
+            description("Object: call to ODPI-C function failed!")
+            display("Object: call to '{}' function failed!", fn_name)
+        }
+        ObjectAttr(fn_name: String) {
+            description("ObjectAttr: call to ODPI-C function failed!")
+            display("ObjectAttr: call to '{}' function failed!", fn_name)
+        }
         ObjectType(fn_name: String) {
             description("MsgProps: call to ODPI-C function failed!")
             display("MsgProps: call to '{}' function failed!", fn_name)
@@ -60,10 +127,26 @@ error_chain! {
             description("OCI Error!")
             display("OCI Error! {}", err)
         }
+        PlsqlNoDataFound(err: Info) {
+            description("PL/SQL raised NO_DATA_FOUND (ORA-01403)!")
+            display("PL/SQL raised NO_DATA_FOUND! {}", err)
+        }
         Pool(fn_name: String) {
             description("Pool: call to ODPI-C function failed!")
             display("Pool: call to '{}' function failed!", fn_name)
         }
+        ResourceBusy(err: Info) {
+            description("The resource is busy and NOWAIT was specified or the timeout expired!")
+            display("Resource busy! {}", err)
+        }
+        Rowid(fn_name: String) {
+            description("Rowid: call to ODPI-C function failed!")
+            display("Rowid: call to '{}' function failed!", fn_name)
+        }
+        RowidsAfterExecuteMany {
+            description("The affected rowids are not available after executing many times!")
+            display("The affected rowids are only available after a single execution!")
+        }
         Statement(fn_name: String) {
             description("Statement: call to ODPI-C function failed!")
             display("Statement: call to '{}' function failed!", fn_name)
@@ -72,13 +155,45 @@ error_chain! {
             description("Subscription: call to ODPI-C function failed!")
             display("Subscription: call to '{}' function failed!", fn_name)
         }
+        Truncation(column: u32, needed: u32, available: u32) {
+            description("The variable is too small to hold the values of the column!")
+            display("Column {} needs {} bytes but the variable only holds {}!",
+                    column,
+                    needed,
+                    available)
+        }
         TxnId {
             description("The given transaction id is longer than 64 bytes!")
             display("The given transaction id is longer than 64 bytes!")
         }
+        TypeMismatch(expected: String, found: String) {
+            description("The value cannot be converted to the requested type!")
+            display("Expected a value of type '{}' but found '{}'!", expected, found)
+        }
+        UnexpectedQuery {
+            description("The statement is a query but a non-query statement was expected!")
+            display("The statement is a query but a non-query statement was expected!")
+        }
+        Unsupported(feature: Feature, required_version: (u32, u32)) {
+            description("The feature is not supported by the client or server version!")
+            display("{:?} requires both client and server to be at version {}.{} or higher!",
+                    feature,
+                    required_version.0,
+                    required_version.1)
+        }
+        ValueCount(expected: u32, found: u32) {
+            description("The number of values does not match the number of columns!")
+            display("Expected {} values but found {}!", expected, found)
+        }
         Var(fn_name: String) {
             description("Var: call to ODPI-C function failed!")
             display("Var: call to '{}' function failed!", fn_name)
         }
+        VarTooSmall(needed: u32, allocated: u32) {
+            description("The destination variable has too few elements allocated!")
+            display("The destination variable needs {} elements but only {} are allocated!",
+                    needed,
+                    allocated)
+        }
     }
 }
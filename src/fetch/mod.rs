@@ -0,0 +1,160 @@
+// Copyright (c) 2017 oic developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Fetch buffers are used for tight loops fetching rows of the same shape from many statements.
+//! The variables holding a fetch array of each column are allocated once, when the fetch buffer is
+//! created by calling the function `FetchBuffer::new()`, and are defined on each executed
+//! statement by `attach()`, rather than being allocated implicitly by every statement.
+use connection::Connection;
+use data::OwnedData;
+use error::{ErrorKind, Result};
+use odpi::flags::{ODPINativeTypeNum, ODPIOracleTypeNum};
+use statement::Statement;
+use variable::Var;
+
+/// This structure holds the variables used to fetch the columns of a known layout, for reuse
+/// across statements.
+pub struct FetchBuffer {
+    /// One variable per column, in position order.
+    vars: Vec<Var>,
+    /// The number of rows each variable can hold.
+    array_size: u32,
+}
+
+impl FetchBuffer {
+    /// Allocate a variable for each column of the given layout.
+    ///
+    /// * `conn` - the connection to allocate the variables on.
+    /// * `columns` - the Oracle type, native type and size in bytes of each column, in position
+    /// order. The size is only used for string and binary columns.
+    /// * `array_size` - the number of rows each variable can hold. This is used as the fetch array
+    /// size of every statement the buffer is attached to.
+    pub fn new(conn: &Connection,
+               columns: &[(ODPIOracleTypeNum, ODPINativeTypeNum, u32)],
+               array_size: u32)
+               -> Result<FetchBuffer> {
+        let array_size = if array_size == 0 { 1 } else { array_size };
+        let mut vars = Vec::with_capacity(columns.len());
+
+        for &(oracle_type, native_type, size) in columns {
+            vars.push(conn.new_var(oracle_type, native_type, array_size, size, true, false)?);
+        }
+
+        Ok(FetchBuffer {
+               vars: vars,
+               array_size: array_size,
+           })
+    }
+
+    /// Defines the variables of the buffer on the given statement, which must be a query that has
+    /// already been executed. The fetch array size of the statement is set to the array size of
+    /// the buffer. Rows fetched afterwards are read with `get_value()`, using the buffer row index
+    /// returned by `Statement::fetch()`. An error is returned if the query does not have one column
+    /// for each variable.
+    ///
+    /// * `stmt` - the executed statement to fetch into the buffer.
+    pub fn attach(&self, stmt: &Statement) -> Result<()> {
+        let num_cols = stmt.get_num_query_columns()?;
+        if num_cols != self.vars.len() as u32 {
+            return Err(ErrorKind::ValueCount(self.vars.len() as u32, num_cols).into());
+        }
+
+        stmt.set_fetch_array_size(self.array_size)?;
+        for (idx, var) in self.vars.iter().enumerate() {
+            stmt.define(idx as u32 + 1, var)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of rows each variable can hold.
+    pub fn array_size(&self) -> u32 {
+        self.array_size
+    }
+
+    /// Returns an owned copy of the value of a column in the buffer.
+    ///
+    /// * `col` - the position of the column. The first position is 1.
+    /// * `buffer_row_index` - the buffer row index returned by `Statement::fetch()`.
+    pub fn get_value(&self, col: u32, buffer_row_index: u32) -> Result<OwnedData> {
+        if col == 0 {
+            return Err(ErrorKind::InvalidColumn(col).into());
+        }
+        match self.vars.get((col - 1) as usize) {
+            Some(var) => var.get_value(buffer_row_index),
+            None => Err(ErrorKind::InvalidColumn(col).into()),
+        }
+    }
+
+    /// Releases the variables of the buffer.
+    pub fn release(&self) -> Result<()> {
+        for var in &self.vars {
+            var.release()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FetchBuffer;
+    use data::OwnedData;
+    use error::{self, ErrorKind, Result};
+    use odpi::flags;
+    use odpi::flags::ODPINativeTypeNum::*;
+    use odpi::flags::ODPIOracleTypeNum::*;
+    use test::{ConnResult, CONN};
+
+    fn attach_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return Err(ErrorKind::Connection("CONN".to_string()).into()),
+        };
+        ::test::ensure_test_schema()?;
+
+        let buffer = FetchBuffer::new(conn, &[(Number, Int64, 0), (Varchar, Bytes, 256)], 10)?;
+        assert_eq!(buffer.array_size(), 10);
+
+        for _ in 0..2 {
+            let stmt = conn.prepare_stmt(Some("select id, username from username \
+                                               where username = 'jozias'"),
+                              None,
+                              false)?;
+            stmt.execute(flags::EXEC_DEFAULT)?;
+            buffer.attach(&stmt)?;
+
+            let (found, buffer_row_index) = stmt.fetch()?;
+            assert!(found);
+            assert_eq!(buffer.get_value(1, buffer_row_index)?, OwnedData::Int64(1));
+            assert_eq!(buffer.get_value(2, buffer_row_index)?,
+                       OwnedData::String("jozias".to_string()));
+            match buffer.get_value(3, buffer_row_index) {
+                Err(error::Error(ErrorKind::InvalidColumn(3), _)) => assert!(true),
+                _ => assert!(false),
+            }
+            stmt.close(None)?;
+        }
+
+        let stmt = conn.prepare_stmt(Some("select 1 from dual"), None, false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        match buffer.attach(&stmt) {
+            Err(error::Error(ErrorKind::ValueCount(2, 1), _)) => assert!(true),
+            _ => assert!(false),
+        }
+        stmt.close(None)?;
+        buffer.release()?;
+        Ok(())
+    }
+
+    #[test]
+    fn attach() {
+        match attach_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+}
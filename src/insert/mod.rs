@@ -0,0 +1,156 @@
+// Copyright (c) 2017 oic developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Prepared inserts are used for hot insert paths. The statement is prepared and the array
+//! variables holding a batch of rows are allocated once, when the prepared insert is created by
+//! calling the function `PreparedInsert::new()`. Rows are added with `add_row()` and inserted with
+//! a single call to `Statement::execute_many()` by `flush()`, after which the buffers are reused
+//! for the next batch.
+use connection::Connection;
+use data::OwnedData;
+use error::{ErrorKind, Result};
+use odpi::flags::{self, ODPINativeTypeNum, ODPIOracleTypeNum};
+use statement::Statement;
+use variable::Var;
+
+/// This structure represents an insert statement whose rows are accumulated in array variables and
+/// executed in batches.
+pub struct PreparedInsert {
+    /// The prepared insert statement.
+    stmt: Statement,
+    /// One array variable per placeholder, in position order.
+    vars: Vec<Var>,
+    /// The number of rows each variable can hold.
+    batch_size: u32,
+    /// The number of rows added since the last flush.
+    pending: u32,
+    /// The number of rows inserted by all flushes so far.
+    total: u64,
+}
+
+impl PreparedInsert {
+    /// Prepare the given insert statement and allocate a variable for each of its placeholders.
+    ///
+    /// * `conn` - the connection to prepare the statement on.
+    /// * `sql` - the insert statement, with one positional placeholder for each column, e.g.
+    /// `insert into username values (:1, :2)`.
+    /// * `columns` - the Oracle type, native type and size in bytes of each placeholder, in
+    /// position order. The size is only used for string and binary columns.
+    /// * `batch_size` - the number of rows accumulated before they are flushed automatically.
+    pub fn new(conn: &Connection,
+               sql: &str,
+               columns: &[(ODPIOracleTypeNum, ODPINativeTypeNum, u32)],
+               batch_size: u32)
+               -> Result<PreparedInsert> {
+        let batch_size = if batch_size == 0 { 1 } else { batch_size };
+        let stmt = conn.prepare_stmt(Some(sql), None, false)?;
+        let mut vars = Vec::with_capacity(columns.len());
+
+        for (idx, &(oracle_type, native_type, size)) in columns.iter().enumerate() {
+            let var = conn.new_var(oracle_type, native_type, batch_size, size, true, false)?;
+            stmt.bind_by_pos(idx as u32 + 1, &var)?;
+            vars.push(var);
+        }
+
+        Ok(PreparedInsert {
+               stmt: stmt,
+               vars: vars,
+               batch_size: batch_size,
+               pending: 0,
+               total: 0,
+           })
+    }
+
+    /// Adds a row to the current batch, flushing the batch first if it is full.
+    ///
+    /// * `values` - one value per placeholder, in position order.
+    pub fn add_row(&mut self, values: &[OwnedData]) -> Result<()> {
+        if values.len() != self.vars.len() {
+            return Err(ErrorKind::ValueCount(self.vars.len() as u32, values.len() as u32).into());
+        }
+        if self.pending == self.batch_size {
+            self.flush()?;
+        }
+
+        for (var, value) in self.vars.iter().zip(values) {
+            var.set_from_data(self.pending, value)?;
+        }
+        self.pending += 1;
+        Ok(())
+    }
+
+    /// Inserts the rows added since the last flush, returning the number of rows inserted. The
+    /// transaction is not committed.
+    pub fn flush(&mut self) -> Result<u32> {
+        let rows = self.pending;
+        if rows == 0 {
+            return Ok(0);
+        }
+
+        self.stmt.execute_many(flags::EXEC_DEFAULT, rows)?;
+        self.pending = 0;
+        self.total += rows as u64;
+        Ok(rows)
+    }
+
+    /// Returns the number of rows added since the last flush.
+    pub fn pending(&self) -> u32 {
+        self.pending
+    }
+
+    /// Returns the number of rows inserted by all flushes so far.
+    pub fn total_inserted(&self) -> u64 {
+        self.total
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PreparedInsert;
+    use data::OwnedData;
+    use error::{self, ErrorKind, Result};
+    use odpi::flags::ODPINativeTypeNum::*;
+    use odpi::flags::ODPIOracleTypeNum::*;
+    use rand::{self, Rng};
+
+    fn add_row_flush_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        let mut rng = rand::thread_rng();
+        let marker = format!("prepared_insert_{}", rng.gen::<u32>());
+
+        let mut insert = PreparedInsert::new(&conn,
+                                             "insert into username values (:1, :2)",
+                                             &[(Number, Int64, 0), (Varchar, Bytes, 256)],
+                                             128)?;
+        for id in 0..1000 {
+            insert.add_row(&[OwnedData::Int64(id), OwnedData::String(marker.clone())])?;
+        }
+        assert_eq!(insert.pending(), 1000 % 128);
+        insert.flush()?;
+        assert_eq!(insert.pending(), 0);
+        assert_eq!(insert.total_inserted(), 1000);
+
+        match insert.add_row(&[OwnedData::Int64(1)]) {
+            Err(error::Error(ErrorKind::ValueCount(2, 1), _)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        let sql = format!("select count(*) from username where username = '{}'", marker);
+        assert_eq!(conn.query_scalar::<i64>(&sql)?, Some(1000));
+        conn.rollback()?;
+        Ok(())
+    }
+
+    #[test]
+    fn add_row_flush() {
+        match add_row_flush_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+}
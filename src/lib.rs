@@ -9,7 +9,7 @@
 //! Rust bindings over the Oracle Database Programming Interface for Drivers and Applications.
 #![deny(missing_docs)]
 #![feature(untagged_unions)]
-#![recursion_limit="128"]
+#![recursion_limit="256"]
 #![cfg_attr(feature = "cargo-clippy", allow(unseparated_literal_suffix))]
 #[macro_use]
 extern crate bitflags;
@@ -36,12 +36,16 @@ pub mod dequeue;
 pub mod enqueue;
 #[allow(missing_docs)]
 pub mod error;
+pub mod fetch;
+pub mod insert;
 pub mod lob;
 pub mod message;
 pub mod object;
+pub mod objectattr;
 pub mod objecttype;
 pub mod pool;
 pub mod query;
+pub mod row;
 pub mod rowid;
 pub mod statement;
 pub mod subscription;
@@ -52,25 +56,37 @@ mod util;
 
 pub use odpi::{constants, flags};
 pub use odpi::structs::ODPIDataValueUnion as DataUnion;
+pub use util::{quote_ident, set_drop_logger};
 
 #[cfg(test)]
 mod test {
+    use connection::Connection;
     use context;
+    use context::params::PoolCreate;
     use error;
+    use odpi::flags;
+    use pool::Pool;
     use std::ffi::CString;
     use std::fs::File;
     use std::io::{BufRead, BufReader};
 
-    #[cfg(test)]
+    /// The connect string of the database the tests run against.
+    pub const CONN_STR: &'static str = "//oic.cbsnae86d3iv.us-east-2.rds.amazonaws.com/ORCL";
+
     pub enum ContextResult {
         Ok(context::Context),
         Err(error::Error),
     }
 
-    #[cfg(test)]
     unsafe impl Sync for ContextResult {}
 
-    #[cfg(test)]
+    pub enum ConnResult {
+        Ok(Connection),
+        Err(error::Error),
+    }
+
+    unsafe impl Sync for ConnResult {}
+
     lazy_static! {
         pub static ref ENC: CString = CString::new("UTF-8").expect("badness");
         pub static ref CREDS: Vec<String> = {
@@ -89,9 +105,17 @@ mod test {
                 Err(e) => ContextResult::Err(e),
             }
         };
+        /// The connection shared by the tests that only read. A rollback on it would also discard
+        /// the pending changes of other tests running in parallel.
+        pub static ref CONN: ConnResult = {
+            match connect() {
+                Ok(conn) => ConnResult::Ok(conn),
+                Err(e) => ConnResult::Err(e),
+            }
+        };
+        static ref SCHEMA: Option<String> = create_test_schema().err().map(|e| e.to_string());
     }
 
-    #[cfg(test)]
     pub fn error_info(e: error::Error) {
         use std::io::{self, Write};
         writeln!(io::stderr(), "{}", e).expect("badness");
@@ -104,4 +128,96 @@ mod test {
         writeln!(io::stderr(), "{}", ctxt_error).expect("badness");
         assert!(false);
     }
+
+    /// Open a connection of its own to the test database, for tests that change data, commit, run
+    /// DDL or otherwise must not share a connection with the tests running in parallel.
+    pub fn connect() -> error::Result<Connection> {
+        let ctxt = context()?;
+        let mut ccp = ctxt.init_common_create_params()?;
+        ccp.set_encoding(ENC.as_ptr());
+        ccp.set_nchar_encoding(ENC.as_ptr());
+        Connection::create(ctxt,
+                           Some(&CREDS[0]),
+                           Some(&CREDS[1]),
+                           Some(CONN_STR),
+                           Some(ccp),
+                           None)
+    }
+
+    /// Create a session pool on the test database with the given credentials, which may be left
+    /// out for a heterogeneous pool, and pool create parameters.
+    pub fn create_pool(username: Option<&str>,
+                       password: Option<&str>,
+                       pcp: Option<PoolCreate>)
+                       -> error::Result<Pool> {
+        let ctxt = context()?;
+        let mut ccp = ctxt.init_common_create_params()?;
+        ccp.set_encoding(ENC.as_ptr());
+        ccp.set_nchar_encoding(ENC.as_ptr());
+        Pool::create(ctxt, username, password, Some(CONN_STR), Some(ccp), pcp)
+    }
+
+    /// The context shared by the tests.
+    pub fn context() -> error::Result<&'static context::Context> {
+        match *CTXT {
+            ContextResult::Ok(ref ctxt) => Ok(ctxt),
+            ContextResult::Err(ref _e) => {
+                Err(error::ErrorKind::Connection("CONTEXT".to_string()).into())
+            }
+        }
+    }
+
+    /// Make sure the `username`, `rawdata`, `nclobdata` and `keyed` tables used by the tests, and
+    /// the `jozias` row, exist. They are created once per test run, on a connection of their own,
+    /// because DDL commits and would otherwise commit the pending rows of other tests.
+    pub fn ensure_test_schema() -> error::Result<()> {
+        match *SCHEMA {
+            None => Ok(()),
+            Some(ref msg) => {
+                Err(error::ErrorKind::Connection(format!("ensure_test_schema: {}", msg)).into())
+            }
+        }
+    }
+
+    /// Create the test schema objects that do not exist yet, for `ensure_test_schema()`.
+    fn create_test_schema() -> error::Result<()> {
+        let stmts = ["begin \
+                        execute immediate \
+                          'create table username (id number, username varchar2(256))'; \
+                      exception \
+                        when others then \
+                          if sqlcode != -955 then raise; end if; \
+                      end;",
+                     "begin \
+                        execute immediate 'create table rawdata (id number, data raw(100))'; \
+                      exception \
+                        when others then \
+                          if sqlcode != -955 then raise; end if; \
+                      end;",
+                     "begin \
+                        execute immediate 'create table nclobdata (id number, data nclob)'; \
+                      exception \
+                        when others then \
+                          if sqlcode != -955 then raise; end if; \
+                      end;",
+                     "begin \
+                        execute immediate \
+                          'create table keyed (id number primary key, name varchar2(256))'; \
+                      exception \
+                        when others then \
+                          if sqlcode != -955 then raise; end if; \
+                      end;",
+                     "insert into username (id, username) \
+                      select 1, 'jozias' from dual \
+                      where not exists (select 1 from username where username = 'jozias')"];
+
+        let conn = connect()?;
+        for sql in &stmts {
+            let stmt = conn.prepare_stmt(Some(sql), None, false)?;
+            stmt.execute(flags::EXEC_DEFAULT)?;
+            stmt.close(None)?;
+        }
+        conn.commit()?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)
+    }
 }
@@ -14,15 +14,21 @@
 //! calling the function `Lob::release()`. They are used for reading and writing data to the
 //! database in smaller pieces than is contained in the large object.
 use error::{ErrorKind, Result};
-use odpi::externs;
+use odpi::{constants, externs};
 use odpi::opaque::ODPILob;
+use std::cell::Cell;
 use std::ptr;
-use util::ODPIStr;
+use util::{warn_on_drop, ODPIStr};
 
-/// LOB handles are used to represent large objects (CLOB, BLOB, NCLOB, BFILE).
+/// LOB handles are used to represent large objects (CLOB, BLOB, NCLOB, BFILE). A `Lob` owns the
+/// reference to the ODPI-C LOB it was created with, and any added with `add_ref()`, and releases
+/// whatever references it still holds when it is dropped.
 pub struct Lob {
     /// The ODPI-C LOB pointer.
     inner: *mut ODPILob,
+    /// The number of references to `inner` held by this handle: the one it was created with plus
+    /// those added by `add_ref()`, less those given up by `release()`.
+    refs: Cell<u32>,
 }
 
 impl Lob {
@@ -35,7 +41,10 @@ impl Lob {
     /// needs to be maintained independently of the reference returned when the LOB was created.
     pub fn add_ref(&self) -> Result<()> {
         try_dpi!(externs::dpiLob_addRef(self.inner),
-                 Ok(()),
+                 {
+                     self.refs.set(self.refs.get() + 1);
+                     Ok(())
+                 },
                  ErrorKind::Lob("dpiLob_addRef".to_string()))
     }
 
@@ -153,22 +162,55 @@ impl Lob {
                  ErrorKind::Lob("dpiLob_openResource".to_string()))
     }
 
-    /// Reads data from the LOB at the specified offset into the provided buffer.
-    pub fn read_bytes(&self, offset: u64, length: u64) -> Result<Vec<i8>> {
-        let buffer = Vec::new();
-        let buf_ptr = buffer.as_ptr() as *mut i8;
-        let mut buf_len = length;
+    /// Reads the entire contents of the LOB. Character LOBs are returned in the encoding used for
+    /// CHAR (or NCHAR) data.
+    pub fn read_all(&self) -> Result<Vec<u8>> {
+        self.read_bytes(1, self.get_size()?)
+    }
 
-        try_dpi!(externs::dpiLob_readBytes(self.inner, offset, length, buf_ptr, &mut buf_len),
-                 Ok(buffer),
-                 ErrorKind::Lob("dpiLob_readBytes".to_string()))
+    /// Reads the entire contents of a character LOB as a string. ODPI-C reads NCLOBs in the
+    /// encoding used for NCHAR data and CLOBs in the encoding used for CHAR data, so the matching
+    /// encoding must be UTF-8, as set with `CommonCreate::set_nchar_encoding()` and
+    /// `CommonCreate::set_encoding()`. An error is returned if the contents are not valid UTF-8.
+    pub fn read_string(&self) -> Result<String> {
+        Ok(String::from_utf8(self.read_all()?)?)
+    }
+
+    /// Reads data from the LOB at the specified offset. Fewer bytes than requested are returned if
+    /// the end of the LOB is reached first. Character LOBs are returned in the encoding used for
+    /// CHAR (or NCHAR) data.
+    ///
+    /// * `offset` - the offset into the LOB data from which to start reading. The first position is
+    /// 1. For character LOBs this represents the number of characters from the beginning of the
+    /// LOB; for binary LOBS, this represents the number of bytes from the beginning of the LOB.
+    /// * `length` - the maximum amount of data to read. For character LOBs this value is in
+    /// characters; for binary LOBs this value is in bytes.
+    pub fn read_bytes(&self, offset: u64, length: u64) -> Result<Vec<u8>> {
+        if length == 0 {
+            return Ok(Vec::new());
+        }
+
+        let buf_size = self.get_buffer_size(length)?;
+        let mut buffer: Vec<u8> = vec![0; buf_size as usize];
+        let mut buf_len = buf_size;
 
+        try_dpi!(externs::dpiLob_readBytes(self.inner,
+                                           offset,
+                                           length,
+                                           buffer.as_mut_ptr() as *mut ::std::os::raw::c_char,
+                                           &mut buf_len),
+                 {
+                     buffer.truncate(buf_len as usize);
+                     Ok(buffer)
+                 },
+                 ErrorKind::Lob("dpiLob_readBytes".to_string()))
     }
 
     /// Releases a reference to the LOB. A count of the references to the LOB is maintained and when
     /// this count reaches zero, the memory associated with the LOB is freed. The LOB is also closed
     /// unless that has already taken place using the function `Lob:::close()`.
     pub fn release(&self) -> Result<()> {
+        self.refs.set(self.refs.get().saturating_sub(1));
         try_dpi!(externs::dpiLob_release(self.inner),
                  Ok(()),
                  ErrorKind::Lob("dpiLob_release".to_string()))
@@ -231,9 +273,30 @@ impl Lob {
     }
 }
 
+impl Drop for Lob {
+    fn drop(&mut self) {
+        if self.inner.is_null() {
+            return;
+        }
+
+        for _ in 0..self.refs.get() {
+            if unsafe { externs::dpiLob_release(self.inner) } != constants::DPI_SUCCESS {
+                warn_on_drop("Failed to release the LOB on drop");
+                break;
+            }
+        }
+        self.refs.set(0);
+    }
+}
+
+/// Wrap an ODPI-C LOB, taking ownership of one reference to it. The reference is released when the
+/// LOB is dropped, so the pointer must not be released by the caller as well.
 impl From<*mut ODPILob> for Lob {
     fn from(inner: *mut ODPILob) -> Lob {
-        Lob { inner: inner }
+        Lob {
+            inner: inner,
+            refs: Cell::new(1),
+        }
     }
 }
 
@@ -301,4 +364,35 @@ mod test {
             }
         }
     }
+
+    fn read_bytes_res() -> Result<()> {
+        let conn = ::test::connect()?;
+
+        let bytes: Vec<u8> = vec![0x00, 0x01, 0x7f, 0x80, 0xfe, 0xff];
+        let temp_lob = conn.new_temp_lob(Blob)?;
+        temp_lob.write_bytes(bytes.iter().map(|&b| b as i8).collect(), 1)?;
+
+        assert_eq!(temp_lob.read_bytes(1, bytes.len() as u64)?, bytes);
+        assert_eq!(temp_lob.read_bytes(3, 2)?, vec![0x7f, 0x80]);
+        assert_eq!(temp_lob.read_bytes(5, 100)?, vec![0xfe, 0xff]);
+        assert!(temp_lob.read_bytes(1, 0)?.is_empty());
+
+        temp_lob.release()?;
+        conn.close(DefaultClose, None)?;
+
+        Ok(())
+    }
+
+    #[test]
+    pub fn read_bytes() {
+        use std::io::{self, Write};
+
+        match read_bytes_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
 }
@@ -7,6 +7,11 @@
 // modified, or distributed except according to those terms.
 
 //! `oci` macros
+/// Evaluate `$ret` if the ODPI-C call `$code` returns `DPI_SUCCESS`, or return `$err` otherwise.
+/// ODPI-C 2 functions only return `DPI_SUCCESS` or `DPI_FAILURE`: OCI's success with info is
+/// reported as `DPI_SUCCESS` and the warning is discarded, so there is no third outcome to
+/// distinguish here. Warnings that matter, such as the compilation errors of a stored unit, are
+/// instead looked up on demand, as `Statement::last_warning()` does.
 macro_rules! try_dpi {
     ($code:expr, $ret:expr, $err:expr) => {{
         if unsafe { $code } == ::odpi::constants::DPI_SUCCESS {
@@ -164,3 +169,38 @@ macro_rules! with_stmt {
         }
     }}
 }
+
+#[cfg(test)]
+mod test {
+    use error::{self, ErrorKind, Result};
+    use odpi::constants::{DPI_FAILURE, DPI_SUCCESS};
+    use std::cell::Cell;
+    use std::os::raw::c_int;
+
+    unsafe fn status(code: c_int) -> c_int {
+        code
+    }
+
+    fn call(code: c_int, evaluated: &Cell<bool>) -> Result<u32> {
+        try_dpi!(status(code),
+                 {
+                     evaluated.set(true);
+                     Ok(1)
+                 },
+                 ErrorKind::Statement("status".to_string()))
+    }
+
+    #[test]
+    fn try_dpi() {
+        let evaluated = Cell::new(false);
+        assert_eq!(call(DPI_SUCCESS, &evaluated).expect("badness"), 1);
+        assert!(evaluated.get());
+
+        let evaluated = Cell::new(false);
+        match call(DPI_FAILURE, &evaluated) {
+            Err(error::Error(ErrorKind::Statement(ref name), _)) => assert_eq!(name, "status"),
+            _ => assert!(false),
+        }
+        assert!(!evaluated.get());
+    }
+}
@@ -10,17 +10,23 @@
 //! Message Properties
 use chrono::{DateTime, UTC};
 use error::{ErrorKind, Result};
-use odpi::{externs, flags};
+use odpi::{constants, externs, flags};
 use odpi::opaque::ODPIMsgProps;
 use odpi::structs::ODPITimestamp;
+use std::cell::Cell;
 use std::ptr;
-use util::ODPIStr;
+use util::{warn_on_drop, ODPIStr};
 
-/// ODPI-C Message Props wrapper.
-#[derive(Clone)]
+/// Message properties handles are used to represent the properties of messages that are enqueued
+/// and dequeued using advanced queuing. A `Properties` owns the reference to the ODPI-C message
+/// properties it was created with, and any added with `add_ref()`, and releases whatever
+/// references it still holds when it is dropped.
 pub struct Properties {
     /// The ODPI-C MsgProps pointer.
     inner: *mut ODPIMsgProps,
+    /// The number of references to `inner` held by this handle: the one it was created with plus
+    /// those added by `add_ref()`, less those given up by `release()`.
+    refs: Cell<u32>,
 }
 
 impl Properties {
@@ -34,7 +40,10 @@ impl Properties {
     /// returned when the handle was created.
     pub fn add_ref(&self) -> Result<()> {
         try_dpi!(externs::dpiMsgProps_addRef(self.inner),
-                 Ok(()),
+                 {
+                     self.refs.set(self.refs.get() + 1);
+                     Ok(())
+                 },
                  ErrorKind::MsgProps("dpiMsgProps_addRef".to_string()))
     }
 
@@ -157,6 +166,7 @@ impl Properties {
     /// properties is maintained and when this count reaches zero, the memory associated with the
     /// properties is freed.
     pub fn release(&self) -> Result<()> {
+        self.refs.set(self.refs.get().saturating_sub(1));
         try_dpi!(externs::dpiMsgProps_release(self.inner),
                  Ok(()),
                  ErrorKind::MsgProps("dpiMsgProps_release".to_string()))
@@ -227,9 +237,46 @@ impl Properties {
     }
 }
 
+impl Clone for Properties {
+    /// Create another handle to the same ODPI-C message properties, owning a reference of its own.
+    fn clone(&self) -> Properties {
+        let refs = if self.inner.is_null() ||
+                      unsafe { externs::dpiMsgProps_addRef(self.inner) } != constants::DPI_SUCCESS {
+            0
+        } else {
+            1
+        };
+        Properties {
+            inner: self.inner,
+            refs: Cell::new(refs),
+        }
+    }
+}
+
+impl Drop for Properties {
+    fn drop(&mut self) {
+        if self.inner.is_null() {
+            return;
+        }
+
+        for _ in 0..self.refs.get() {
+            if unsafe { externs::dpiMsgProps_release(self.inner) } != constants::DPI_SUCCESS {
+                warn_on_drop("Failed to release the message properties on drop");
+                break;
+            }
+        }
+        self.refs.set(0);
+    }
+}
+
+/// Wrap an ODPI-C message properties, taking ownership of one reference to it. The reference is
+/// released when the handle is dropped, so the pointer must not be released by the caller as well.
 impl From<*mut ODPIMsgProps> for Properties {
     fn from(inner: *mut ODPIMsgProps) -> Properties {
-        Properties { inner: inner }
+        Properties {
+            inner: inner,
+            refs: Cell::new(1),
+        }
     }
 }
 
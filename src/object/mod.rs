@@ -6,7 +6,6 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-//! [NOT IMPL]
 //! This structure represents instances of the types created by the SQL command CREATE OR REPLACE
 //! TYPE and is available by handle to a calling application or driver. An object is created by
 //! calling the function `ObjectType::createObject()` or by calling the function `Object::copy()`.
@@ -14,7 +13,13 @@
 //! Objects are destroyed when the last reference is released by calling the function
 //! `Object::release()`. All of the attributes of the structure `ODPIBaseType` are included in this
 //! structure in addition to the ones specific to this structure described below.
+use data::{Data, OwnedData};
+use error::{ErrorKind, Result};
+use objectattr::ObjectAttr;
+use odpi::{externs, flags};
 use odpi::opaque::ODPIObject;
+use odpi::structs::{ODPIData, ODPIDataValueUnion};
+use std::ptr;
 
 /// This structure represents instances of the types created by the SQL command CREATE OR REPLACE
 /// TYPE
@@ -30,6 +35,231 @@ impl Object {
     pub fn inner(&self) -> *mut ODPIObject {
         self.inner
     }
+
+    /// Adds a reference to the object. This is intended for situations where a reference to the
+    /// object needs to be maintained independently of the reference returned when the object was
+    /// created.
+    pub fn add_ref(&self) -> Result<()> {
+        try_dpi!(externs::dpiObject_addRef(self.inner),
+                 Ok(()),
+                 ErrorKind::Object("dpiObject_addRef".to_string()))
+    }
+
+    /// Sets the value of the element found at the end of the collection, extending the size of the
+    /// collection by one element.
+    ///
+    /// * `value` - the value which should be appended. Strings and byte strings are copied into the
+    /// object and do not need to be retained after this function call has completed.
+    pub fn append_element(&self, value: &OwnedData) -> Result<()> {
+        let (native_type, mut odpi_data) = value.to_odpi();
+
+        try_dpi!(externs::dpiObject_appendElement(self.inner, native_type, &mut odpi_data),
+                 Ok(()),
+                 ErrorKind::Object("dpiObject_appendElement".to_string()))
+    }
+
+    /// Appends an object to the end of the collection, extending the size of the collection by one
+    /// element. This is used to populate collections of objects or PL/SQL records.
+    ///
+    /// * `obj` - the object which should be appended. It must be an instance of the element type of
+    /// the collection.
+    pub fn append_object(&self, obj: &Object) -> Result<()> {
+        let mut odpi_data = ODPIData {
+            is_null: 0,
+            value: ODPIDataValueUnion { as_object: obj.inner },
+        };
+
+        try_dpi!(externs::dpiObject_appendElement(self.inner,
+                                                  flags::ODPINativeTypeNum::Object,
+                                                  &mut odpi_data),
+                 Ok(()),
+                 ErrorKind::Object("dpiObject_appendElement".to_string()))
+    }
+
+    /// Creates an independent copy of an object and returns a reference to the newly created
+    /// object. This reference should be released as soon as it is no longer needed.
+    pub fn copy(&self) -> Result<Object> {
+        let mut copied = ptr::null_mut();
+
+        try_dpi!(externs::dpiObject_copy(self.inner, &mut copied),
+                 Ok(copied.into()),
+                 ErrorKind::Object("dpiObject_copy".to_string()))
+    }
+
+    /// Deletes an element from the collection. Note that the position ordinals of the remaining
+    /// elements are not changed. The delete operation creates holes in the collection.
+    ///
+    /// * `index` - the index into the collection of the element which is to be deleted.
+    pub fn delete_element_by_index(&self, index: i32) -> Result<()> {
+        try_dpi!(externs::dpiObject_deleteElementByIndex(self.inner, index),
+                 Ok(()),
+                 ErrorKind::Object("dpiObject_deleteElementByIndex".to_string()))
+    }
+
+    /// Returns the value of one of the object's attributes, copied out of the object.
+    ///
+    /// * `attr` - the attribute which is to be retrieved. The attribute must belong to the same
+    /// type as the object; otherwise, an error is returned.
+    /// * `native_type` - the native type of the value that is to be returned, usually the default
+    /// native type reported by `ObjectAttr::get_info()`.
+    pub fn get_attribute_value(&self,
+                               attr: &ObjectAttr,
+                               native_type: flags::ODPINativeTypeNum)
+                               -> Result<OwnedData> {
+        let mut odpi_data = ODPIData {
+            is_null: 1,
+            value: ODPIDataValueUnion { as_int_64: 0 },
+        };
+
+        try_dpi!(externs::dpiObject_getAttributeValue(self.inner,
+                                                      attr.inner(),
+                                                      native_type,
+                                                      &mut odpi_data),
+                 OwnedData::from_data(native_type, &Data::from(&mut odpi_data as *mut ODPIData)),
+                 ErrorKind::Object("dpiObject_getAttributeValue".to_string()))
+    }
+
+    /// Returns whether an element exists at the specified index.
+    ///
+    /// * `index` - the index into the collection of the element which is to be checked.
+    pub fn get_element_exists_by_index(&self, index: i32) -> Result<bool> {
+        let mut exists = 0;
+
+        try_dpi!(externs::dpiObject_getElementExistsByIndex(self.inner, index, &mut exists),
+                 Ok(exists == 1),
+                 ErrorKind::Object("dpiObject_getElementExistsByIndex".to_string()))
+    }
+
+    /// Returns the value of the element found at the specified index, copied out of the
+    /// collection.
+    ///
+    /// * `index` - the index into the collection of the element which is to be retrieved.
+    /// * `native_type` - the native type of the value that is to be returned, usually the default
+    /// native type of the element type of the collection.
+    pub fn get_element_value_by_index(&self,
+                                      index: i32,
+                                      native_type: flags::ODPINativeTypeNum)
+                                      -> Result<OwnedData> {
+        let mut odpi_data = ODPIData {
+            is_null: 1,
+            value: ODPIDataValueUnion { as_int_64: 0 },
+        };
+
+        try_dpi!(externs::dpiObject_getElementValueByIndex(self.inner,
+                                                           index,
+                                                           native_type,
+                                                           &mut odpi_data),
+                 OwnedData::from_data(native_type, &Data::from(&mut odpi_data as *mut ODPIData)),
+                 ErrorKind::Object("dpiObject_getElementValueByIndex".to_string()))
+    }
+
+    /// Returns the first index used in a collection, or `None` if the collection is empty.
+    pub fn get_first_index(&self) -> Result<Option<i32>> {
+        let mut index = 0;
+        let mut exists = 0;
+
+        try_dpi!(externs::dpiObject_getFirstIndex(self.inner, &mut index, &mut exists),
+                 Ok(if exists == 1 { Some(index) } else { None }),
+                 ErrorKind::Object("dpiObject_getFirstIndex".to_string()))
+    }
+
+    /// Returns the last index used in a collection, or `None` if the collection is empty.
+    pub fn get_last_index(&self) -> Result<Option<i32>> {
+        let mut index = 0;
+        let mut exists = 0;
+
+        try_dpi!(externs::dpiObject_getLastIndex(self.inner, &mut index, &mut exists),
+                 Ok(if exists == 1 { Some(index) } else { None }),
+                 ErrorKind::Object("dpiObject_getLastIndex".to_string()))
+    }
+
+    /// Returns the next index used in a collection following the specified index, or `None` if
+    /// there is no next index.
+    ///
+    /// * `index` - the index after which the next index is to be determined. This does not have
+    /// to be an actual index in the collection.
+    pub fn get_next_index(&self, index: i32) -> Result<Option<i32>> {
+        let mut next = 0;
+        let mut exists = 0;
+
+        try_dpi!(externs::dpiObject_getNextIndex(self.inner, index, &mut next, &mut exists),
+                 Ok(if exists == 1 { Some(next) } else { None }),
+                 ErrorKind::Object("dpiObject_getNextIndex".to_string()))
+    }
+
+    /// Returns the previous index used in a collection preceding the specified index, or `None`
+    /// if there is no previous index.
+    ///
+    /// * `index` - the index before which the previous index is to be determined. This does not
+    /// have to be an actual index in the collection.
+    pub fn get_prev_index(&self, index: i32) -> Result<Option<i32>> {
+        let mut prev = 0;
+        let mut exists = 0;
+
+        try_dpi!(externs::dpiObject_getPrevIndex(self.inner, index, &mut prev, &mut exists),
+                 Ok(if exists == 1 { Some(prev) } else { None }),
+                 ErrorKind::Object("dpiObject_getPrevIndex".to_string()))
+    }
+
+    /// Returns the number of elements in a collection.
+    pub fn get_size(&self) -> Result<i32> {
+        let mut size = 0;
+
+        try_dpi!(externs::dpiObject_getSize(self.inner, &mut size),
+                 Ok(size),
+                 ErrorKind::Object("dpiObject_getSize".to_string()))
+    }
+
+    /// Releases a reference to the object. A count of the references to the object is maintained
+    /// and when this count reaches zero, the memory associated with the object is freed.
+    pub fn release(&self) -> Result<()> {
+        try_dpi!(externs::dpiObject_release(self.inner),
+                 Ok(()),
+                 ErrorKind::Object("dpiObject_release".to_string()))
+    }
+
+    /// Sets the value of one of the object's attributes.
+    ///
+    /// * `attr` - the attribute which is to be set. The attribute must belong to the same type as
+    /// the object; otherwise, an error is returned.
+    /// * `value` - the value which should be set. Strings and byte strings are copied into the
+    /// object and do not need to be retained after this function call has completed.
+    pub fn set_attribute_value(&self, attr: &ObjectAttr, value: &OwnedData) -> Result<()> {
+        let (native_type, mut odpi_data) = value.to_odpi();
+
+        try_dpi!(externs::dpiObject_setAttributeValue(self.inner,
+                                                      attr.inner(),
+                                                      native_type,
+                                                      &mut odpi_data),
+                 Ok(()),
+                 ErrorKind::Object("dpiObject_setAttributeValue".to_string()))
+    }
+
+    /// Sets the value of the element found at the specified index.
+    ///
+    /// * `index` - the index into the collection of the element which is to be set.
+    /// * `value` - the value which should be set. Strings and byte strings are copied into the
+    /// object and do not need to be retained after this function call has completed.
+    pub fn set_element_value_by_index(&self, index: i32, value: &OwnedData) -> Result<()> {
+        let (native_type, mut odpi_data) = value.to_odpi();
+
+        try_dpi!(externs::dpiObject_setElementValueByIndex(self.inner,
+                                                           index,
+                                                           native_type,
+                                                           &mut odpi_data),
+                 Ok(()),
+                 ErrorKind::Object("dpiObject_setElementValueByIndex".to_string()))
+    }
+
+    /// Trims a number of elements from the end of a collection.
+    ///
+    /// * `num_to_trim` - the number of elements to trim from the end of the collection. If the
+    /// number of elements to trim exceeds the current size of the collection an error is returned.
+    pub fn trim(&self, num_to_trim: u32) -> Result<()> {
+        try_dpi!(externs::dpiObject_trim(self.inner, num_to_trim),
+                 Ok(()),
+                 ErrorKind::Object("dpiObject_trim".to_string()))
+    }
 }
 
 impl From<*mut ODPIObject> for Object {
@@ -37,3 +267,99 @@ impl From<*mut ODPIObject> for Object {
         Object { inner: inner }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use data::OwnedData;
+    use error::Result;
+    use odpi::flags::ODPINativeTypeNum::*;
+
+    fn attributes_res() -> Result<()> {
+        // DDL commits, so it must not share a connection with other tests.
+        let conn = ::test::connect()?;
+        conn.run_script("create or replace type mimir_point_t as object \
+                         (x number, label varchar2(30))\n/\n")?;
+
+        let point_type = conn.get_object_type("MIMIR_POINT_T")?;
+        let attrs = point_type.get_attributes()?;
+        let point = point_type.create_object()?;
+        point.set_attribute_value(&attrs[0], &OwnedData::Double(1.5))?;
+        point.set_attribute_value(&attrs[1], &OwnedData::String("origin".to_string()))?;
+
+        let copy = point.copy()?;
+        point.set_attribute_value(&attrs[1], &OwnedData::String("moved".to_string()))?;
+        assert_eq!(point.get_attribute_value(&attrs[0], Double)?,
+                   OwnedData::Double(1.5));
+        assert_eq!(point.get_attribute_value(&attrs[1], Bytes)?,
+                   OwnedData::String("moved".to_string()));
+        assert_eq!(copy.get_attribute_value(&attrs[1], Bytes)?,
+                   OwnedData::String("origin".to_string()));
+
+        copy.release()?;
+        point.release()?;
+        for attr in &attrs {
+            attr.release()?;
+        }
+        point_type.release()?;
+        Ok(())
+    }
+
+    #[test]
+    fn attributes() {
+        match attributes_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn elements_res() -> Result<()> {
+        // DDL commits, so it must not share a connection with other tests.
+        let conn = ::test::connect()?;
+        conn.run_script("create or replace type mimir_num_tab_t as table of number\n/\n")?;
+
+        let tab_type = conn.get_object_type("MIMIR_NUM_TAB_T")?;
+        let tab = tab_type.create_object()?;
+        assert_eq!(tab.get_first_index()?, None);
+        for value in &[10.0, 20.0, 30.0, 40.0] {
+            tab.append_element(&OwnedData::Double(*value))?;
+        }
+        assert_eq!(tab.get_size()?, 4);
+
+        let first = tab.get_first_index()?.expect("expected a first index");
+        let last = tab.get_last_index()?.expect("expected a last index");
+        assert_eq!(tab.get_element_value_by_index(first, Double)?,
+                   OwnedData::Double(10.0));
+        assert_eq!(tab.get_element_value_by_index(last, Double)?,
+                   OwnedData::Double(40.0));
+
+        tab.set_element_value_by_index(first, &OwnedData::Double(15.0))?;
+        assert_eq!(tab.get_element_value_by_index(first, Double)?,
+                   OwnedData::Double(15.0));
+
+        let second = tab.get_next_index(first)?.expect("expected a next index");
+        tab.delete_element_by_index(second)?;
+        assert!(!tab.get_element_exists_by_index(second)?);
+        let third = tab.get_next_index(first)?.expect("expected a next index");
+        assert_eq!(tab.get_element_value_by_index(third, Double)?,
+                   OwnedData::Double(30.0));
+        assert_eq!(tab.get_prev_index(third)?, Some(first));
+        assert_eq!(tab.get_prev_index(first)?, None);
+        assert_eq!(tab.get_next_index(last)?, None);
+
+        tab.trim(1)?;
+        assert!(!tab.get_element_exists_by_index(last)?);
+        assert_eq!(tab.get_last_index()?, Some(third));
+
+        tab.release()?;
+        tab_type.release()?;
+        Ok(())
+    }
+
+    #[test]
+    fn elements() {
+        match elements_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+}
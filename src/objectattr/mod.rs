@@ -0,0 +1,94 @@
+// Copyright (c) 2017 oic developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Object attribute handles are used to represent the attributes of types such as those created by
+//! the SQL command CREATE OR REPLACE TYPE. They are created by calling the function
+//! `ObjectType::get_attributes()` and are destroyed when the last reference is released by calling
+//! the function `ObjectAttr::release()`.
+use error::{ErrorKind, Result};
+use odpi::{externs, flags};
+use odpi::opaque::ODPIObjectAttr;
+use odpi::structs::ODPIObjectAttrInfo;
+use util::ODPIStr;
+
+/// Object attribute handles are used to represent the attributes of types such as those created by
+/// the SQL command CREATE OR REPLACE TYPE.
+pub struct ObjectAttr {
+    /// A pointer to the opaque `ODPIObjectAttr`.
+    inner: *mut ODPIObjectAttr,
+}
+
+impl ObjectAttr {
+    /// Get the pointer to the inner ODPI struct.
+    #[doc(hidden)]
+    pub fn inner(&self) -> *mut ODPIObjectAttr {
+        self.inner
+    }
+
+    /// Returns information about the attribute.
+    pub fn get_info(&self) -> Result<Info> {
+        let mut info: ODPIObjectAttrInfo = Default::default();
+
+        try_dpi!(externs::dpiObjectAttr_getInfo(self.inner, &mut info),
+                 Ok(Info::new(info)),
+                 ErrorKind::ObjectAttr("dpiObjectAttr_getInfo".to_string()))
+    }
+
+    /// Releases a reference to the attribute. A count of the references to the attribute is
+    /// maintained and when this count reaches zero, the memory associated with the attribute is
+    /// freed.
+    pub fn release(&self) -> Result<()> {
+        try_dpi!(externs::dpiObjectAttr_release(self.inner),
+                 Ok(()),
+                 ErrorKind::ObjectAttr("dpiObjectAttr_release".to_string()))
+    }
+}
+
+impl From<*mut ODPIObjectAttr> for ObjectAttr {
+    fn from(inner: *mut ODPIObjectAttr) -> ObjectAttr {
+        ObjectAttr { inner: inner }
+    }
+}
+
+/// This structure is used for passing information about an object type's attribute from ODPI-C.
+/// It is populated by the function `ObjectAttr::get_info()`.
+pub struct Info {
+    /// The ODPI-C object attribute info struct.
+    inner: ODPIObjectAttrInfo,
+}
+
+impl Info {
+    /// Create a new `Info` struct.
+    pub fn new(inner: ODPIObjectAttrInfo) -> Info {
+        Info { inner: inner }
+    }
+
+    /// Get the `name` value.
+    ///
+    /// Specifies the name of the attribute, as a string in the encoding used for CHAR data.
+    pub fn name(&self) -> String {
+        let name_s = ODPIStr::new(self.inner.name, self.inner.name_length);
+        name_s.into()
+    }
+
+    /// Get the `oracle_type_num` value.
+    ///
+    /// Specifies the type of the attribute. It will be one of the values from the enumeration
+    /// `ODPIOracleTypeNum`.
+    pub fn oracle_type_num(&self) -> flags::ODPIOracleTypeNum {
+        self.inner.oracle_type_num
+    }
+
+    /// Get the `default_native_type_num` value.
+    ///
+    /// Specifies the default native type for the attribute. It will be one of the values from the
+    /// enumeration `ODPINativeTypeNum`.
+    pub fn default_native_type_num(&self) -> flags::ODPINativeTypeNum {
+        self.inner.default_native_type_num
+    }
+}
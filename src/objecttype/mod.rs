@@ -6,7 +6,6 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-//! [NOT IMPL]
 //! Object type handles are used to represent types such as those created by the SQL command CREATE
 //! OR REPLACE TYPE. They are created using the function `Connection::get_object_type()` or
 //! implicitly when fetching from a column containing objects by calling the function
@@ -14,9 +13,15 @@
 //! another object by calling the function `ObjectAttribute::get_info()` or as the element type of a
 //! collection by calling the function `ObjectType::get_info()`. They are destroyed when the last
 //! reference is released by calling the function `ObjectType::release()`.
+use data::OwnedData;
 use error::{ErrorKind, Result};
-use odpi::externs;
+use object::Object;
+use objectattr::ObjectAttr;
+use odpi::{externs, flags};
 use odpi::opaque::ODPIObjectType;
+use odpi::structs::ODPIObjectTypeInfo;
+use std::ptr;
+use util::ODPIStr;
 
 /// Object type handles are used to represent types such as those created by the SQL command CREATE
 /// OR REPLACE TYPE.
@@ -40,6 +45,144 @@ impl ObjectType {
                  Ok(()),
                  ErrorKind::ObjectType("dpiObjectType_addRef".to_string()))
     }
+
+    /// Creates an object of the specified type and returns a reference to it. This reference
+    /// should be released as soon as it is no longer needed.
+    pub fn create_object(&self) -> Result<Object> {
+        let mut obj_ptr = ptr::null_mut();
+
+        try_dpi!(externs::dpiObjectType_createObject(self.inner, &mut obj_ptr),
+                 Ok(obj_ptr.into()),
+                 ErrorKind::ObjectType("dpiObjectType_createObject".to_string()))
+    }
+
+    /// Creates an object of the specified type and sets the named attributes to the given values.
+    /// Names are matched against the attributes returned by `get_attributes()` without regard to
+    /// ASCII case. Attributes that are not named are left NULL. An error is returned, and the
+    /// object released, if a name is not an attribute of the type
+    /// (`ErrorKind::InvalidAttribute`) or a value cannot be converted to the type of its
+    /// attribute (`ErrorKind::TypeMismatch`). Any other failure to set a value or to release an
+    /// attribute is returned as is, again after releasing the object.
+    ///
+    /// * `attrs` - the attribute names and the values to set them to.
+    pub fn create_from(&self, attrs: &[(&str, OwnedData)]) -> Result<Object> {
+        let type_attrs = self.get_attributes()?;
+        let obj = self.create_object()?;
+
+        let mut res = set_attributes(&obj, &type_attrs, attrs);
+        for attr in &type_attrs {
+            let released = attr.release();
+            if res.is_ok() {
+                res = released;
+            }
+        }
+        match res {
+            Ok(()) => Ok(obj),
+            Err(e) => {
+                // The first error is the one worth reporting.
+                let _ = obj.release();
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns the list of attributes that belong to the object type, in the order in which they
+    /// were declared. A reference is returned for each attribute and each should be released as
+    /// soon as it is no longer needed.
+    pub fn get_attributes(&self) -> Result<Vec<ObjectAttr>> {
+        let num_attributes = self.num_attributes()?;
+        if num_attributes == 0 {
+            return Ok(Vec::new());
+        }
+        let mut attrs = vec![ptr::null_mut(); num_attributes as usize];
+
+        try_dpi!(externs::dpiObjectType_getAttributes(self.inner,
+                                                      num_attributes,
+                                                      attrs.as_mut_ptr()),
+                 Ok(attrs.into_iter().map(ObjectAttr::from).collect()),
+                 ErrorKind::ObjectType("dpiObjectType_getAttributes".to_string()))
+    }
+
+    /// Returns information about the object type.
+    pub fn get_info(&self) -> Result<Info> {
+        let mut info: ODPIObjectTypeInfo = Default::default();
+
+        try_dpi!(externs::dpiObjectType_getInfo(self.inner, &mut info),
+                 Ok(Info::new(info)),
+                 ErrorKind::ObjectType("dpiObjectType_getInfo".to_string()))
+    }
+
+    /// Returns the number of attributes that the object type has.
+    fn num_attributes(&self) -> Result<u16> {
+        Ok(self.get_info()?.num_attributes())
+    }
+
+    /// Releases a reference to the object type. A count of the references to the object type is
+    /// maintained and when this count reaches zero, the memory associated with the object type is
+    /// freed.
+    pub fn release(&self) -> Result<()> {
+        try_dpi!(externs::dpiObjectType_release(self.inner),
+                 Ok(()),
+                 ErrorKind::ObjectType("dpiObjectType_release".to_string()))
+    }
+}
+
+/// Set each named value on the matching attribute of the object.
+fn set_attributes(obj: &Object,
+                  type_attrs: &[ObjectAttr],
+                  attrs: &[(&str, OwnedData)])
+                  -> Result<()> {
+    let mut infos = Vec::with_capacity(type_attrs.len());
+    for attr in type_attrs {
+        infos.push(attr.get_info()?);
+    }
+
+    for &(name, ref value) in attrs {
+        let upper = name.to_uppercase();
+        let idx = match infos.iter().position(|info| info.name().to_uppercase() == upper) {
+            Some(idx) => idx,
+            None => return Err(ErrorKind::InvalidAttribute(name.to_string()).into()),
+        };
+        let oracle_type = infos[idx].oracle_type_num();
+        if !accepts(oracle_type, value) {
+            return Err(ErrorKind::TypeMismatch(format!("{:?}", oracle_type),
+                                               value.type_name().to_string())
+                               .into());
+        }
+        obj.set_attribute_value(&type_attrs[idx], value)?;
+    }
+    Ok(())
+}
+
+/// Can a value of the given variant be converted to the given Oracle type by
+/// `dpiObject_setAttributeValue()`? These are the conversions ODPI-C 2.0 implements for object
+/// attributes; NULL can be set on any attribute.
+fn accepts(oracle_type: flags::ODPIOracleTypeNum, value: &OwnedData) -> bool {
+    use odpi::flags::ODPIOracleTypeNum::*;
+
+    match (oracle_type, value) {
+        (_, &OwnedData::Null) => true,
+        (Varchar, &OwnedData::String(_)) |
+        (NVarchar, &OwnedData::String(_)) |
+        (Char, &OwnedData::String(_)) |
+        (NChar, &OwnedData::String(_)) |
+        (Raw, &OwnedData::Bytes(_)) |
+        (NativeFloat, &OwnedData::Float(_)) |
+        (NativeDouble, &OwnedData::Double(_)) |
+        (NativeInt, &OwnedData::Int64(_)) |
+        (Number, &OwnedData::Int64(_)) |
+        (Number, &OwnedData::Uint64(_)) |
+        (Number, &OwnedData::Double(_)) |
+        (Number, &OwnedData::String(_)) |
+        (Date, &OwnedData::Date(_)) |
+        (Date, &OwnedData::Timestamp(_)) |
+        (Timestamp, &OwnedData::Date(_)) |
+        (Timestamp, &OwnedData::Timestamp(_)) |
+        (TimestampTz, &OwnedData::Timestamp(_)) |
+        (TimestampLtz, &OwnedData::Timestamp(_)) |
+        (Boolean, &OwnedData::Boolean(_)) => true,
+        _ => false,
+    }
 }
 
 impl From<*mut ODPIObjectType> for ObjectType {
@@ -47,3 +190,178 @@ impl From<*mut ODPIObjectType> for ObjectType {
         ObjectType { inner: oot }
     }
 }
+
+/// This structure is used for passing information about an object type from ODPI-C. It is
+/// populated by the function `ObjectType::get_info()`.
+pub struct Info {
+    /// The ODPI-C object type info struct.
+    inner: ODPIObjectTypeInfo,
+}
+
+impl Info {
+    /// Create a new `Info` struct.
+    pub fn new(inner: ODPIObjectTypeInfo) -> Info {
+        Info { inner: inner }
+    }
+
+    /// Get the `schema` value.
+    ///
+    /// Specifies the schema which owns the object type, as a string in the encoding used for CHAR
+    /// data.
+    pub fn schema(&self) -> String {
+        let schema_s = ODPIStr::new(self.inner.schema, self.inner.schema_length);
+        schema_s.into()
+    }
+
+    /// Get the `name` value.
+    ///
+    /// Specifies the name of the object type, as a string in the encoding used for CHAR data.
+    pub fn name(&self) -> String {
+        let name_s = ODPIStr::new(self.inner.name, self.inner.name_length);
+        name_s.into()
+    }
+
+    /// Get the `is_collection` value.
+    ///
+    /// Specifies if the object type is a collection or not.
+    pub fn is_collection(&self) -> bool {
+        self.inner.is_collection == 1
+    }
+
+    /// Get the `element_oracle_type_num` value.
+    ///
+    /// Specifies the type of the elements of the collection. This value is only populated if the
+    /// object type is a collection.
+    pub fn element_oracle_type_num(&self) -> flags::ODPIOracleTypeNum {
+        self.inner.element_oracle_type_num
+    }
+
+    /// Get the `element_default_native_type_num` value.
+    ///
+    /// Specifies the default native type for the elements of the collection. This value is only
+    /// populated if the object type is a collection.
+    pub fn element_default_native_type_num(&self) -> flags::ODPINativeTypeNum {
+        self.inner.element_default_native_type_num
+    }
+
+    /// Get the `element_object_type` value.
+    ///
+    /// Specifies the type of the elements of the collection, if they are objects. Otherwise this
+    /// value is `None`. The info struct only borrows the element type from its collection type, so
+    /// a reference is added to it here and the returned object type should be released with
+    /// `ObjectType::release()` when it is no longer needed.
+    pub fn element_object_type(&self) -> Result<Option<ObjectType>> {
+        if self.inner.element_object_type.is_null() {
+            return Ok(None);
+        }
+
+        let elem_type: ObjectType = self.inner.element_object_type.into();
+        elem_type.add_ref()?;
+        Ok(Some(elem_type))
+    }
+
+    /// Get the `num_attributes` value.
+    ///
+    /// Specifies the number of attributes that the object type has.
+    pub fn num_attributes(&self) -> u16 {
+        self.inner.num_attributes
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use data::OwnedData;
+    use error::{self, ErrorKind, Result};
+    use test::CREDS;
+
+    fn create_from_res() -> Result<()> {
+        // DDL commits, so it must not share a connection with other tests.
+        let conn = ::test::connect()?;
+        let stmt = conn.prepare_stmt(Some("create or replace type mimir_pair_t as object \
+                                           (id number, name varchar2(30))"),
+                          None,
+                          false)?;
+        stmt.execute(::odpi::flags::EXEC_DEFAULT)?;
+        stmt.close(None)?;
+
+        let pair_type = conn.get_object_type("MIMIR_PAIR_T")?;
+        let obj = pair_type.create_from(&[("id", OwnedData::Int64(7)),
+                                          ("NAME", OwnedData::String("jozias".to_string()))])?;
+
+        let attrs = pair_type.get_attributes()?;
+        let id_info = attrs[0].get_info()?;
+        let name_info = attrs[1].get_info()?;
+        assert_eq!(obj.get_attribute_value(&attrs[0], id_info.default_native_type_num())?,
+                   OwnedData::Double(7.0));
+        assert_eq!(obj.get_attribute_value(&attrs[1], name_info.default_native_type_num())?,
+                   OwnedData::String("jozias".to_string()));
+        obj.release()?;
+
+        match pair_type.create_from(&[("missing", OwnedData::Int64(1))]) {
+            Err(error::Error(ErrorKind::InvalidAttribute(ref name), _)) => {
+                assert_eq!(name, "missing")
+            }
+            _ => assert!(false),
+        }
+        match pair_type.create_from(&[("id", OwnedData::Boolean(true))]) {
+            Err(error::Error(ErrorKind::TypeMismatch(..), _)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        for attr in &attrs {
+            attr.release()?;
+        }
+        pair_type.release()?;
+        Ok(())
+    }
+
+    #[test]
+    fn create_from() {
+        match create_from_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn get_info_res() -> Result<()> {
+        // DDL commits, so it must not share a connection with other tests.
+        let conn = ::test::connect()?;
+        conn.run_script("create or replace type mimir_info_t as object \
+                         (id number, name varchar2(30))\n/\n\
+                         create or replace type mimir_info_tab_t as table of mimir_info_t\n/\n")?;
+
+        let info_type = conn.get_object_type("MIMIR_INFO_T")?;
+        let info = info_type.get_info()?;
+        assert_eq!(info.schema(), CREDS[0].to_uppercase());
+        assert_eq!(info.name(), "MIMIR_INFO_T");
+        assert!(!info.is_collection());
+        assert!(info.element_object_type()?.is_none());
+        assert_eq!(info.num_attributes(), 2);
+
+        let tab_type = conn.get_object_type("MIMIR_INFO_TAB_T")?;
+        let tab_info = tab_type.get_info()?;
+        assert_eq!(tab_info.name(), "MIMIR_INFO_TAB_T");
+        assert!(tab_info.is_collection());
+        assert_eq!(tab_info.element_oracle_type_num(), ::odpi::flags::ODPIOracleTypeNum::Object);
+        assert_eq!(tab_info.num_attributes(), 0);
+        match tab_info.element_object_type()? {
+            Some(elem_type) => {
+                assert_eq!(elem_type.get_info()?.name(), "MIMIR_INFO_T");
+                elem_type.release()?;
+            }
+            None => assert!(false),
+        }
+
+        tab_type.release()?;
+        info_type.release()?;
+        Ok(())
+    }
+
+    #[test]
+    fn get_info() {
+        match get_info_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+}
@@ -19,3 +19,5 @@ pub const DPI_SUCCESS: c_int = 0;
 pub const DPI_FAILURE: c_int = -1;
 /// Used by dequeue set wait.
 pub const DPI_DEQ_WAIT_NO_WAIT: c_int = 0;
+/// The default array size used for performing fetches.
+pub const DPI_DEFAULT_FETCH_ARRAY_SIZE: c_uint = 100;
@@ -108,9 +108,9 @@ extern "C" {
                                    value: *mut *const ::std::os::raw::c_char,
                                    valueLength: *mut u32)
                                    -> ::std::os::raw::c_int;
-    // pub fn dpiConn_getHandle(conn: *mut opaque::ODPIConn,
-    //                          handle: *mut *mut ::std::os::raw::c_void)
-    //                          -> ::std::os::raw::c_int;
+    pub fn dpiConn_getHandle(conn: *mut opaque::ODPIConn,
+                             handle: *mut *mut ::std::os::raw::c_void)
+                             -> ::std::os::raw::c_int;
     pub fn dpiConn_getInternalName(conn: *mut opaque::ODPIConn,
                                    value: *mut *const ::std::os::raw::c_char,
                                    valueLength: *mut u32)
@@ -419,8 +419,85 @@ extern "C" {
                                    -> ::std::os::raw::c_int;
 }
 
+extern "C" {
+    pub fn dpiObject_addRef(obj: *mut opaque::ODPIObject) -> ::std::os::raw::c_int;
+    pub fn dpiObject_appendElement(obj: *mut opaque::ODPIObject,
+                                   nativeTypeNum: flags::ODPINativeTypeNum,
+                                   value: *mut structs::ODPIData)
+                                   -> ::std::os::raw::c_int;
+    pub fn dpiObject_copy(obj: *mut opaque::ODPIObject,
+                          copiedObj: *mut *mut opaque::ODPIObject)
+                          -> ::std::os::raw::c_int;
+    pub fn dpiObject_deleteElementByIndex(obj: *mut opaque::ODPIObject,
+                                          index: i32)
+                                          -> ::std::os::raw::c_int;
+    pub fn dpiObject_getAttributeValue(obj: *mut opaque::ODPIObject,
+                                       attr: *mut opaque::ODPIObjectAttr,
+                                       nativeTypeNum: flags::ODPINativeTypeNum,
+                                       value: *mut structs::ODPIData)
+                                       -> ::std::os::raw::c_int;
+    pub fn dpiObject_getElementExistsByIndex(obj: *mut opaque::ODPIObject,
+                                             index: i32,
+                                             exists: *mut ::std::os::raw::c_int)
+                                             -> ::std::os::raw::c_int;
+    pub fn dpiObject_getElementValueByIndex(obj: *mut opaque::ODPIObject,
+                                            index: i32,
+                                            nativeTypeNum: flags::ODPINativeTypeNum,
+                                            value: *mut structs::ODPIData)
+                                            -> ::std::os::raw::c_int;
+    pub fn dpiObject_getFirstIndex(obj: *mut opaque::ODPIObject,
+                                   index: *mut i32,
+                                   exists: *mut ::std::os::raw::c_int)
+                                   -> ::std::os::raw::c_int;
+    pub fn dpiObject_getLastIndex(obj: *mut opaque::ODPIObject,
+                                  index: *mut i32,
+                                  exists: *mut ::std::os::raw::c_int)
+                                  -> ::std::os::raw::c_int;
+    pub fn dpiObject_getNextIndex(obj: *mut opaque::ODPIObject,
+                                  index: i32,
+                                  nextIndex: *mut i32,
+                                  exists: *mut ::std::os::raw::c_int)
+                                  -> ::std::os::raw::c_int;
+    pub fn dpiObject_getPrevIndex(obj: *mut opaque::ODPIObject,
+                                  index: i32,
+                                  prevIndex: *mut i32,
+                                  exists: *mut ::std::os::raw::c_int)
+                                  -> ::std::os::raw::c_int;
+    pub fn dpiObject_getSize(obj: *mut opaque::ODPIObject, size: *mut i32) -> ::std::os::raw::c_int;
+    pub fn dpiObject_release(obj: *mut opaque::ODPIObject) -> ::std::os::raw::c_int;
+    pub fn dpiObject_setAttributeValue(obj: *mut opaque::ODPIObject,
+                                       attr: *mut opaque::ODPIObjectAttr,
+                                       nativeTypeNum: flags::ODPINativeTypeNum,
+                                       value: *mut structs::ODPIData)
+                                       -> ::std::os::raw::c_int;
+    pub fn dpiObject_setElementValueByIndex(obj: *mut opaque::ODPIObject,
+                                            index: i32,
+                                            nativeTypeNum: flags::ODPINativeTypeNum,
+                                            value: *mut structs::ODPIData)
+                                            -> ::std::os::raw::c_int;
+    pub fn dpiObject_trim(obj: *mut opaque::ODPIObject, numToTrim: u32) -> ::std::os::raw::c_int;
+}
+
+extern "C" {
+    pub fn dpiObjectAttr_getInfo(attr: *mut opaque::ODPIObjectAttr,
+                                 info: *mut structs::ODPIObjectAttrInfo)
+                                 -> ::std::os::raw::c_int;
+    pub fn dpiObjectAttr_release(attr: *mut opaque::ODPIObjectAttr) -> ::std::os::raw::c_int;
+}
+
 extern "C" {
     pub fn dpiObjectType_addRef(objType: *mut opaque::ODPIObjectType) -> ::std::os::raw::c_int;
+    pub fn dpiObjectType_createObject(objType: *mut opaque::ODPIObjectType,
+                                      obj: *mut *mut opaque::ODPIObject)
+                                      -> ::std::os::raw::c_int;
+    pub fn dpiObjectType_getAttributes(objType: *mut opaque::ODPIObjectType,
+                                       numAttributes: u16,
+                                       attributes: *mut *mut opaque::ODPIObjectAttr)
+                                       -> ::std::os::raw::c_int;
+    pub fn dpiObjectType_getInfo(objType: *mut opaque::ODPIObjectType,
+                                 info: *mut structs::ODPIObjectTypeInfo)
+                                 -> ::std::os::raw::c_int;
+    pub fn dpiObjectType_release(objType: *mut opaque::ODPIObjectType) -> ::std::os::raw::c_int;
 }
 
 extern "C" {
@@ -481,6 +558,15 @@ extern "C" {
     pub fn dpiPool_setTimeout(pool: *mut opaque::ODPIPool, value: u32) -> ::std::os::raw::c_int;
 }
 
+extern "C" {
+    pub fn dpiRowid_addRef(rowid: *mut opaque::ODPIRowid) -> ::std::os::raw::c_int;
+    pub fn dpiRowid_getStringValue(rowid: *mut opaque::ODPIRowid,
+                                   value: *mut *const ::std::os::raw::c_char,
+                                   valueLength: *mut u32)
+                                   -> ::std::os::raw::c_int;
+    pub fn dpiRowid_release(rowid: *mut opaque::ODPIRowid) -> ::std::os::raw::c_int;
+}
+
 extern "C" {
     pub fn dpiStmt_addRef(stmt: *mut opaque::ODPIStmt) -> ::std::os::raw::c_int;
     pub fn dpiStmt_bindByName(stmt: *mut opaque::ODPIStmt,
@@ -507,6 +593,10 @@ extern "C" {
                          tag: *const ::std::os::raw::c_char,
                          tagLength: u32)
                          -> ::std::os::raw::c_int;
+    pub fn dpiStmt_define(stmt: *mut opaque::ODPIStmt,
+                          pos: u32,
+                          var: *mut opaque::ODPIVar)
+                          -> ::std::os::raw::c_int;
     pub fn dpiStmt_execute(stmt: *mut opaque::ODPIStmt,
                            mode: flags::ODPIExecMode,
                            numQueryColumns: *mut u32)
@@ -543,6 +633,9 @@ extern "C" {
     pub fn dpiStmt_getFetchArraySize(stmt: *mut opaque::ODPIStmt,
                                      arraySize: *mut u32)
                                      -> ::std::os::raw::c_int;
+    pub fn dpiStmt_getImplicitResult(stmt: *mut opaque::ODPIStmt,
+                                     implicitResult: *mut *mut opaque::ODPIStmt)
+                                     -> ::std::os::raw::c_int;
     pub fn dpiStmt_getInfo(stmt: *mut opaque::ODPIStmt,
                            info: *mut structs::ODPIStmtInfo)
                            -> ::std::os::raw::c_int;
@@ -561,16 +654,30 @@ extern "C" {
     pub fn dpiStmt_getRowCount(stmt: *mut opaque::ODPIStmt,
                                count: *mut u64)
                                -> ::std::os::raw::c_int;
+    pub fn dpiStmt_getRowCounts(stmt: *mut opaque::ODPIStmt,
+                                numRowCounts: *mut u32,
+                                rowCounts: *mut *mut u64)
+                                -> ::std::os::raw::c_int;
     pub fn dpiStmt_release(stmt: *mut opaque::ODPIStmt) -> ::std::os::raw::c_int;
     pub fn dpiStmt_scroll(stmt: *mut opaque::ODPIStmt,
                           mode: flags::ODPIFetchMode,
                           offset: i32,
                           rowCountOffset: i32)
                           -> ::std::os::raw::c_int;
+    pub fn dpiStmt_setFetchArraySize(stmt: *mut opaque::ODPIStmt,
+                                     arraySize: u32)
+                                     -> ::std::os::raw::c_int;
 }
 
 extern "C" {
     pub fn dpiSubscr_addRef(subscr: *mut opaque::ODPISubscr) -> ::std::os::raw::c_int;
+    pub fn dpiSubscr_close(subscr: *mut opaque::ODPISubscr) -> ::std::os::raw::c_int;
+    pub fn dpiSubscr_prepareStmt(subscr: *mut opaque::ODPISubscr,
+                                 sql: *const ::std::os::raw::c_char,
+                                 sqlLength: u32,
+                                 stmt: *mut *mut opaque::ODPIStmt)
+                                 -> ::std::os::raw::c_int;
+    pub fn dpiSubscr_release(subscr: *mut opaque::ODPISubscr) -> ::std::os::raw::c_int;
 }
 
 #[allow(dead_code)]
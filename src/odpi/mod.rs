@@ -10,5 +10,6 @@
 pub mod constants;
 pub mod externs;
 pub mod flags;
+pub mod oci;
 pub mod opaque;
 pub mod structs;
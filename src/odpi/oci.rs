@@ -0,0 +1,43 @@
+// Copyright (c) 2017 oic developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! OCI externs and constants for the few attributes ODPI-C 2 does not expose. The Oracle Client
+//! library is linked by the build script, so these are always available.
+use std::os::raw::{c_int, c_void};
+
+/// Value returned on success.
+pub const OCI_SUCCESS: c_int = 0;
+/// Error handle type.
+pub const OCI_HTYPE_ERROR: u32 = 2;
+/// Service context handle type.
+pub const OCI_HTYPE_SVCCTX: u32 = 3;
+/// Session handle type.
+pub const OCI_HTYPE_SESSION: u32 = 9;
+/// The environment a handle belongs to.
+pub const OCI_ATTR_ENV: u32 = 5;
+/// The session of a service context.
+pub const OCI_ATTR_SESSION: u32 = 7;
+/// Whether a transaction is open in a session, available from Oracle Client 12.1.
+pub const OCI_ATTR_TRANSACTION_IN_PROGRESS: u32 = 484;
+
+extern "C" {
+    pub fn OCIAttrGet(trgthndlp: *const c_void,
+                      trghndltyp: u32,
+                      attributep: *mut c_void,
+                      sizep: *mut u32,
+                      attrtype: u32,
+                      errhp: *mut c_void)
+                      -> c_int;
+    pub fn OCIHandleAlloc(parenth: *const c_void,
+                          hndlpp: *mut *mut c_void,
+                          htype: u32,
+                          xtramem_sz: usize,
+                          usrmempp: *mut *mut c_void)
+                          -> c_int;
+    pub fn OCIHandleFree(hndlp: *mut c_void, htype: u32) -> c_int;
+}
@@ -385,6 +385,83 @@ pub struct ODPIIntervalYM {
     pub months: i32,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+/// This structure is used for passing information about an object type's attribute from ODPI-C. It
+/// is used by the function `ObjectAttr::get_info()`.
+pub struct ODPIObjectAttrInfo {
+    /// Specifies the name of the attribute, as a byte string in the encoding used for CHAR data.
+    pub name: *const c_char,
+    /// Specifies the length of the name member, in bytes.
+    pub name_length: u32,
+    /// Specifies the type of the attribute. It will be one of the values from the enumeration
+    /// `ODPIOracleTypeNum`.
+    pub oracle_type_num: flags::ODPIOracleTypeNum,
+    /// Specifies the default native type for the attribute. It will be one of the values from the
+    /// enumeration `ODPINativeTypeNum`.
+    pub default_native_type_num: flags::ODPINativeTypeNum,
+    /// Specifies a reference to the type of the attribute, if the attribute is an object. If the
+    /// attribute is not an object, this value is NULL.
+    pub object_type: *mut opaque::ODPIObjectType,
+}
+
+impl Default for ODPIObjectAttrInfo {
+    fn default() -> ODPIObjectAttrInfo {
+        ODPIObjectAttrInfo {
+            name: ptr::null(),
+            name_length: 0,
+            oracle_type_num: flags::ODPIOracleTypeNum::TypeNone,
+            default_native_type_num: flags::ODPINativeTypeNum::Invalid,
+            object_type: ptr::null_mut(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+/// This structure is used for passing information about an object type from ODPI-C. It is used by
+/// the function `ObjectType::get_info()`.
+pub struct ODPIObjectTypeInfo {
+    /// Specifies the schema which owns the object type, as a byte string in the encoding used for
+    /// CHAR data.
+    pub schema: *const c_char,
+    /// Specifies the length of the schema member, in bytes.
+    pub schema_length: u32,
+    /// Specifies the name of the object type, as a byte string in the encoding used for CHAR data.
+    pub name: *const c_char,
+    /// Specifies the length of the name member, in bytes.
+    pub name_length: u32,
+    /// Specifies if the object type is a collection (1) or not (0).
+    pub is_collection: c_int,
+    /// Specifies the type of the elements of the collection. This value is only populated if the
+    /// object type is a collection.
+    pub element_oracle_type_num: flags::ODPIOracleTypeNum,
+    /// Specifies the default native type for the elements of the collection. This value is only
+    /// populated if the object type is a collection.
+    pub element_default_native_type_num: flags::ODPINativeTypeNum,
+    /// Specifies a reference to the type of the elements of the collection, if they are objects.
+    /// Otherwise this value is NULL.
+    pub element_object_type: *mut opaque::ODPIObjectType,
+    /// Specifies the number of attributes that the object type has.
+    pub num_attributes: u16,
+}
+
+impl Default for ODPIObjectTypeInfo {
+    fn default() -> ODPIObjectTypeInfo {
+        ODPIObjectTypeInfo {
+            schema: ptr::null(),
+            schema_length: 0,
+            name: ptr::null(),
+            name_length: 0,
+            is_collection: 0,
+            element_oracle_type_num: flags::ODPIOracleTypeNum::TypeNone,
+            element_default_native_type_num: flags::ODPINativeTypeNum::Invalid,
+            element_object_type: ptr::null_mut(),
+            num_attributes: 0,
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
 /// This structure is used for creating session pools, which can in turn be used to create
@@ -12,20 +12,51 @@
 //! function `acquireConnection()`.
 use common::encoding;
 use connection::Connection;
-use context::Context;
+use context::{Context, Handle};
 use context::params::{CommonCreate, ConnCreate, PoolCreate};
 use error::{ErrorKind, Result};
-use odpi::{externs, flags};
+use odpi::{constants, externs, flags};
 use odpi::opaque::{ODPIConn, ODPIPool};
 use odpi::structs::ODPIEncodingInfo;
+use slog::Logger;
+use std::cell::Cell;
+use std::ffi::CString;
+use std::fmt;
 use std::ptr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use util::ODPIStr;
 
+/// How long `acquire_connection_timeout()` sleeps between attempts to acquire a session.
+const ACQUIRE_RETRY_INTERVAL_MS: u64 = 10;
+
+/// How many sessions `acquire_connection()` will try when validating sessions on acquire.
+const ACQUIRE_VALIDATE_ATTEMPTS: u32 = 3;
+
 /// This structure represents session pools and is available by handle to a calling application or '
-/// driver.
+/// driver. A `Pool` owns the reference to the ODPI-C pool it was created with, and any added with
+/// `add_ref()`, and releases whatever references it still holds when it is dropped.
 pub struct Pool {
     /// An ODPI-C dpiPool opaque struct pointer.
     inner: *mut ODPIPool,
+    /// The ODPI-C context the pool was created with, handed to acquired connections so that they
+    /// can read the details of errors.
+    context: Option<Arc<Handle>>,
+    /// Are sessions pinged before `acquire_connection()` hands them out?
+    validate_on_acquire: AtomicBool,
+    /// Do all sessions use the credentials the pool was created with?
+    homogeneous: bool,
+    /// Are the sessions created with external authentication?
+    external_auth: bool,
+    /// Was the pool created with credentials, which sessions use when none are given?
+    has_credentials: bool,
+    /// Optional stderr logger, set by `set_stderr()`.
+    stderr: Option<Logger>,
+    /// The number of references to `inner` held by this handle: the one it was created with plus
+    /// those added by `add_ref()`, less those given up by `release()`.
+    refs: Cell<u32>,
 }
 
 impl Pool {
@@ -38,6 +69,18 @@ impl Pool {
     /// Acquires a connection from the pool and returns a reference to it. This reference should be
     /// released as soon as it is no longer needed.
     ///
+    /// If validation has been enabled with `set_validate_on_acquire()`, the session is pinged
+    /// before it is returned. A session that fails the ping is dropped from the pool and another
+    /// one is acquired, up to three attempts, after which the error from the last ping is
+    /// returned.
+    ///
+    /// The sessions of a heterogeneous pool, created with `PoolCreate::set_homogeneous(false)`,
+    /// may each use credentials of their own. A username must be given unless the pool was created
+    /// with credentials, which are then used, or uses external authentication, or
+    /// `ErrorKind::CredentialsRequired` is returned. The sessions of a homogeneous pool all use the
+    /// credentials the pool was created with, and a warning is logged to the logger set with
+    /// `set_stderr()` if credentials are given for one.
+    ///
     /// * `username` - the name of the user used for authenticating the user, as a string in the
     /// encoding used for CHAR data. None is also acceptable if external authentication is being
     /// requested or credentials were supplied when the pool was created.
@@ -52,6 +95,59 @@ impl Pool {
                               password: Option<&str>,
                               conn_create_params: Option<ConnCreate>)
                               -> Result<Connection> {
+        self.acquire_validated(username, password, conn_create_params)
+    }
+
+    /// Acquire a connection from the pool, validating it if `set_validate_on_acquire()` asked for
+    /// it.
+    fn acquire_validated(&self,
+                         username: Option<&str>,
+                         password: Option<&str>,
+                         conn_create_params: Option<ConnCreate>)
+                         -> Result<Connection> {
+        self.check_credentials(username)?;
+        if !self.validate_on_acquire.load(Ordering::SeqCst) {
+            return self.acquire(username, password, conn_create_params);
+        }
+
+        let mut attempt = 1;
+        loop {
+            let params = conn_create_params.as_ref().map(|cp| ConnCreate::new(cp.inner()));
+            let conn = self.acquire(username, password, params)?;
+            match conn.ping() {
+                Ok(()) => return Ok(conn),
+                Err(e) => {
+                    // The session is dead, so make sure it is not handed out again. Any error from
+                    // the close is irrelevant next to the ping failure.
+                    let _ = conn.close(flags::ODPIConnCloseMode::DropSession, None);
+                    if attempt >= ACQUIRE_VALIDATE_ATTEMPTS {
+                        return Err(e);
+                    }
+                }
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Check that credentials are given to acquire a connection from a heterogeneous pool.
+    fn check_credentials(&self, username: Option<&str>) -> Result<()> {
+        if self.homogeneous {
+            if username.is_some() {
+                try_warn!(self.stderr,
+                          "Credentials given to acquire a connection from a homogeneous pool");
+            }
+        } else if username.is_none() && !self.external_auth && !self.has_credentials {
+            return Err(ErrorKind::CredentialsRequired.into());
+        }
+        Ok(())
+    }
+
+    /// Acquire a connection from the pool without validating it.
+    fn acquire(&self,
+               username: Option<&str>,
+               password: Option<&str>,
+               conn_create_params: Option<ConnCreate>)
+               -> Result<Connection> {
         let username_s = ODPIStr::from(username);
         let password_s = ODPIStr::from(password);
         let conn_cp = if let Some(conn_create_params) = conn_create_params {
@@ -68,15 +164,64 @@ impl Pool {
                                                     password_s.len(),
                                                     &mut conn_cp.inner(),
                                                     &mut conn),
-                 Ok(conn.into()),
+                 {
+                     let mut conn: Connection = conn.into();
+                     conn.set_context(self.context.clone());
+                     Ok(conn)
+                 },
                  ErrorKind::Pool("dpiPool_acquireConnection".to_string()))
     }
 
+    /// Acquires a connection from the pool, waiting at most `timeout` for a session to become
+    /// available, overriding the get mode of the pool for this call only. ODPI-C 2 does not support
+    /// timed waits, so the pool is switched to `ODPIPoolGetMode::NoWait`, attempts are repeated
+    /// until one succeeds or the timeout expires, and the previous get mode is then restored. A
+    /// `Pool` cannot be shared between threads, so no other acquire through it can see the
+    /// temporary get mode.
+    ///
+    /// The error from the last attempt is returned if no session became available in time.
+    ///
+    /// * `username` - the name of the user used for authenticating the user. See
+    /// `acquire_connection()`.
+    /// * `password` - the password to use for authenticating the user. See `acquire_connection()`.
+    /// * `conn_create_params` - An optional `ConnCreate` structure which is used to specify
+    /// parameters for connection creation.
+    /// * `timeout` - the maximum amount of time to wait for a session.
+    pub fn acquire_connection_timeout(&self,
+                                      username: Option<&str>,
+                                      password: Option<&str>,
+                                      conn_create_params: Option<ConnCreate>,
+                                      timeout: Duration)
+                                      -> Result<Connection> {
+        self.check_credentials(username)?;
+        let start = Instant::now();
+        let previous = self.get_get_mode()?;
+        self.set_get_mode(flags::ODPIPoolGetMode::NoWait)?;
+
+        let res = loop {
+            let params = conn_create_params.as_ref().map(|cp| ConnCreate::new(cp.inner()));
+            match self.acquire_validated(username, password, params) {
+                Ok(conn) => break Ok(conn),
+                Err(e) => {
+                    if start.elapsed() >= timeout {
+                        break Err(e);
+                    }
+                }
+            }
+            thread::sleep(Duration::from_millis(ACQUIRE_RETRY_INTERVAL_MS));
+        };
+        self.set_get_mode(previous)?;
+        res
+    }
+
     /// Adds a reference to the pool. This is intended for situations where a reference to the pool
     /// needs to be maintained independently of the reference returned when the pool was created.
     pub fn add_ref(&self) -> Result<()> {
         try_dpi!(externs::dpiPool_addRef(self.inner),
-                 Ok(()),
+                 {
+                     self.refs.set(self.refs.get() + 1);
+                     Ok(())
+                 },
                  ErrorKind::Pool("dpiPool_addRef".to_string()))
     }
 
@@ -144,7 +289,14 @@ impl Pool {
                                          &comm_cp.inner(),
                                          &mut pool_cp.inner(),
                                          &mut inner),
-                 Ok(inner.into()),
+                 {
+                     let mut pool: Pool = inner.into();
+                     pool.context = Some(context.handle());
+                     pool.homogeneous = pool_cp.get_homogeneous();
+                     pool.external_auth = pool_cp.get_external_auth();
+                     pool.has_credentials = username.is_some();
+                     Ok(pool)
+                 },
                  ErrorKind::Pool("dpiPool_create".to_string()))
     }
 
@@ -221,6 +373,7 @@ impl Pool {
     /// when this count reaches zero, the memory associated with the pool is freed and the session
     /// pool is closed if that has not already taken place using the function `Pool::close()`.
     pub fn release(&self) -> Result<()> {
+        self.refs.set(self.refs.get().saturating_sub(1));
         try_dpi!(externs::dpiPool_release(self.inner),
                  Ok(()),
                  ErrorKind::Pool("dpiPool_release".to_string()))
@@ -246,6 +399,15 @@ impl Pool {
                  ErrorKind::Pool("dpiPool_setMaxLifetimeSession".to_string()))
     }
 
+    /// Sets the logger that warnings about the pool are written to, such as credentials given to
+    /// acquire a connection from a homogeneous pool or a failure to release the pool when it is
+    /// dropped. None, the default, disables the warnings.
+    ///
+    /// * `stderr` - the logger to write warnings to.
+    pub fn set_stderr(&mut self, stderr: Option<Logger>) {
+        self.stderr = stderr;
+    }
+
     /// Sets the default size of the statement cache for sessions in the pool.
     ///
     /// * `stmt_cache_size` - the new size of the statement cache, in number of statements.
@@ -262,24 +424,285 @@ impl Pool {
                  Ok(()),
                  ErrorKind::Pool("dpiPool_setTimeout".to_string()))
     }
+
+    /// Sets whether `acquire_connection()` pings each session before handing it out, transparently
+    /// replacing sessions that have died, for example because they were killed or the network
+    /// connection was lost while they sat in the pool. Validation costs a round trip per acquire,
+    /// so it is disabled by default.
+    ///
+    /// * `validate` - true to validate sessions on acquire.
+    pub fn set_validate_on_acquire(&self, validate: bool) {
+        self.validate_on_acquire.store(validate, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        if self.inner.is_null() {
+            return;
+        }
+
+        for _ in 0..self.refs.get() {
+            if unsafe { externs::dpiPool_release(self.inner) } != constants::DPI_SUCCESS {
+                try_warn!(self.stderr, "Failed to release the pool on drop");
+                break;
+            }
+        }
+        self.refs.set(0);
+    }
 }
 
+/// Wrap an ODPI-C pool, taking ownership of one reference to it. The reference is released when the
+/// pool is dropped, so the pointer must not be released by the caller as well.
 impl From<*mut ODPIPool> for Pool {
     fn from(inner: *mut ODPIPool) -> Pool {
-        Pool { inner: inner }
+        Pool {
+            inner: inner,
+            context: None,
+            validate_on_acquire: AtomicBool::new(false),
+            homogeneous: true,
+            external_auth: false,
+            has_credentials: false,
+            stderr: None,
+            refs: Cell::new(1),
+        }
+    }
+}
+
+/// Builder for the parameters of `Pool::create()`, mirroring `ConnectionBuilder`. Parameters that
+/// are not set keep the ODPI-C defaults.
+#[derive(Clone, Default)]
+pub struct PoolBuilder {
+    /// The name of the user used for authenticating sessions.
+    username: Option<String>,
+    /// The password used for authenticating sessions.
+    password: Option<String>,
+    /// The connect string identifying the database to which sessions are established.
+    connect_string: Option<String>,
+    /// The encoding to use for CHAR data.
+    encoding: Option<String>,
+    /// The encoding to use for NCHAR data.
+    nchar_encoding: Option<String>,
+    /// The minimum number of sessions in the pool.
+    min_sessions: Option<u32>,
+    /// The maximum number of sessions in the pool.
+    max_sessions: Option<u32>,
+    /// The number of sessions created when more sessions are needed.
+    session_increment: Option<u32>,
+    /// The number of seconds a session may be idle before it is pinged when acquired.
+    ping_interval: Option<i32>,
+    /// The number of milliseconds to wait for a ping to complete.
+    ping_timeout: Option<i32>,
+    /// Do all sessions use the credentials the pool was created with?
+    homogeneous: Option<bool>,
+    /// The mode used when acquiring sessions from the pool.
+    get_mode: Option<flags::ODPIPoolGetMode>,
+    /// The default size of the statement cache of sessions in the pool.
+    stmt_cache_size: Option<u32>,
+    /// The number of seconds after which idle sessions are terminated.
+    timeout: Option<u32>,
+}
+
+impl PoolBuilder {
+    /// Create a new builder with no parameters set.
+    pub fn new() -> PoolBuilder {
+        Default::default()
+    }
+
+    /// Set the name of the user used for authenticating sessions.
+    pub fn username(&mut self, username: &str) -> &mut PoolBuilder {
+        self.username = Some(username.to_string());
+        self
+    }
+
+    /// Set the password used for authenticating sessions.
+    pub fn password(&mut self, password: &str) -> &mut PoolBuilder {
+        self.password = Some(password.to_string());
+        self
+    }
+
+    /// Set the connect string identifying the database to which sessions are established.
+    pub fn connect_string(&mut self, connect_string: &str) -> &mut PoolBuilder {
+        self.connect_string = Some(connect_string.to_string());
+        self
+    }
+
+    /// Set the encoding to use for CHAR data, as an IANA or Oracle specific character set name
+    /// such as `UTF-8`. The NLS_LANG environment variable is used if it is not set.
+    pub fn encoding(&mut self, encoding: &str) -> &mut PoolBuilder {
+        self.encoding = Some(encoding.to_string());
+        self
+    }
+
+    /// Set the encoding to use for NCHAR data, as an IANA or Oracle specific character set name
+    /// such as `UTF-8`. The NLS_NCHAR environment variable is used if it is not set.
+    pub fn nchar_encoding(&mut self, nchar_encoding: &str) -> &mut PoolBuilder {
+        self.nchar_encoding = Some(nchar_encoding.to_string());
+        self
+    }
+
+    /// Set the minimum number of sessions in the pool, which are created when the pool is.
+    pub fn min_sessions(&mut self, min_sessions: u32) -> &mut PoolBuilder {
+        self.min_sessions = Some(min_sessions);
+        self
+    }
+
+    /// Set the maximum number of sessions in the pool.
+    pub fn max_sessions(&mut self, max_sessions: u32) -> &mut PoolBuilder {
+        self.max_sessions = Some(max_sessions);
+        self
+    }
+
+    /// Set the number of sessions created when more sessions are needed.
+    pub fn session_increment(&mut self, session_increment: u32) -> &mut PoolBuilder {
+        self.session_increment = Some(session_increment);
+        self
+    }
+
+    /// Set the number of seconds a session may be idle before it is pinged when acquired. A
+    /// negative value disables the ping.
+    pub fn ping_interval(&mut self, ping_interval: i32) -> &mut PoolBuilder {
+        self.ping_interval = Some(ping_interval);
+        self
+    }
+
+    /// Set the number of milliseconds to wait for a ping to complete before the session is
+    /// dropped and another is acquired.
+    pub fn ping_timeout(&mut self, ping_timeout: i32) -> &mut PoolBuilder {
+        self.ping_timeout = Some(ping_timeout);
+        self
+    }
+
+    /// Set whether all sessions use the credentials the pool was created with. A heterogeneous
+    /// pool takes credentials in `Pool::acquire_connection()` instead.
+    pub fn homogeneous(&mut self, homogeneous: bool) -> &mut PoolBuilder {
+        self.homogeneous = Some(homogeneous);
+        self
+    }
+
+    /// Set the mode used when acquiring sessions from the pool.
+    pub fn get_mode(&mut self, get_mode: flags::ODPIPoolGetMode) -> &mut PoolBuilder {
+        self.get_mode = Some(get_mode);
+        self
+    }
+
+    /// Set the default size of the statement cache of sessions in the pool, in number of
+    /// statements.
+    pub fn stmt_cache_size(&mut self, stmt_cache_size: u32) -> &mut PoolBuilder {
+        self.stmt_cache_size = Some(stmt_cache_size);
+        self
+    }
+
+    /// Set the number of seconds after which idle sessions in the pool are terminated.
+    pub fn timeout(&mut self, timeout: u32) -> &mut PoolBuilder {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Create a session pool using the parameters that have been set, by calling `Pool::create()`.
+    /// The statement cache size and timeout are set on the pool once it has been created.
+    ///
+    /// * `context` - the context in which the pool is created.
+    pub fn build(&self, context: &Context) -> Result<Pool> {
+        let mut common_create_params = context.init_common_create_params()?;
+        // The encodings are only read while the pool is created, so they must outlive the call to
+        // `Pool::create()`.
+        let encoding = match self.encoding {
+            Some(ref encoding) => Some(CString::new(&encoding[..])?),
+            None => None,
+        };
+        if let Some(ref encoding) = encoding {
+            common_create_params.set_encoding(encoding.as_ptr());
+        }
+        let nchar_encoding = match self.nchar_encoding {
+            Some(ref nchar_encoding) => Some(CString::new(&nchar_encoding[..])?),
+            None => None,
+        };
+        if let Some(ref nchar_encoding) = nchar_encoding {
+            common_create_params.set_nchar_encoding(nchar_encoding.as_ptr());
+        }
+
+        let mut pool_create_params = context.init_pool_create_params()?;
+        if let Some(min_sessions) = self.min_sessions {
+            pool_create_params.set_min_sessions(min_sessions);
+        }
+        if let Some(max_sessions) = self.max_sessions {
+            pool_create_params.set_max_sessions(max_sessions);
+        }
+        if let Some(session_increment) = self.session_increment {
+            pool_create_params.set_session_increment(session_increment);
+        }
+        if let Some(ping_interval) = self.ping_interval {
+            pool_create_params.set_ping_interval(ping_interval);
+        }
+        if let Some(ping_timeout) = self.ping_timeout {
+            pool_create_params.set_ping_timeout(ping_timeout);
+        }
+        if let Some(homogeneous) = self.homogeneous {
+            pool_create_params.set_homogeneous(homogeneous);
+        }
+        if let Some(get_mode) = self.get_mode {
+            pool_create_params.set_get_mode(get_mode);
+        }
+
+        let pool = Pool::create(context,
+                                self.username.as_ref().map(|s| &s[..]),
+                                self.password.as_ref().map(|s| &s[..]),
+                                self.connect_string.as_ref().map(|s| &s[..]),
+                                Some(common_create_params),
+                                Some(pool_create_params))?;
+        if let Some(stmt_cache_size) = self.stmt_cache_size {
+            pool.set_stmt_cache_size(stmt_cache_size)?;
+        }
+        if let Some(timeout) = self.timeout {
+            pool.set_timeout(timeout)?;
+        }
+        Ok(pool)
+    }
+}
+
+/// The password is redacted, so a builder can be logged without leaking the credentials.
+impl fmt::Debug for PoolBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PoolBuilder")
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .field("connect_string", &self.connect_string)
+            .field("encoding", &self.encoding)
+            .field("nchar_encoding", &self.nchar_encoding)
+            .field("min_sessions", &self.min_sessions)
+            .field("max_sessions", &self.max_sessions)
+            .field("session_increment", &self.session_increment)
+            .field("ping_interval", &self.ping_interval)
+            .field("ping_timeout", &self.ping_timeout)
+            .field("homogeneous", &self.homogeneous)
+            .field("get_mode", &self.get_mode)
+            .field("stmt_cache_size", &self.stmt_cache_size)
+            .field("timeout", &self.timeout)
+            .finish()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use test::CREDS;
+    use test::{CONN_STR, CREDS};
     use context::Context;
     use data::Data;
-    use error::Result;
+    use error::{Error, ErrorKind, Result};
     use odpi::flags::{self, ODPIConnCloseMode, ODPIPoolCloseMode};
     use odpi::flags::ODPINativeTypeNum::*;
-    use pool::Pool;
+    use pool::{Pool, PoolBuilder};
     use std::ffi::CString;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn builder_debug_redacts_password() {
+        let mut builder = PoolBuilder::new();
+        builder.username("jozias").password("s3cret");
+        let debug = format!("{:?}", builder);
+        assert!(debug.contains("jozias"));
+        assert!(!debug.contains("s3cret"));
+    }
 
     fn pool_res() -> Result<()> {
         let ctxt = Context::create()?;
@@ -368,6 +791,178 @@ mod test {
         Ok(())
     }
 
+    fn homogeneous_res() -> Result<()> {
+        let pool = ::test::create_pool(Some(&CREDS[0]), Some(&CREDS[1]), None)?;
+
+        let conn = pool.acquire_connection(None, None, None)?;
+        assert_eq!(conn.query_scalar::<String>("select user from dual")?,
+                   Some(CREDS[0].to_uppercase()));
+        conn.close(ODPIConnCloseMode::DefaultClose, None)?;
+        pool.close(ODPIPoolCloseMode::DefaultClose)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn homogeneous() {
+        use std::io::{self, Write};
+
+        match homogeneous_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
+
+    fn heterogeneous_res() -> Result<()> {
+        let ctxt = ::test::context()?;
+        let mut pcp = ctxt.init_pool_create_params()?;
+        pcp.set_homogeneous(false);
+
+        let pool = ::test::create_pool(None, None, Some(pcp))?;
+
+        match pool.acquire_connection(None, None, None) {
+            Err(Error(ErrorKind::CredentialsRequired, _)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        let conn = pool.acquire_connection(Some(&CREDS[0]), Some(&CREDS[1]), None)?;
+        assert_eq!(conn.query_scalar::<String>("select user from dual")?,
+                   Some(CREDS[0].to_uppercase()));
+        conn.close(ODPIConnCloseMode::DefaultClose, None)?;
+        pool.close(ODPIPoolCloseMode::DefaultClose)?;
+
+        // A heterogeneous pool created with credentials uses them when none are given.
+        let mut pcp = ctxt.init_pool_create_params()?;
+        pcp.set_homogeneous(false);
+
+        let pool = ::test::create_pool(Some(&CREDS[0]), Some(&CREDS[1]), Some(pcp))?;
+        let conn = pool.acquire_connection(None, None, None)?;
+        assert_eq!(conn.query_scalar::<String>("select user from dual")?,
+                   Some(CREDS[0].to_uppercase()));
+        conn.close(ODPIConnCloseMode::DefaultClose, None)?;
+        pool.close(ODPIPoolCloseMode::DefaultClose)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn heterogeneous() {
+        use std::io::{self, Write};
+
+        match heterogeneous_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
+
+    fn acquire_connection_timeout_res() -> Result<()> {
+        let mut pcp = ::test::context()?.init_pool_create_params()?;
+        pcp.set_max_sessions(1);
+        pcp.set_get_mode(flags::ODPIPoolGetMode::NoWait);
+
+        let pool = ::test::create_pool(Some(&CREDS[0]), Some(&CREDS[1]), Some(pcp))?;
+
+        let conn = pool.acquire_connection(None, None, None)?;
+
+        // The pool default fails at once when no session is available.
+        let start = Instant::now();
+        match pool.acquire_connection(None, None, None) {
+            Ok(_) => assert!(false),
+            Err(_) => assert!(start.elapsed() < Duration::from_millis(500)),
+        }
+
+        // The override waits for the given time, whatever the pool default is.
+        for default in &[flags::ODPIPoolGetMode::NoWait, flags::ODPIPoolGetMode::Wait] {
+            pool.set_get_mode(*default)?;
+            let start = Instant::now();
+            match pool.acquire_connection_timeout(None, None, None, Duration::from_millis(500)) {
+                Ok(_) => assert!(false),
+                Err(_) => {
+                    let elapsed = start.elapsed();
+                    assert!(elapsed >= Duration::from_millis(500));
+                    assert!(elapsed < Duration::from_secs(5));
+                }
+            }
+            assert_eq!(pool.get_get_mode()?, *default);
+        }
+
+        conn.close(ODPIConnCloseMode::DefaultClose, None)?;
+        let conn = pool.acquire_connection_timeout(None, None, None, Duration::from_millis(500))?;
+        conn.close(ODPIConnCloseMode::DefaultClose, None)?;
+        pool.close(ODPIPoolCloseMode::DefaultClose)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn acquire_connection_timeout() {
+        use std::io::{self, Write};
+
+        match acquire_connection_timeout_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
+
+    fn validate_on_acquire_res() -> Result<()> {
+        let mut pcp = ::test::context()?.init_pool_create_params()?;
+        pcp.set_min_sessions(1);
+        pcp.set_max_sessions(1);
+
+        let pool = ::test::create_pool(Some(&CREDS[0]), Some(&CREDS[1]), Some(pcp))?;
+        pool.set_validate_on_acquire(true);
+        let session_sql = "select sid, serial# from v$session \
+                           where sid = sys_context('USERENV', 'SID')";
+
+        let conn = pool.acquire_connection(None, None, None)?;
+        let (sid, serial) = conn.query_row::<(i64, i64)>(session_sql, &[])?
+            .expect("expected a session");
+        conn.close(ODPIConnCloseMode::DefaultClose, None)?;
+
+        let killer = ::test::connect()?;
+        let kill = killer.prepare_stmt(Some(&format!("alter system kill session '{},{}' \
+                                                      immediate",
+                                                     sid,
+                                                     serial)),
+                                       None,
+                                       false)?;
+        kill.execute(flags::EXEC_DEFAULT)?;
+        kill.close(None)?;
+        killer.close(ODPIConnCloseMode::DefaultClose, None)?;
+
+        let conn = pool.acquire_connection(None, None, None)?;
+        let (new_sid, new_serial) = conn.query_row::<(i64, i64)>(session_sql, &[])?
+            .expect("expected a session");
+        assert!((new_sid, new_serial) != (sid, serial));
+        conn.close(ODPIConnCloseMode::DefaultClose, None)?;
+        pool.close(ODPIPoolCloseMode::DefaultClose)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn validate_on_acquire() {
+        use std::io::{self, Write};
+
+        match validate_on_acquire_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
+
     #[test]
     fn pool() {
         use std::io::{self, Write};
@@ -380,4 +975,73 @@ mod test {
             }
         }
     }
+
+    /// The settings of a pool, and of a connection acquired from it, that the pool tests compare.
+    fn observe(pool: &Pool) -> Result<(String, flags::ODPIPoolGetMode, u32, u32, u32, String)> {
+        let conn = pool.acquire_connection(None, None, None)?;
+        let user = conn.query_scalar::<String>("select user from dual")?.unwrap_or_default();
+        let open_count = pool.get_open_count()?;
+        conn.close(ODPIConnCloseMode::DefaultClose, None)?;
+
+        Ok((pool.get_encoding_info()?.encoding().to_string(),
+            pool.get_get_mode()?,
+            pool.get_stmt_cache_size()?,
+            pool.get_timeout()?,
+            open_count,
+            user))
+    }
+
+    fn builder_res() -> Result<()> {
+        let ctxt = ::test::context()?;
+        let mut pcp = ctxt.init_pool_create_params()?;
+        pcp.set_min_sessions(2);
+        pcp.set_max_sessions(4);
+        pcp.set_session_increment(1);
+        pcp.set_get_mode(flags::ODPIPoolGetMode::Wait);
+
+        let pool = ::test::create_pool(Some(&CREDS[0]), Some(&CREDS[1]), Some(pcp))?;
+        pool.set_stmt_cache_size(50)?;
+        pool.set_timeout(600)?;
+
+        let built = PoolBuilder::new()
+            .username(&CREDS[0])
+            .password(&CREDS[1])
+            .connect_string(CONN_STR)
+            .encoding("UTF-8")
+            .nchar_encoding("UTF-8")
+            .min_sessions(2)
+            .max_sessions(4)
+            .session_increment(1)
+            .get_mode(flags::ODPIPoolGetMode::Wait)
+            .stmt_cache_size(50)
+            .timeout(600)
+            .build(ctxt)?;
+
+        let observed = observe(&pool)?;
+        assert_eq!(observed,
+                   ("UTF-8".to_string(),
+                    flags::ODPIPoolGetMode::Wait,
+                    50,
+                    600,
+                    2,
+                    CREDS[0].to_uppercase()));
+        assert_eq!(observe(&built)?, observed);
+
+        pool.close(ODPIPoolCloseMode::DefaultClose)?;
+        built.close(ODPIPoolCloseMode::DefaultClose)?;
+        Ok(())
+    }
+
+    #[test]
+    fn builder() {
+        use std::io::{self, Write};
+
+        match builder_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
 }
@@ -94,6 +94,13 @@ impl Info {
         self.inner.scale
     }
 
+    /// Returns true if the column is a NUMBER with a scale of zero, so that its values are always
+    /// integers. A NUMBER declared without a precision or scale has a scale of -127 and may hold
+    /// fractional values.
+    pub fn is_integer(&self) -> bool {
+        self.inner.oracle_type_num == flags::ODPIOracleTypeNum::Number && self.inner.scale == 0
+    }
+
     /// Get the `null_ok' value.
     ///
     /// Specifies if the column that is being queried may return null values or not.
@@ -0,0 +1,342 @@
+// Copyright (c) 2017 oic developers
+//
+// Licensed under the Apache License, Version 2.0
+// <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the MIT
+// license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. All files in the project carrying such notice may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Row handles hold the values of the query columns for a single row fetched from a statement.
+//! They are created by calling the function `Statement::fetch_row()`. The values are copied out of
+//! the buffers managed by ODPI-C, so a row remains valid after further rows have been fetched.
+use data::{FromData, OwnedData};
+use error::{ErrorKind, Result};
+use query;
+
+/// This structure represents a single row fetched from a query.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    /// The column values, in query order.
+    values: Vec<OwnedData>,
+}
+
+impl Row {
+    /// Create a new row from the given column values.
+    #[doc(hidden)]
+    pub fn new(values: Vec<OwnedData>) -> Row {
+        Row { values: values }
+    }
+
+    /// Returns the number of columns in the row.
+    pub fn num_columns(&self) -> u32 {
+        self.values.len() as u32
+    }
+
+    /// Returns the column values, in query order.
+    pub fn values(&self) -> &[OwnedData] {
+        &self.values
+    }
+
+    /// Consumes the row, returning the column values in query order.
+    pub fn into_values(self) -> Vec<OwnedData> {
+        self.values
+    }
+
+    /// Returns the raw value of the column at the given position.
+    ///
+    /// * `col` - the position of the column. Columns are numbered from left to right, starting
+    /// from 1, as with `Statement::get_query_value()`.
+    pub fn get_data(&self, col: u32) -> Result<&OwnedData> {
+        if col == 0 {
+            return Err(ErrorKind::InvalidColumn(col).into());
+        }
+        self.values.get((col - 1) as usize).ok_or_else(|| ErrorKind::InvalidColumn(col).into())
+    }
+
+    /// Returns the value of the column at the given position converted to the requested type. An
+    /// error is returned if the column is NULL or cannot be converted.
+    ///
+    /// * `col` - the position of the column, starting from 1.
+    pub fn get<T: FromData>(&self, col: u32) -> Result<T> {
+        T::from_data(self.get_data(col)?)
+    }
+
+    /// Returns the value of the column at the given position converted to the requested type, or
+    /// `None` if the column is NULL. An error is only returned if the value cannot be converted.
+    ///
+    /// * `col` - the position of the column, starting from 1.
+    pub fn get_opt<T: FromData>(&self, col: u32) -> Result<Option<T>> {
+        self.get::<Option<T>>(col)
+    }
+
+    /// Returns the row as a JSON object keyed by column name. Numeric values in NUMBER columns with
+    /// a scale of zero are written as integers and all other numeric values as floats, so that
+    /// consumers can tell the two apart. Dates, timestamps and intervals are written as strings and
+    /// binary values as hex strings.
+    ///
+    /// * `columns` - the query information for each column of the row, as returned by
+    /// `Statement::get_query_info()`, in query order.
+    pub fn to_json(&self, columns: &[query::Info]) -> String {
+        let fields: Vec<String> = self.values
+            .iter()
+            .zip(columns)
+            .map(|(value, info)| {
+                     format!("{}:{}",
+                             json_string(&info.name()),
+                             json_value(value, info.is_integer()))
+                 })
+            .collect();
+        format!("{{{}}}", fields.join(","))
+    }
+}
+
+/// A type that can be built from the values of a row, such as a tuple of `FromData` types. This
+/// is used by `Connection::query_row()` to fetch a row of known columns in one call.
+pub trait FromRow: Sized {
+    /// Convert the row into `Self`. An error is returned if a value is NULL for a non-`Option`
+    /// type, cannot be converted, or the row has too few columns.
+    fn from_row(row: &Row) -> Result<Self>;
+}
+
+/// Implement `FromRow` for a tuple, taking each element from the column at the given position.
+/// Columns beyond the size of the tuple are ignored.
+macro_rules! tuple_from_row {
+    ($($name:ident: $col:expr),+) => {
+        impl<$($name: FromData),+> FromRow for ($($name,)+) {
+            fn from_row(row: &Row) -> Result<Self> {
+                Ok(($(row.get::<$name>($col)?,)+))
+            }
+        }
+    };
+}
+
+tuple_from_row!(A: 1);
+tuple_from_row!(A: 1, B: 2);
+tuple_from_row!(A: 1, B: 2, C: 3);
+tuple_from_row!(A: 1, B: 2, C: 3, D: 4);
+tuple_from_row!(A: 1, B: 2, C: 3, D: 4, E: 5);
+tuple_from_row!(A: 1, B: 2, C: 3, D: 4, E: 5, F: 6);
+tuple_from_row!(A: 1, B: 2, C: 3, D: 4, E: 5, F: 6, G: 7);
+tuple_from_row!(A: 1, B: 2, C: 3, D: 4, E: 5, F: 6, G: 7, H: 8);
+
+/// Render a float as JSON, always including a fractional part. JSON has no representation for NaN
+/// or infinity, so those are written as null.
+fn json_float(value: f64) -> String {
+    if value.is_finite() {
+        format!("{:?}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+/// Render a string as a quoted and escaped JSON string.
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Render a value as JSON. Numbers are written as integers when `integer` is true.
+fn json_value(value: &OwnedData, integer: bool) -> String {
+    match *value {
+        OwnedData::Null => "null".to_string(),
+        OwnedData::Boolean(b) => b.to_string(),
+        OwnedData::Int64(i) if integer => i.to_string(),
+        OwnedData::Uint64(u) if integer => u.to_string(),
+        OwnedData::Float(f) if integer && f.is_finite() => (f as i64).to_string(),
+        OwnedData::Double(d) if integer && d.is_finite() => (d as i64).to_string(),
+        OwnedData::Int64(i) => json_float(i as f64),
+        OwnedData::Uint64(u) => json_float(u as f64),
+        OwnedData::Float(f) => json_float(f as f64),
+        OwnedData::Double(d) => json_float(d),
+        OwnedData::String(ref s) => json_string(s),
+        OwnedData::Bytes(ref b) => {
+            let hex: Vec<String> = b.iter().map(|byte| format!("{:02x}", byte)).collect();
+            json_string(&hex.concat())
+        }
+        OwnedData::Date(dt) => json_string(&dt.to_string()),
+        OwnedData::Timestamp(ts) => json_string(&ts.to_rfc3339()),
+        OwnedData::IntervalDS(d) => json_string(&d.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{json_string, json_value, FromRow, Row};
+    use chrono::{NaiveDateTime, Timelike};
+    use data::OwnedData;
+    use error::{self, ErrorKind, Result};
+    use odpi::flags;
+    use test::{ConnResult, CONN};
+
+    #[test]
+    fn get() {
+        let row = Row::new(vec![OwnedData::Double(1.0),
+                                OwnedData::String("jozias".to_string()),
+                                OwnedData::Null]);
+
+        match row.get::<i64>(1) {
+            Ok(id) => assert_eq!(id, 1),
+            Err(e) => ::test::error_info(e),
+        }
+        match row.get::<String>(2) {
+            Ok(username) => assert_eq!(username, "jozias"),
+            Err(e) => ::test::error_info(e),
+        }
+        match row.get::<String>(3) {
+            Err(error::Error(ErrorKind::NullValue, _)) => assert!(true),
+            _ => assert!(false),
+        }
+        match row.get::<bool>(2) {
+            Err(error::Error(ErrorKind::TypeMismatch(..), _)) => assert!(true),
+            _ => assert!(false),
+        }
+        match row.get::<String>(4) {
+            Err(error::Error(ErrorKind::InvalidColumn(4), _)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn from_row() {
+        let row = Row::new(vec![OwnedData::Double(1.0),
+                                OwnedData::String("jozias".to_string()),
+                                OwnedData::Null]);
+
+        match <(i64, String, Option<String>)>::from_row(&row) {
+            Ok(values) => assert_eq!(values, (1, "jozias".to_string(), None)),
+            Err(e) => ::test::error_info(e),
+        }
+        match <(i64,)>::from_row(&row) {
+            Ok(values) => assert_eq!(values, (1,)),
+            Err(e) => ::test::error_info(e),
+        }
+        match <(i64, String, String)>::from_row(&row) {
+            Err(error::Error(ErrorKind::NullValue, _)) => assert!(true),
+            _ => assert!(false),
+        }
+        match <(i64, String, Option<String>, i64)>::from_row(&row) {
+            Err(error::Error(ErrorKind::InvalidColumn(4), _)) => assert!(true),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn json_values() {
+        assert_eq!(json_value(&OwnedData::Double(12.0), true), "12");
+        assert_eq!(json_value(&OwnedData::Double(12.0), false), "12.0");
+        assert_eq!(json_value(&OwnedData::Int64(3), false), "3.0");
+        assert_eq!(json_value(&OwnedData::Double(::std::f64::NAN), false), "null");
+        assert_eq!(json_value(&OwnedData::Null, true), "null");
+        assert_eq!(json_value(&OwnedData::Bytes(vec![0xde, 0xad]), false), r#""dead""#);
+        assert_eq!(json_string("a\"b\\c\n"), r#""a\"b\\c\n""#);
+    }
+
+    fn get_opt_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return Err(ErrorKind::Connection("CONN".to_string()).into()),
+        };
+        let stmt = conn.prepare_stmt(Some("select 1, 'jozias' from dual union all \
+                                           select 2, null from dual order by 1"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let first = stmt.fetch_row()?.expect("expected a first row");
+        assert_eq!(first.get_opt::<String>(2)?, Some("jozias".to_string()));
+
+        let second = stmt.fetch_row()?.expect("expected a second row");
+        assert_eq!(second.get::<i64>(1)?, 2);
+        assert_eq!(second.get_opt::<String>(2)?, None);
+        match second.get_opt::<bool>(1) {
+            Err(error::Error(ErrorKind::TypeMismatch(..), _)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        assert!(stmt.fetch_row()?.is_none());
+        Ok(())
+    }
+
+    fn date_timestamp_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return Err(ErrorKind::Connection("CONN".to_string()).into()),
+        };
+        let stmt = conn.prepare_stmt(Some("select to_date('2017-06-01 12:34:56', \
+                                           'YYYY-MM-DD HH24:MI:SS'), \
+                                           to_timestamp('2017-06-01 12:34:56.789', \
+                                           'YYYY-MM-DD HH24:MI:SS.FF3') from dual"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let row = stmt.fetch_row()?.expect("expected a row");
+        match *row.get_data(1)? {
+            OwnedData::Date(dt) => {
+                assert_eq!(dt.nanosecond(), 0);
+                assert_eq!(dt.second(), 56);
+            }
+            ref other => panic!("expected a DATE, found {:?}", other),
+        }
+        match *row.get_data(2)? {
+            OwnedData::Timestamp(ts) => assert_eq!(ts.nanosecond(), 789_000_000),
+            ref other => panic!("expected a TIMESTAMP, found {:?}", other),
+        }
+        assert_eq!(row.get::<NaiveDateTime>(1)?.second(), 56);
+        Ok(())
+    }
+
+    #[test]
+    fn date_timestamp() {
+        match date_timestamp_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn to_json_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return Err(ErrorKind::Connection("CONN".to_string()).into()),
+        };
+        let stmt = conn.prepare_stmt(Some("select cast(12 as number(10, 0)) as whole, \
+                                           cast(12.5 as number(10, 2)) as part, \
+                                           cast(3 as number(10, 2)) as even from dual"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let json = stmt.fetch_row_json()?.expect("expected a row");
+        assert_eq!(json, r#"{"WHOLE":12,"PART":12.5,"EVEN":3.0}"#);
+        Ok(())
+    }
+
+    #[test]
+    fn to_json() {
+        match to_json_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    fn get_opt() {
+        match get_opt_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+}
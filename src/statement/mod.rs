@@ -12,30 +12,362 @@
 //! `DPI_ORACLE_TYPE_STMT` is created. Statement handles can be closed by calling the function
 //! `close()` or by releasing the last reference to the statement by calling the function
 //! `release()`.
-use common::error;
-use data::Data;
+use common::{error, version};
+use common::version::Feature;
+use data::{Data, FromData, OwnedData};
 use error::{ErrorKind, Result};
-use odpi::externs;
+use lob::Lob;
+use odpi::{constants, externs, flags};
 use odpi::flags::{ODPIExecMode, ODPIFetchMode, ODPINativeTypeNum, ODPIStatementType};
-use odpi::opaque::ODPIStmt;
-use odpi::structs::{ODPIData, ODPIQueryInfo, ODPIStmtInfo};
+use odpi::opaque::{ODPIConn, ODPIStmt};
+use odpi::structs::{ODPIData, ODPIErrorInfo, ODPIQueryInfo, ODPIStmtInfo};
 use query;
+use row::Row;
+use rowid::Rowid;
+use std::cell::{Cell, RefCell};
+use std::iter::FusedIterator;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 use std::{ptr, slice};
-use util::ODPIStr;
+use util::{placeholders, stored_unit, warn_on_drop, ODPIStr};
 use variable::Var;
 
+/// Types that provide values for the named placeholders of a statement, for use with
+/// `Statement::bind_struct()`.
+///
+/// ```ignore
+/// struct User {
+///     id: i64,
+///     username: String,
+/// }
+///
+/// impl Bind for User {
+///     fn binds(&self) -> Vec<(&'static str, OwnedData)> {
+///         vec![(":id", OwnedData::Int64(self.id)),
+///              (":username", OwnedData::String(self.username.clone()))]
+///     }
+/// }
+/// ```
+pub trait Bind {
+    /// Returns the placeholder names, including the leading colon, and the values to bind to them.
+    fn binds(&self) -> Vec<(&'static str, OwnedData)>;
+}
+
+/// Identifies a placeholder in a statement, either by its position or by its name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BindKey {
+    /// The position of the placeholder, starting from 1.
+    Pos(u32),
+    /// The name of the placeholder. Names are compared without a leading colon and ignoring case.
+    Name(String),
+}
+
+impl BindKey {
+    /// Normalize the key so that ":out" and "OUT" refer to the same placeholder.
+    fn normalize(self) -> BindKey {
+        match self {
+            BindKey::Name(name) => BindKey::Name(name.trim_left_matches(':').to_uppercase()),
+            pos => pos,
+        }
+    }
+}
+
+impl From<u32> for BindKey {
+    fn from(pos: u32) -> BindKey {
+        BindKey::Pos(pos)
+    }
+}
+
+impl<'a> From<&'a str> for BindKey {
+    fn from(name: &str) -> BindKey {
+        BindKey::Name(name.to_string())
+    }
+}
+
+/// Describes a placeholder of a prepared statement, as returned by `Statement::binds()`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BindInfo {
+    /// The position where the placeholder first appears, starting from 1.
+    position: u32,
+    /// The name of the placeholder, without the leading colon, or `None` for positional
+    /// placeholders such as `:1`.
+    name: Option<String>,
+}
+
+impl BindInfo {
+    /// Get the `position` value.
+    ///
+    /// The position of the placeholder, as used by `Statement::bind_by_pos()`.
+    pub fn position(&self) -> u32 {
+        self.position
+    }
+
+    /// Get the `name` value.
+    ///
+    /// The name of the placeholder, as used by `Statement::bind_by_name()`, or `None` for
+    /// positional placeholders such as `:1`.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(|name| &name[..])
+    }
+}
+
+/// Timings for a completed statement, given to the sink set with `Connection::set_metrics_sink()`
+/// when the statement is closed or dropped.
+#[derive(Clone, Debug)]
+pub struct StmtMetric {
+    /// The SQL text the statement was prepared with.
+    sql: String,
+    /// The time spent preparing the statement.
+    parse_time: Duration,
+    /// The time spent in all executions of the statement.
+    execute_time: Duration,
+    /// The time spent in all fetches from the statement.
+    fetch_time: Duration,
+    /// The number of rows fetched or affected.
+    rows: u64,
+}
+
+impl StmtMetric {
+    /// Get the `sql` value.
+    ///
+    /// The SQL text the statement was prepared with, or an empty string if it is not known.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Get the `parse_time` value.
+    ///
+    /// The time spent in `Connection::prepare_stmt()`. OCI defers parsing until the statement is
+    /// first executed, so this is usually small and the parse is included in `execute_time()`.
+    pub fn parse_time(&self) -> Duration {
+        self.parse_time
+    }
+
+    /// Get the `execute_time` value.
+    ///
+    /// The total time spent in `Statement::execute()` and `Statement::execute_many()`.
+    pub fn execute_time(&self) -> Duration {
+        self.execute_time
+    }
+
+    /// Get the `fetch_time` value.
+    ///
+    /// The total time spent in `Statement::fetch()` and `Statement::fetch_rows()`, including the
+    /// fetches made by `Statement::fetch_row()`.
+    pub fn fetch_time(&self) -> Duration {
+        self.fetch_time
+    }
+
+    /// Get the `rows` value.
+    ///
+    /// The number of rows fetched so far for queries, or the number of rows affected for other
+    /// statements, as reported by `Statement::get_row_count()`.
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+}
+
+/// The error code OCI reports, as success with info, when a stored unit is created with
+/// compilation errors.
+const ORA_SUCCESS_WITH_COMPILATION_ERROR: i32 = 24344;
+
+/// The largest fetch array size adaptive fetching grows to.
+pub const ADAPTIVE_FETCH_ARRAY_SIZE_CAP: u32 = 6400;
+
+/// The largest fetch array size accepted by `Statement::set_fetch_array_size()`. Every variable
+/// defined for fetching allocates this many elements, so larger sizes can only fail to allocate.
+pub const MAX_FETCH_ARRAY_SIZE: u32 = ::std::u32::MAX / 2;
+
 /// This structure represents statements of all types (queries, DML, DLL and PL/SQL) and is
-/// available by handle to a calling application or driver.
+/// available by handle to a calling application or driver. A `Statement` owns the reference to the
+/// ODPI-C statement it was created with, and any added with `add_ref()`, and releases whatever
+/// references it still holds when it is dropped.
 pub struct Statement {
     /// The ODPI-C statement
     inner: *mut ODPIStmt,
+    /// The ODPI-C connection that prepared the statement, used to create internal variables.
+    conn: *mut ODPIConn,
+    /// The SQL text the statement was prepared with, if known.
+    sql: Option<String>,
+    /// Is the fetch array size grown while the rows of the query are fetched by `fetch_rows()`?
+    adaptive_fetch: Cell<bool>,
+    /// Should the fetch array size be grown before the next call to `fetch_rows()` fetches from
+    /// the database?
+    grow_before_fetch: Cell<bool>,
+    /// Should the statement be closed without a tag, regardless of the tag given to `close()`?
+    uncached: Cell<bool>,
+    /// Was the last execution made with `execute_many()`?
+    executed_many: Cell<bool>,
+    /// The client (if known) and server versions of the connection that prepared the statement.
+    versions: Option<(Option<version::Info>, version::Info)>,
+    /// The variables bound with `bind_by_name()` and `bind_by_pos()`.
+    bound_vars: RefCell<Vec<(BindKey, Var)>>,
+    /// The number of references to `inner` held by this handle: the one it was created with plus
+    /// those added by `add_ref()`, less those given up by `release()`.
+    refs: Cell<u32>,
+    /// The sink a `StmtMetric` is given to when the statement is closed or dropped, taken once the
+    /// metric has been emitted.
+    metrics_sink: RefCell<Option<Arc<Box<Fn(StmtMetric) + Send + Sync>>>>,
+    /// The time spent preparing the statement.
+    parse_time: Duration,
+    /// The total time spent executing the statement.
+    execute_time: Cell<Duration>,
+    /// The total time spent fetching from the statement.
+    fetch_time: Cell<Duration>,
+    /// The warning raised by the last execution, if any.
+    last_warning: RefCell<Option<error::Info>>,
+    /// The type and name of the stored unit defined by the last execution, taken when its
+    /// compilation errors are looked up by `last_warning()`.
+    defined_unit: RefCell<Option<(String, String)>>,
 }
 
 impl Statement {
-    /// Create a new statement from an `ODPIStmt` pointer
+    /// Create a new statement from an `ODPIStmt` pointer, taking ownership of one reference to it.
+    /// The reference is released when the statement is dropped.
     #[doc(hidden)]
     pub fn new(inner: *mut ODPIStmt) -> Statement {
-        Statement { inner: inner }
+        Statement {
+            inner: inner,
+            conn: ptr::null_mut(),
+            sql: None,
+            adaptive_fetch: Cell::new(false),
+            grow_before_fetch: Cell::new(false),
+            uncached: Cell::new(false),
+            executed_many: Cell::new(false),
+            versions: None,
+            bound_vars: RefCell::new(Vec::new()),
+            refs: Cell::new(1),
+            metrics_sink: RefCell::new(None),
+            parse_time: Duration::new(0, 0),
+            execute_time: Cell::new(Duration::new(0, 0)),
+            fetch_time: Cell::new(Duration::new(0, 0)),
+            last_warning: RefCell::new(None),
+            defined_unit: RefCell::new(None),
+        }
+    }
+
+    /// Create a single element variable on the connection that prepared the statement, for
+    /// binding values that cannot be bound directly.
+    fn new_var(&self,
+               oracle_type: flags::ODPIOracleTypeNum,
+               native_type: ODPINativeTypeNum,
+               size: u32)
+               -> Result<Var> {
+        if self.conn.is_null() {
+            return Err(ErrorKind::Statement("dpiConn_newVar".to_string()).into());
+        }
+        let mut var_ptr = ptr::null_mut();
+        let mut data_ptr = ptr::null_mut();
+
+        try_dpi!(externs::dpiConn_newVar(self.conn,
+                                         oracle_type,
+                                         native_type,
+                                         1,
+                                         size,
+                                         1,
+                                         0,
+                                         ptr::null_mut(),
+                                         &mut var_ptr,
+                                         &mut data_ptr),
+                 Ok(Var::new(var_ptr, native_type)),
+                 ErrorKind::Statement("dpiConn_newVar".to_string()))
+    }
+
+    /// Bind a variable to a named placeholder, remembering it for `get_out()`.
+    fn bind_var_by_name(&self, name: &str, var: &Var) -> Result<()> {
+        let name_s = ODPIStr::from(name);
+
+        try_dpi!(externs::dpiStmt_bindByName(self.inner, name_s.ptr(), name_s.len(), var.inner()),
+                 self.record_bind(name.into(), var),
+                 ErrorKind::Statement("dpiStmt_bindByName".to_string()))
+    }
+
+    /// Remember a bound variable so that its value can be read back with `get_out()`. The
+    /// statement keeps a reference of its own to the variable.
+    fn record_bind(&self, key: BindKey, var: &Var) -> Result<()> {
+        let key = key.normalize();
+        let var = var.new_ref()?;
+        let mut bound_vars = self.bound_vars.borrow_mut();
+        bound_vars.retain(|&(ref k, _)| *k != key);
+        bound_vars.push((key, var));
+        Ok(())
+    }
+
+    /// Set the connection that prepared the statement.
+    #[doc(hidden)]
+    pub fn set_conn(&mut self, conn: *mut ODPIConn) {
+        self.conn = conn;
+    }
+
+    /// Set the SQL text the statement was prepared with.
+    #[doc(hidden)]
+    pub fn set_sql(&mut self, sql: &str) {
+        self.sql = Some(sql.to_string());
+    }
+
+    /// Set the sink to give a `StmtMetric` to when the statement is closed or dropped, and the time
+    /// it took to prepare the statement.
+    #[doc(hidden)]
+    pub fn set_metrics(&mut self,
+                       sink: Arc<Box<Fn(StmtMetric) + Send + Sync>>,
+                       parse_time: Duration) {
+        *self.metrics_sink.borrow_mut() = Some(sink);
+        self.parse_time = parse_time;
+    }
+
+    /// Give the metrics of the statement to the sink, if one is set and has not been given them
+    /// already.
+    fn emit_metric(&self) {
+        let sink = match self.metrics_sink.borrow_mut().take() {
+            Some(sink) => sink,
+            None => return,
+        };
+
+        sink(StmtMetric {
+                 sql: self.sql.clone().unwrap_or_default(),
+                 parse_time: self.parse_time,
+                 execute_time: self.execute_time.get(),
+                 fetch_time: self.fetch_time.get(),
+                 rows: self.get_row_count().unwrap_or(0),
+             });
+    }
+
+    /// Set the client and server versions used to check that version dependent features are
+    /// available before they are used.
+    #[doc(hidden)]
+    pub fn set_versions(&mut self, client: Option<version::Info>, server: version::Info) {
+        self.versions = Some((client, server));
+    }
+
+    /// Return an `ErrorKind::Unsupported` error if the versions of the connection that prepared
+    /// the statement are known and do not support the given feature.
+    fn check_feature(&self, feature: Feature) -> Result<()> {
+        match self.versions {
+            Some((ref client, ref server)) if !feature.is_supported(client.as_ref(), server) => {
+                Err(ErrorKind::Unsupported(feature, feature.required_version()).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the features required by the given execution mode.
+    fn check_exec_mode(&self, mode: ODPIExecMode) -> Result<()> {
+        if mode.contains(flags::BATCH_ERRORS) {
+            self.check_feature(Feature::BatchErrors)?;
+        }
+        if mode.contains(flags::ARRAY_DML_ROWCOUNTS) {
+            self.check_feature(Feature::ArrayDmlRowCounts)?;
+        }
+        Ok(())
+    }
+
+    /// Mark the statement as one that should never be tagged in the statement cache.
+    #[doc(hidden)]
+    pub fn set_uncached(&self) {
+        self.uncached.set(true);
     }
 
     /// Get the `inner` value.
@@ -49,10 +381,48 @@ impl Statement {
     /// was created.
     pub fn add_ref(&self) -> Result<()> {
         try_dpi!(externs::dpiStmt_addRef(self.inner),
-                 Ok(()),
+                 {
+                     self.refs.set(self.refs.get() + 1);
+                     Ok(())
+                 },
                  ErrorKind::Statement("dpiStmt_addRef".to_string()))
     }
 
+    /// Returns the rowids of the rows affected by the last call to `execute()` of a DML statement
+    /// with a `returning rowid into` clause, read from the variable of native type
+    /// DPI_NATIVE_TYPE_ROWID bound to the statement with `bind_by_name()` or `bind_by_pos()`. The
+    /// rowids are returned in the order they were returned by the database. The variable must have
+    /// been created with enough elements for every affected row. A reference is added to each
+    /// rowid, which should be released with `Rowid::release()` when it is no longer needed.
+    /// `ErrorKind::NoRowidVar` is returned if no rowid variable is bound.
+    ///
+    /// ODPI-C resets the returned values of the variable for every iteration of `execute_many()`,
+    /// so only the rowids of the last iteration would survive it, and
+    /// `ErrorKind::RowidsAfterExecuteMany` is returned instead if the last execution was made with
+    /// `execute_many()`.
+    pub fn affected_rowids(&self) -> Result<Vec<Rowid>> {
+        if self.executed_many.get() {
+            return Err(ErrorKind::RowidsAfterExecuteMany.into());
+        }
+        let bound_vars = self.bound_vars.borrow();
+        let rowid_var = bound_vars.iter()
+            .find(|&&(_, ref var)| var.native_type() == ODPINativeTypeNum::Rowid);
+
+        match rowid_var {
+            Some(&(_, ref var)) => var.get_rowids(),
+            None => Err(ErrorKind::NoRowidVar.into()),
+        }
+    }
+
+    /// Returns the batch errors that took place during the last execution with batch mode enabled,
+    /// each paired with the offset of the row in the input arrays that caused it. Offsets start
+    /// from 0. Batch errors are only available when both the client and the server are at 12.1.
+    pub fn batch_errors(&self) -> Result<Vec<(u32, error::Info)>> {
+        let count = self.get_batch_error_count()?;
+        let errors = self.get_batch_errors(count)?;
+        Ok(errors.into_iter().map(|err| (err.offset() as u32, err)).collect())
+    }
+
     /// Binds a variable to a named placeholder in the statement. A reference to the variable is
     /// retained by the library and is released when the statement itself is released or a new
     /// variable is bound to the same name.
@@ -61,12 +431,7 @@ impl Statement {
     /// which is to be bound.
     /// * `var` - a variable which is to be bound.
     pub fn bind_by_name(&self, name: &str, var: Var) -> Result<()> {
-        let name_s = ODPIStr::from(name);
-
-        /// TODO: Test this when Var is complete.
-        try_dpi!(externs::dpiStmt_bindByName(self.inner, name_s.ptr(), name_s.len(), var.inner()),
-                 Ok(()),
-                 ErrorKind::Statement("dpiStmt_bindByName".to_string()))
+        self.bind_var_by_name(name, &var)
     }
 
     /// Binds a variable to a placeholder in the statement by position. A reference to the variable
@@ -79,10 +444,50 @@ impl Statement {
     /// * `var` - a variable which is to be bound.
     pub fn bind_by_pos(&self, pos: u32, var: &Var) -> Result<()> {
         try_dpi!(externs::dpiStmt_bindByPos(self.inner, pos, var.inner()),
-                 Ok(()),
+                 self.record_bind(pos.into(), var),
                  ErrorKind::Statement("dpiStmt_bindByPos".to_string()))
     }
 
+    /// Binds binary data to the placeholder at the given position as a RAW value. The bytes are
+    /// passed to the database unchanged, without any character set conversion. A variable is
+    /// created internally and kept alive for as long as the statement is. The statement must have
+    /// been prepared with `Connection::prepare_stmt()`.
+    ///
+    /// * `pos` - the position which is to be bound, starting from 1.
+    /// * `value` - the bytes that are to be bound.
+    pub fn bind_bytes(&self, pos: u32, value: &[u8]) -> Result<()> {
+        let var = self.new_raw_var(value)?;
+        self.bind_by_pos(pos, &var)
+    }
+
+    /// Create a single element RAW variable holding the given bytes, so that they are bound
+    /// without any character set conversion.
+    fn new_raw_var(&self, value: &[u8]) -> Result<Var> {
+        let var = self.new_var(flags::ODPIOracleTypeNum::Raw,
+                               ODPINativeTypeNum::Bytes,
+                               value.len() as u32)?;
+        try_dpi!(externs::dpiVar_setFromBytes(var.inner(),
+                                              0,
+                                              value.as_ptr() as *const ::std::os::raw::c_char,
+                                              value.len() as u32),
+                 Ok(var),
+                 ErrorKind::Var("dpiVar_setFromBytes".to_string()))
+    }
+
+    /// Binds an open cursor to the placeholder at the given position, for passing a `SYS_REFCURSOR`
+    /// into PL/SQL. A variable of type `Stmt` is created internally and kept alive for as long as
+    /// the statement is. The statement must have been prepared with `Connection::prepare_stmt()`.
+    ///
+    /// * `pos` - the position which is to be bound. The position of a placeholder is determined by
+    /// its location in the statement. Placeholders are numbered from left to right, starting from
+    /// 1, and duplicate names do not count as additional placeholders.
+    /// * `cursor` - the open cursor that is to be bound.
+    pub fn bind_cursor(&self, pos: u32, cursor: &Statement) -> Result<()> {
+        let var = self.new_var(flags::ODPIOracleTypeNum::Stmt, ODPINativeTypeNum::Stmt, 0)?;
+        var.set_from_stmt(0, cursor)?;
+        self.bind_by_pos(pos, &var)
+    }
+
     /// Binds a value to a named placeholder in the statement without the need to create a variable
     /// directly. One is created implicitly and released when the statement is released or a new
     /// value is bound to the same name.
@@ -110,6 +515,59 @@ impl Statement {
                  ErrorKind::Statement("dpiStmt_bindValueByName".to_string()))
     }
 
+    /// Binds an owned value to a named placeholder in the statement. The value is copied into a
+    /// variable that is created implicitly, exactly as with `bind_value_by_name()`, except for
+    /// `OwnedData::Bytes`, which ODPI-C would bind as a VARCHAR and which is therefore bound as a
+    /// RAW value, as with `bind_bytes()`.
+    ///
+    /// * `name` - a string in the encoding used for CHAR data giving the name of the placeholder
+    /// which is to be bound.
+    /// * `value` - the value which is to be bound.
+    pub fn bind_data_by_name(&self, name: &str, value: &OwnedData) -> Result<()> {
+        if let OwnedData::Bytes(ref bytes) = *value {
+            let var = self.new_raw_var(bytes)?;
+            return self.bind_var_by_name(name, &var);
+        }
+        let name_s = ODPIStr::from(name);
+        let (native_type, mut data) = value.to_odpi();
+
+        try_dpi!(externs::dpiStmt_bindValueByName(self.inner,
+                                                  name_s.ptr(),
+                                                  name_s.len(),
+                                                  native_type,
+                                                  &mut data),
+                 Ok(()),
+                 ErrorKind::Statement("dpiStmt_bindValueByName".to_string()))
+    }
+
+    /// Binds an owned value to a placeholder in the statement by position, as with
+    /// `bind_data_by_name()`. `OwnedData::Bytes` is bound as a RAW value.
+    ///
+    /// * `pos` - the position which is to be bound. The position of a placeholder is determined by
+    /// its location in the statement. Placeholders are numbered from left to right, starting from
+    /// 1, and duplicate names do not count as additional placeholders.
+    /// * `value` - the value which is to be bound.
+    pub fn bind_data_by_pos(&self, pos: u32, value: &OwnedData) -> Result<()> {
+        if let OwnedData::Bytes(ref bytes) = *value {
+            return self.bind_bytes(pos, bytes);
+        }
+        let (native_type, mut data) = value.to_odpi();
+
+        try_dpi!(externs::dpiStmt_bindValueByPos(self.inner, pos, native_type, &mut data),
+                 Ok(()),
+                 ErrorKind::Statement("dpiStmt_bindValueByPos".to_string()))
+    }
+
+    /// Binds every value returned by `Bind::binds()` to its named placeholder.
+    ///
+    /// * `value` - the value providing the binds.
+    pub fn bind_struct<T: Bind>(&self, value: &T) -> Result<()> {
+        for (name, data) in value.binds() {
+            self.bind_data_by_name(name, &data)?;
+        }
+        Ok(())
+    }
+
     /// Binds a value to a placeholder in the statement without the need to create a variable
     /// directly. One is created implicitly and released when the statement is released or a new
     /// value is bound to the same position.
@@ -132,27 +590,169 @@ impl Statement {
                  ErrorKind::Statement("dpiStmt_bindValueByPos".to_string()))
     }
 
+    /// Returns the position and name of each placeholder in the statement, combining
+    /// `get_bind_count()` and `get_bind_names()`. A name used several times is returned once, at
+    /// the position where it first appears, as with `get_bind_names()`. In PL/SQL that is its
+    /// only position. In SQL every occurrence of a name counts as a position of its own, so later
+    /// names may be at a higher position than their index in the result; the positions are then
+    /// found by scanning the SQL text the statement was prepared with, and `ErrorKind::Statement`
+    /// is returned if it is not known.
+    pub fn binds(&self) -> Result<Vec<BindInfo>> {
+        let count = self.get_bind_count()?;
+        let mut names = self.get_bind_names(count)?;
+        names.retain(|name| !name.is_empty());
+
+        if names.len() as u32 != count {
+            names = match self.sql {
+                Some(ref sql) => placeholders(sql),
+                None => Vec::new(),
+            };
+            if names.len() as u32 != count {
+                return Err(ErrorKind::Statement("dpiStmt_getBindNames".to_string()).into());
+            }
+        }
+
+        let mut seen = Vec::new();
+        Ok(names.into_iter()
+               .enumerate()
+               .filter(|&(_, ref name)| if seen.contains(name) {
+                           false
+                       } else {
+                           seen.push(name.clone());
+                           true
+                       })
+               .map(|(idx, name)| {
+                        let positional = name.chars().all(|c| c.is_digit(10));
+                        BindInfo {
+                            position: idx as u32 + 1,
+                            name: if positional { None } else { Some(name) },
+                        }
+                    })
+               .collect())
+    }
+
     /// Closes the statement and makes it unusable for further work immediately, rather than when
     /// the reference count reaches zero.
     ///
     /// * `tag` - a key to associate the statement with in the statement cache, in the encoding used
     /// for CHAR data. None is also acceptable in which case the statement is not tagged. This value
     /// is ignored for statements that are acquired through bind variables (REF CURSOR) or implicit
-    /// results.
+    /// results, and for statements prepared with `Connection::prepare_uncached()`.
     pub fn close(&self, tag: Option<&str>) -> Result<()> {
-        let tag_s = ODPIStr::from(tag);
+        self.emit_metric();
+        let tag_s = if self.uncached.get() {
+            ODPIStr::from(None)
+        } else {
+            ODPIStr::from(tag)
+        };
         try_dpi!(externs::dpiStmt_close(self.inner, tag_s.ptr(), tag_s.len()),
                  Ok(()),
                  ErrorKind::Statement("dpiStmt_close".to_string()))
     }
 
-    // /// Defines the variable that will be used to fetch rows from the statement. A reference to
-    //the
-    // /// variable will be retained until the next define is performed on the same position or the
-    // /// statement is closed.
-    // pub fn define(&self, pos: u32, var: &mut Var) -> Result<()> {
-    //     Ok(())
-    // }
+    /// Defines the variable that will be used to fetch rows from the statement. A reference to the
+    /// variable will be retained until the next define is performed on the same position or the
+    /// statement is closed.
+    ///
+    /// A fetch into a variable whose buffer is smaller than the column fails with ORA-24345 (or
+    /// silently truncates the value, depending on the client), so for string and raw columns the
+    /// buffer size is checked against the column size reported by `get_query_info()` first, and
+    /// `ErrorKind::Truncation` is returned if it is too small. The variable should then be created
+    /// again with a larger size.
+    ///
+    /// * `pos` - the position which is being defined. The first position is 1.
+    /// * `var` - the variable which is to be used for fetching rows from the statement at the given
+    /// position. It should have at least as many elements as the fetch array size.
+    pub fn define(&self, pos: u32, var: &Var) -> Result<()> {
+        let info = self.get_query_info(pos)?;
+        match info.oracle_type_num() {
+            flags::ODPIOracleTypeNum::Varchar |
+            flags::ODPIOracleTypeNum::NVarchar |
+            flags::ODPIOracleTypeNum::Char |
+            flags::ODPIOracleTypeNum::NChar |
+            flags::ODPIOracleTypeNum::Raw => {
+                let needed = info.client_size_in_bytes();
+                let available = var.get_size_in_bytes()?;
+                if needed > available {
+                    return Err(ErrorKind::Truncation(pos, needed, available).into());
+                }
+            }
+            _ => {}
+        }
+
+        self.adaptive_fetch.set(false);
+        self.grow_before_fetch.set(false);
+        try_dpi!(externs::dpiStmt_define(self.inner, pos, var.inner()),
+                 Ok(()),
+                 ErrorKind::Statement("dpiStmt_define".to_string()))
+    }
+
+    /// Describes the columns of the query without fetching any rows, by executing the statement in
+    /// the `DESCRIBE_ONLY` mode. Each `query::Info` reports the name, type, size, precision, scale
+    /// and nullability of its column; a column declared NOT NULL, such as a primary key column,
+    /// reports `null_ok() == false`. ODPI-C does not report key membership or the owning schema and
+    /// table of a column, so those must be looked up in the data dictionary if they are needed.
+    pub fn describe(&self) -> Result<Vec<query::Info>> {
+        self.execute(flags::DESCRIBE_ONLY)?;
+        self.get_query_columns()
+    }
+
+    /// Enables adaptive fetching for the statement. Once `fetch_rows()` has handed out every row
+    /// fetched in a round trip and reports that more rows are available, the fetch array size is
+    /// doubled before the next round trip, up to `ADAPTIVE_FETCH_ARRAY_SIZE_CAP`, so large result
+    /// sets require fewer round trips to the database. The number of rows returned by each call
+    /// is still limited by its `max_rows` argument only.
+    ///
+    /// ODPI-C does not allow the fetch array size to grow past the number of elements of the
+    /// variables already defined for the query, so each column is defined again with a larger
+    /// variable before the fetch array size is raised. Variables defined by the caller with
+    /// `define()` must not be replaced, so `define()` turns adaptive fetching off, and fetching
+    /// with `fetch()` or `scroll()` cancels a pending growth, as rows may remain in the buffers.
+    ///
+    /// The tradeoff is memory: every column defined for fetching allocates a buffer of one element
+    /// per row of the fetch array, so a small result set stays cheap while the buffers of a wide
+    /// row at the cap can require several megabytes of client memory for a single statement.
+    pub fn enable_adaptive_fetch(&self) {
+        self.adaptive_fetch.set(true);
+    }
+
+    /// Doubles the fetch array size, up to `ADAPTIVE_FETCH_ARRAY_SIZE_CAP`, after defining every
+    /// column again with a variable of that many elements, allocated as ODPI-C allocates the
+    /// variables it defines implicitly. The variables replaced are released by ODPI-C, so this is
+    /// only called once the rows they hold have been handed out.
+    fn grow_fetch_array_size(&self) -> Result<()> {
+        let size = self.get_fetch_array_size()?;
+        let next = size.saturating_mul(2).min(ADAPTIVE_FETCH_ARRAY_SIZE_CAP);
+        if next <= size || self.conn.is_null() {
+            return Ok(());
+        }
+
+        for pos in 1..(self.get_num_query_columns()? + 1) {
+            let info = self.get_query_info(pos)?;
+            let native_type = info.default_native_type_num();
+            let object_type = info.object_type().map_or(ptr::null_mut(), |t| t.inner());
+            let mut var_ptr = ptr::null_mut();
+            let mut data_ptr = ptr::null_mut();
+            let var: Result<Var> =
+                try_dpi!(externs::dpiConn_newVar(self.conn,
+                                                 info.oracle_type_num(),
+                                                 native_type,
+                                                 next,
+                                                 info.client_size_in_bytes(),
+                                                 1,
+                                                 0,
+                                                 object_type,
+                                                 &mut var_ptr,
+                                                 &mut data_ptr),
+                         Ok(Var::new(var_ptr, native_type)),
+                         ErrorKind::Statement("dpiConn_newVar".to_string()));
+            let var = var?;
+            try_dpi!(externs::dpiStmt_define(self.inner, pos, var.inner()),
+                     Ok(()),
+                     ErrorKind::Statement("dpiStmt_define".to_string()))?;
+        }
+        self.set_fetch_array_size(next)
+    }
 
     /// Executes the statement using the bound values. For queries this makes available metadata
     /// which can be acquired using the function dpiStmt_getQueryInfo(). For non-queries, out and
@@ -160,10 +760,109 @@ impl Statement {
     ///
     /// * `mode` - one or more of the values from the enumeration `ODPIExecMode`, OR'ed together.
     pub fn execute(&self, mode: ODPIExecMode) -> Result<u32> {
+        self.check_exec_mode(mode)?;
+        self.executed_many.set(false);
+        *self.last_warning.borrow_mut() = None;
+        *self.defined_unit.borrow_mut() = None;
         let mut cols_queried = 0;
-        try_dpi!(externs::dpiStmt_execute(self.inner, mode, &mut cols_queried),
-                 Ok(cols_queried),
-                 ErrorKind::Statement("dpiStmt_execute".to_string()))
+        let start = Instant::now();
+        let res: Result<u32> =
+            try_dpi!(externs::dpiStmt_execute(self.inner, mode, &mut cols_queried),
+                     Ok(cols_queried),
+                     ErrorKind::Statement("dpiStmt_execute".to_string()));
+        self.execute_time.set(self.execute_time.get() + start.elapsed());
+
+        if res.is_ok() {
+            *self.defined_unit.borrow_mut() = self.sql.as_ref().and_then(|sql| stored_unit(sql));
+        }
+        res
+    }
+
+    /// Look up the compilation errors of the stored unit just defined by the statement, which
+    /// OCI reports as success with info (ORA-24344) but ODPI-C 2 reports as plain success.
+    fn compile_warning(&self, unit_type: &str, name: &str) -> Result<Option<error::Info>> {
+        if self.conn.is_null() {
+            return Ok(None);
+        }
+        let sql = ODPIStr::from("select line, position, text from user_errors \
+                                 where type = :1 and name = :2 and attribute = 'ERROR' \
+                                 order by sequence");
+        let mut stmt_ptr = ptr::null_mut();
+        let stmt: Result<Statement> =
+            try_dpi!(externs::dpiConn_prepareStmt(self.conn,
+                                                  0,
+                                                  sql.ptr(),
+                                                  sql.len(),
+                                                  ptr::null(),
+                                                  0,
+                                                  &mut stmt_ptr),
+                     Ok(Statement::new(stmt_ptr)),
+                     ErrorKind::Statement("dpiConn_prepareStmt".to_string()));
+        let stmt = stmt?;
+        stmt.bind_data_by_pos(1, &OwnedData::String(unit_type.to_string()))?;
+        stmt.bind_data_by_pos(2, &OwnedData::String(name.to_string()))?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        let row = stmt.fetch_row()?;
+        stmt.close(None)?;
+
+        match row {
+            Some(row) => {
+                let message = format!("ORA-24344: success with compilation error: {} {} line \
+                                       {}, column {}: {}",
+                                      unit_type,
+                                      name,
+                                      row.get::<i64>(1)?,
+                                      row.get::<i64>(2)?,
+                                      row.get::<String>(3)?);
+                Ok(Some(error::Info::new(ORA_SUCCESS_WITH_COMPILATION_ERROR,
+                                         0,
+                                         message,
+                                         "dpiStmt_execute".to_string(),
+                                         "execute".to_string(),
+                                         String::new(),
+                                         false)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Executes a DML statement with a RETURNING INTO clause and collects the returned values. Each
+    /// output variable is bound to its placeholder before the statement is executed, and the values
+    /// at the same position in every variable are returned together as one row, so the result has
+    /// one row per affected row and one column per output variable, in the order given.
+    ///
+    /// The returned values are copied out of the variables before this function returns, so it is
+    /// safe to execute with `COMMIT_ON_SUCCESS`: the commit is made in the same round trip as the
+    /// statement, and the values are read afterwards from the variables, which remain valid until
+    /// they are bound again or released. Reading the variables directly with `Var::get_value()`
+    /// afterwards only gives access to the rows returned by this execution.
+    ///
+    /// * `mode` - one or more of the values from the enumeration `ODPIExecMode`, OR'ed together.
+    /// * `outputs` - the placeholder name and output variable for each returned column. Each
+    /// variable must have been created with enough elements for all of the affected rows.
+    pub fn execute_returning(&self,
+                             mode: ODPIExecMode,
+                             outputs: &[(&str, &Var)])
+                             -> Result<Vec<Vec<OwnedData>>> {
+        for &(name, var) in outputs {
+            self.bind_var_by_name(name, var)?;
+        }
+        self.execute(mode)?;
+
+        let mut num_rows = self.get_row_count()?;
+        for &(_, var) in outputs {
+            num_rows = num_rows.min(var.get_data()?.len() as u64);
+        }
+
+        let mut rows = Vec::with_capacity(num_rows as usize);
+        for pos in 0..num_rows as u32 {
+            let mut row = Vec::with_capacity(outputs.len());
+            for &(_, var) in outputs {
+                row.push(var.get_value(pos)?);
+            }
+            rows.push(row);
+        }
+        Ok(rows)
     }
 
     /// Executes the statement the specified number of times using the bound values. Each bound
@@ -173,9 +872,14 @@ impl Statement {
     /// * `num_iters` - the number of times the statement is executed. Each iteration corresponds to
     /// one of the elements of the array that was bound earlier.
     pub fn execute_many(&self, mode: ODPIExecMode, num_iters: u32) -> Result<()> {
-        try_dpi!(externs::dpiStmt_executeMany(self.inner, mode, num_iters),
-                 Ok(()),
-                 ErrorKind::Statement("dpiStmt_executeMany".to_string()))
+        self.check_exec_mode(mode)?;
+        self.executed_many.set(true);
+        let start = Instant::now();
+        let res: Result<()> = try_dpi!(externs::dpiStmt_executeMany(self.inner, mode, num_iters),
+                                       Ok(()),
+                                       ErrorKind::Statement("dpiStmt_executeMany".to_string()));
+        self.execute_time.set(self.execute_time.get() + start.elapsed());
+        res
     }
 
     /// Fetches a single row from the statement. If the statement does not refer to a query an error
@@ -186,10 +890,116 @@ impl Statement {
     pub fn fetch(&self) -> Result<(bool, u32)> {
         let mut found = 0;
         let mut buffer_row_index = 0;
+        let start = Instant::now();
+        self.grow_before_fetch.set(false);
+
+        let res: Result<(bool, u32)> =
+            try_dpi!(externs::dpiStmt_fetch(self.inner, &mut found, &mut buffer_row_index),
+                     Ok((found == 1, buffer_row_index)),
+                     ErrorKind::Statement("dpiStmt_fetch".to_string()));
+        self.fetch_time.set(self.fetch_time.get() + start.elapsed());
+        res
+    }
+
+    /// Returns the cursor in the column at the given position for the currently fetched row, as
+    /// with `get_query_value()`, for queries with a CURSOR expression or a SYS_REFCURSOR column.
+    /// Cursors are statement handles rather than values, so they cannot be copied out by
+    /// `fetch_row()` and must be read with this function after `fetch()`. A reference is added to
+    /// the cursor, so it remains valid after further rows have been fetched. As with implicit
+    /// results, the cursor has already been executed, so its rows are fetched straight away. An
+    /// error is returned if the column is NULL or is not a cursor.
+    ///
+    /// * `col` - the position of the column, starting from 1.
+    pub fn fetch_cursor(&self, col: u32) -> Result<Statement> {
+        let (native_type, data) = self.get_query_value(col)?;
+
+        if native_type != ODPINativeTypeNum::Stmt {
+            return Err(ErrorKind::TypeMismatch("Stmt".to_string(), format!("{:?}", native_type))
+                           .into());
+        }
+        if data.is_null() || unsafe { (*data).is_null } == 1 {
+            return Err(ErrorKind::NullValue.into());
+        }
+
+        let cursor_ptr = unsafe { (*data).value.as_stmt };
+        try_dpi!(externs::dpiStmt_addRef(cursor_ptr),
+                 {
+                     let mut cursor = Statement::new(cursor_ptr);
+                     cursor.conn = self.conn;
+                     cursor.versions = self.versions.clone();
+                     Ok(cursor)
+                 },
+                 ErrorKind::Statement("dpiStmt_addRef".to_string()))
+    }
+
+    /// Fetches a single row from the statement and returns the owned values of all of its columns,
+    /// in query order, without the caller needing to know the column types in advance. Each value
+    /// is read with `get_query_value()` and converted according to the native type reported for
+    /// its column. Returns `None` when no more rows are available.
+    pub fn fetch_dynamic(&self) -> Result<Option<Vec<OwnedData>>> {
+        Ok(self.fetch_row()?.map(Row::into_values))
+    }
+
+    /// Returns the LOB in the column at the given position for the currently fetched row, as with
+    /// `get_query_value()`. A reference is added to the LOB, so it remains valid after further
+    /// rows have been fetched and should be released with `Lob::release()` when it is no longer
+    /// needed. An error is returned if the column is NULL or is not a LOB. The contents of NCLOB
+    /// columns are read in the encoding used for NCHAR data, see `Lob::read_string()`.
+    ///
+    /// * `col` - the position of the column, starting from 1.
+    pub fn fetch_lob(&self, col: u32) -> Result<Lob> {
+        let (native_type, data) = self.get_query_value(col)?;
+
+        if native_type != ODPINativeTypeNum::Lob {
+            return Err(ErrorKind::TypeMismatch("Lob".to_string(), format!("{:?}", native_type))
+                           .into());
+        }
+        if data.is_null() || unsafe { (*data).is_null } == 1 {
+            return Err(ErrorKind::NullValue.into());
+        }
+
+        let lob_ptr = unsafe { (*data).value.as_lob };
+        try_dpi!(externs::dpiLob_addRef(lob_ptr),
+                 Ok(lob_ptr.into()),
+                 ErrorKind::Lob("dpiLob_addRef".to_string()))
+    }
+
+    /// Fetches a single row from the statement and copies the values of all of its columns out of
+    /// the ODPI-C buffers. Returns `None` when no more rows are available. Cursor columns cannot be
+    /// copied, so `ErrorKind::CursorColumn` is returned for a query that has one; such rows must
+    /// be read with `fetch()` and `fetch_cursor()` instead.
+    pub fn fetch_row(&self) -> Result<Option<Row>> {
+        let (found, _) = self.fetch()?;
+        if !found {
+            return Ok(None);
+        }
+
+        let cols = self.get_num_query_columns()?;
+        let mut values = Vec::with_capacity(cols as usize);
+        for pos in 1..(cols + 1) {
+            let oracle_type = self.get_query_info(pos)?.oracle_type_num();
+            let (native_type, data) = self.get_query_value(pos)?;
+            if native_type == ODPINativeTypeNum::Stmt {
+                return Err(ErrorKind::CursorColumn(pos).into());
+            }
+            values.push(OwnedData::from_column(oracle_type, native_type, &data.into())?);
+        }
+        Ok(Some(Row::new(values)))
+    }
+
+    /// Fetches a single row from the statement, as with `fetch_row()`, and returns it as a JSON
+    /// object keyed by column name. See `Row::to_json()` for how the values are written.
+    pub fn fetch_row_json(&self) -> Result<Option<String>> {
+        let row = match self.fetch_row()? {
+            Some(row) => row,
+            None => return Ok(None),
+        };
 
-        try_dpi!(externs::dpiStmt_fetch(self.inner, &mut found, &mut buffer_row_index),
-                 Ok((found == 1, buffer_row_index)),
-                 ErrorKind::Statement("dpiStmt_fetch".to_string()))
+        let mut columns = Vec::with_capacity(row.num_columns() as usize);
+        for pos in 1..(row.num_columns() + 1) {
+            columns.push(self.get_query_info(pos)?);
+        }
+        Ok(Some(row.to_json(&columns)))
     }
 
     /// Returns the number of rows that are available in the buffers defined for the query. If no
@@ -203,22 +1013,41 @@ impl Statement {
     ///
     /// Returns a tuple representing (row_index, num_rows_fetched, more_rows).
     pub fn fetch_rows(&self, max_rows: u32) -> Result<(u32, u32, bool)> {
+        if self.grow_before_fetch.get() {
+            self.grow_before_fetch.set(false);
+            self.grow_fetch_array_size()?;
+        }
         let mut buffer_row_index = 0;
         let mut num_rows_fetched = 0;
         let mut more_rows = 0;
-
-        try_dpi!(externs::dpiStmt_fetchRows(self.inner,
-                                            max_rows,
-                                            &mut buffer_row_index,
-                                            &mut num_rows_fetched,
-                                            &mut more_rows),
-                 Ok((buffer_row_index, num_rows_fetched, more_rows == 1)),
-                 ErrorKind::Statement("dpiStmt_fetchRows".to_string()))
+        let start = Instant::now();
+
+        let res: Result<(u32, u32, bool)> =
+            try_dpi!(externs::dpiStmt_fetchRows(self.inner,
+                                                max_rows,
+                                                &mut buffer_row_index,
+                                                &mut num_rows_fetched,
+                                                &mut more_rows),
+                     Ok((buffer_row_index, num_rows_fetched, more_rows == 1)),
+                     ErrorKind::Statement("dpiStmt_fetchRows".to_string()));
+        self.fetch_time.set(self.fetch_time.get() + start.elapsed());
+
+        if let Ok((index, fetched, true)) = res {
+            if self.adaptive_fetch.get() {
+                // Growing replaces the variables, so wait until the buffers have been emptied:
+                // fewer rows than requested means none were left, and so does reaching the end of
+                // a full round trip.
+                let size = self.get_fetch_array_size()?;
+                self.grow_before_fetch.set(fetched < max_rows || index + fetched >= size);
+            }
+        }
+        res
     }
 
     /// Returns the number of batch errors that took place during the last execution with batch mode
     /// enabled. Batch errors are only available when both the client and the server are at 12.1.
     pub fn get_batch_error_count(&self) -> Result<u32> {
+        self.check_feature(Feature::BatchErrors)?;
         let mut count = 0;
 
         try_dpi!(externs::dpiStmt_getBatchErrorCount(self.inner, &mut count),
@@ -232,12 +1061,11 @@ impl Statement {
     /// * `num_errors` - the size of the errors array in number of elements. The number of batch
     /// errors that are available can be determined using `get_batch_error_count()`.
     pub fn get_batch_errors(&self, num_errors: u32) -> Result<Vec<error::Info>> {
-        let err_ptr = ptr::null_mut();
+        self.check_feature(Feature::BatchErrors)?;
+        let mut odpi_vec = vec![ODPIErrorInfo::default(); num_errors as usize];
 
-        try_dpi!(externs::dpiStmt_getBatchErrors(self.inner, num_errors, err_ptr),
+        try_dpi!(externs::dpiStmt_getBatchErrors(self.inner, num_errors, odpi_vec.as_mut_ptr()),
                  {
-                     let err_slice = unsafe { slice::from_raw_parts(err_ptr, num_errors as usize) };
-                     let odpi_vec = Vec::from(err_slice);
                      let res_vec = odpi_vec.iter().map(|x| (*x).into()).collect();
                      Ok(res_vec)
                  },
@@ -290,10 +1118,52 @@ impl Statement {
                  ErrorKind::Statement("dpiStmt_getFetchArraySize".to_string()))
     }
 
-    /// Returns the next implicit result available from the last execution of the statement.
-    /// Implicit results are only available when both the client and server are 12.1 or higher.
-    pub fn get_implicit_result(&self) -> Result<()> {
-        Err(ErrorKind::Statement("Not Implemented!".to_string()).into())
+    /// Returns the next implicit result available from the last execution of the statement, or
+    /// `None` when there are no more, so callers loop until `None` is returned. Once the implicit
+    /// results are exhausted further calls keep returning `None`. Implicit results are only
+    /// available when both the client and server are 12.1 or higher, and any error reported by
+    /// ODPI-C, for example for a statement that has been closed, is returned as is.
+    ///
+    /// Each implicit result is a query that has already been executed by the PL/SQL block that
+    /// returned it, so it must not be executed again. Its column metadata is available straight
+    /// away from `get_query_columns()` (or `get_num_query_columns()` and `get_query_info()`),
+    /// before any rows are fetched, and its rows are fetched as with any other query. The statement
+    /// should be released with `release()` when it is no longer needed.
+    pub fn get_implicit_result(&self) -> Result<Option<Statement>> {
+        self.check_feature(Feature::ImplicitResults)?;
+        let mut implicit_ptr = ptr::null_mut();
+
+        try_dpi!(externs::dpiStmt_getImplicitResult(self.inner, &mut implicit_ptr),
+                 {
+                     if implicit_ptr.is_null() {
+                         Ok(None)
+                     } else {
+                         let mut implicit = Statement::new(implicit_ptr);
+                         implicit.conn = self.conn;
+                         implicit.versions = self.versions.clone();
+                         Ok(Some(implicit))
+                     }
+                 },
+                 ErrorKind::Statement("dpiStmt_getImplicitResult".to_string()))
+    }
+
+    /// Returns the first element of a variable bound with `bind_by_name()` or `bind_by_pos()`,
+    /// converted to the requested type. This is intended for reading OUT and IN/OUT parameters of
+    /// PL/SQL blocks after the statement has been executed. The variable must be looked up the same
+    /// way it was bound, by name or by position.
+    ///
+    /// * `key` - the position (starting from 1) or name of the placeholder the variable was bound
+    /// to.
+    pub fn get_out<T: FromData, K: Into<BindKey>>(&self, key: K) -> Result<T> {
+        let key = key.into().normalize();
+        let bound_vars = self.bound_vars.borrow();
+
+        match bound_vars.iter().find(|&&(ref k, _)| *k == key) {
+            Some(&(_, ref var)) => T::from_data(&var.get_value(0)?),
+            None => {
+                Err(ErrorKind::Statement(format!("get_out: nothing bound to {:?}", key)).into())
+            }
+        }
     }
 
     /// Returns information about the statement.
@@ -323,6 +1193,17 @@ impl Statement {
                  ErrorKind::Statement("dpiStmt_getQueryInfo".to_string()))
     }
 
+    /// Returns information about every column of the query, in query order. The statement must
+    /// have been executed, or be an implicit result returned by `get_implicit_result()`.
+    pub fn get_query_columns(&self) -> Result<Vec<query::Info>> {
+        let cols = self.get_num_query_columns()?;
+        let mut infos = Vec::with_capacity(cols as usize);
+        for pos in 1..(cols + 1) {
+            infos.push(self.get_query_info(pos)?);
+        }
+        Ok(infos)
+    }
+
     /// Returns the value of the column at the given position for the currently fetched row, without
     /// needing to provide a variable.
     pub fn get_query_value(&self, pos: u32) -> Result<(ODPINativeTypeNum, *mut ODPIData)> {
@@ -344,11 +1225,69 @@ impl Statement {
                  ErrorKind::Statement("dpiStmt_getRowCount".to_string()))
     }
 
-    /// Returns an array of row counts affected by the last invocation of `Statement::executeMany()`
-    /// with the array DML rowcounts mode enabled. This feature is only available if both client and
-    /// server are at 12.1.
+    /// Returns an array of row counts affected by the last invocation of `execute_many()` with the
+    /// array DML rowcounts mode enabled, one for each iteration in the order the iterations were
+    /// executed. This feature is only available if both client and server are at 12.1.
     pub fn get_row_counts(&self) -> Result<Vec<u64>> {
-        Err(ErrorKind::Statement("Not Implemented!".to_string()).into())
+        self.check_feature(Feature::ArrayDmlRowCounts)?;
+        let mut num_row_counts = 0;
+        let mut row_counts_ptr = ptr::null_mut();
+
+        try_dpi!(externs::dpiStmt_getRowCounts(self.inner,
+                                               &mut num_row_counts,
+                                               &mut row_counts_ptr),
+                 {
+                     if row_counts_ptr.is_null() {
+                         Ok(Vec::new())
+                     } else {
+                         let row_counts = unsafe {
+                             slice::from_raw_parts(row_counts_ptr, num_row_counts as usize)
+                         };
+                         Ok(row_counts.to_vec())
+                     }
+                 },
+                 ErrorKind::Statement("dpiStmt_getRowCounts".to_string()))
+    }
+
+    /// Returns the SQL_ID of the statement, for correlating it with AWR and ASH data. ODPI-C does
+    /// not expose the SQL_ID, so this is a best-effort lookup of the text of the statement in
+    /// `v$sql`, which requires SELECT access to that view. The text is compared in full against
+    /// `v$sql.sql_fulltext`, with the 1000 characters kept in `v$sql.sql_text` only used to narrow
+    /// the search, so statements sharing a long prefix are told apart. `None` is returned if the
+    /// statement has not been executed yet or was prepared from the statement cache by tag only.
+    pub fn get_sql_id(&self) -> Result<Option<String>> {
+        let sql = match self.sql {
+            Some(ref sql) => sql,
+            None => return Ok(None),
+        };
+        if self.conn.is_null() {
+            return Ok(None);
+        }
+        let lookup_s = ODPIStr::from("select sql_id from v$sql \
+                                      where sql_text = substr(:sql, 1, 1000) \
+                                      and dbms_lob.compare(sql_fulltext, to_clob(:sql)) = 0 \
+                                      and rownum = 1");
+        let mut stmt_ptr = ptr::null_mut();
+
+        try_dpi!(externs::dpiConn_prepareStmt(self.conn,
+                                              0,
+                                              lookup_s.ptr(),
+                                              lookup_s.len(),
+                                              ptr::null(),
+                                              0,
+                                              &mut stmt_ptr),
+                 {
+                     let lookup = Statement::new(stmt_ptr);
+                     lookup.bind_data_by_name(":sql", &OwnedData::String(sql.clone()))?;
+                     lookup.execute(flags::EXEC_DEFAULT)?;
+                     let row = lookup.fetch_row()?;
+                     lookup.close(None)?;
+                     match row {
+                         Some(row) => row.get_opt::<String>(1),
+                         None => Ok(None),
+                     }
+                 },
+                 ErrorKind::Statement("dpiConn_prepareStmt".to_string()))
     }
 
     /// Returns the id of the query that was just registered on the subscription by calling
@@ -357,16 +1296,46 @@ impl Statement {
         Err(ErrorKind::Statement("Not Implemented!".to_string()).into())
     }
 
+    /// Returns the warning raised by the last execution of the statement, or `None` if it raised
+    /// none. Creating a stored unit such as a procedure with compilation errors succeeds, leaving
+    /// the unit invalid, so the first error from `USER_ERRORS` is returned as an ORA-24344 warning
+    /// rather than being silently swallowed. The errors are only queried on the first call after
+    /// such an execution, so call this before the unit is defined again. The statement must have
+    /// been prepared with `Connection::prepare_stmt()`.
+    pub fn last_warning(&self) -> Option<error::Info> {
+        let defined_unit = self.defined_unit.borrow_mut().take();
+        if let Some((unit_type, name)) = defined_unit {
+            // The definition itself succeeded, so a failure to look up its errors is not
+            // reported in its place.
+            if let Ok(warning) = self.compile_warning(&unit_type, &name) {
+                *self.last_warning.borrow_mut() = warning;
+            }
+        }
+        self.last_warning.borrow().clone()
+    }
+
     /// Releases a reference to the statement. A count of the references to the statement is
     /// maintained and when this count reaches zero, the memory associated with the statement is
     /// freed and the statement is closed if that has not already taken place using the function
-    /// `close()`.
+    /// `close()`. The reference given up is no longer released when the statement is dropped.
     pub fn release(&self) -> Result<()> {
+        self.refs.set(self.refs.get().saturating_sub(1));
         try_dpi!(externs::dpiStmt_release(self.inner),
                  Ok(()),
                  ErrorKind::Statement("dpiStmt_release".to_string()))
     }
 
+    /// Returns an iterator over the remaining rows of an executed query, fetching each with
+    /// `fetch_row()`. The values are copied out of the ODPI-C buffers, which are overwritten by the
+    /// next fetch, so each row remains valid after iteration moves on. An error fetching a row is
+    /// yielded in place of the row and ends the iteration.
+    pub fn rows<'a>(&'a self) -> Rows<'a> {
+        Rows {
+            stmt: self,
+            done: false,
+        }
+    }
+
     /// Scrolls the statement to the position in the cursor specified by the mode and offset.
     ///
     /// * `mode` - one of the values from the enumeration `ODPIFetchMode`.
@@ -374,6 +1343,7 @@ impl Statement {
     /// the cursor.
     /// * `row_count_offset` -
     pub fn scroll(&self, mode: ODPIFetchMode, offset: i32, row_count_offset: i32) -> Result<()> {
+        self.grow_before_fetch.set(false);
         try_dpi!(externs::dpiStmt_scroll(self.inner, mode, offset, row_count_offset),
                  Ok(()),
                  ErrorKind::Statement("dpiStmt_scroll".to_string()))
@@ -384,10 +1354,182 @@ impl Statement {
     /// network round trips are required to fetch rows from the database but more memory is also
     /// required. A value of zero will reset the array size to the default value of
     /// DPI_DEFAULT_FETCH_ARRAY_SIZE.
-    pub fn set_fetch_array_size(&self, _array_size: u32) -> Result<()> {
-        Err(ErrorKind::Statement("Not Implemented!".to_string()).into())
-    }
-}
+    ///
+    /// * `array_size` - the number of rows to fetch per round trip. Values larger than
+    /// `MAX_FETCH_ARRAY_SIZE` are rejected with `ErrorKind::InvalidFetchArraySize` without calling
+    /// ODPI-C.
+    pub fn set_fetch_array_size(&self, array_size: u32) -> Result<()> {
+        if array_size > MAX_FETCH_ARRAY_SIZE {
+            return Err(ErrorKind::InvalidFetchArraySize(array_size, MAX_FETCH_ARRAY_SIZE).into());
+        }
+
+        try_dpi!(externs::dpiStmt_setFetchArraySize(self.inner, array_size),
+                 Ok(()),
+                 ErrorKind::Statement("dpiStmt_setFetchArraySize".to_string()))
+    }
+
+    /// Fetches the rows of an executed query on a background thread, sending the values of each
+    /// row to the given channel as they are fetched. The first error ends the stream after it has
+    /// been sent. The statement is closed and the sender dropped when the rows are exhausted, which
+    /// closes the channel, or when the receiver hangs up. The connection must remain open until
+    /// the returned thread has finished.
+    ///
+    /// * `sender` - the channel to which each row, or the error that ended the stream, is sent.
+    pub fn spawn_stream(self, sender: Sender<Result<Vec<OwnedData>>>) -> JoinHandle<()> {
+        let stream = StreamStmt(self);
+
+        thread::spawn(move || {
+            let stmt = stream.0;
+            loop {
+                match stmt.fetch_dynamic() {
+                    Ok(Some(values)) => {
+                        if sender.send(Ok(values)).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        let _ = sender.send(Err(e));
+                        break;
+                    }
+                }
+            }
+            let _ = stmt.close(None);
+        })
+    }
+
+    /// Returns the total number of rows affected by the last call to `execute_many()`. When the
+    /// statement was executed with the `ARRAY_DML_ROWCOUNTS` mode the row counts of every iteration
+    /// are summed; otherwise the row count reported by `get_row_count()` is returned.
+    pub fn total_affected_after_many(&self) -> Result<u64> {
+        match self.get_row_counts() {
+            Ok(ref counts) if !counts.is_empty() => Ok(counts.iter().sum()),
+            _ => self.get_row_count(),
+        }
+    }
+}
+
+impl Drop for Statement {
+    fn drop(&mut self) {
+        if self.inner.is_null() {
+            return;
+        }
+        if self.refs.get() > 0 {
+            // A panic must not unwind out of a drop, which may itself be running during a panic,
+            // so one raised by the sink is caught and the metric is lost.
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| self.emit_metric()));
+        }
+
+        for _ in 0..self.refs.get() {
+            if unsafe { externs::dpiStmt_release(self.inner) } != constants::DPI_SUCCESS {
+                warn_on_drop("Failed to release the statement on drop");
+                break;
+            }
+        }
+        self.refs.set(0);
+    }
+}
+
+/// An iterator over the rows of a query, returned by `Statement::rows()`.
+/// Once it has returned `None` it keeps returning `None` without fetching again.
+pub struct Rows<'a> {
+    /// The statement the rows are fetched from.
+    stmt: &'a Statement,
+    /// Set once the rows are exhausted or a fetch has failed.
+    done: bool,
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Result<Row>> {
+        if self.done {
+            return None;
+        }
+
+        match self.stmt.fetch_row() {
+            Ok(Some(row)) => Some(Ok(row)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+
+    /// ODPI-C only reports the number of rows fetched so far, not how many remain, so no lower
+    /// bound is known before the rows are exhausted.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done { (0, Some(0)) } else { (0, None) }
+    }
+}
+
+/// An iterator over the rows of a query that owns its statement, returned by functions such as
+/// `Connection::query_in()` that prepare the statement themselves. The statement is released when
+/// the iterator is dropped. Like `Rows`, it keeps returning `None` once the rows are exhausted.
+pub struct RowIterator {
+    /// The statement the rows are fetched from.
+    stmt: Statement,
+    /// Set once the rows are exhausted or a fetch has failed.
+    done: bool,
+}
+
+impl RowIterator {
+    /// Create a new row iterator over the rows of the given executed statement.
+    #[doc(hidden)]
+    pub fn new(stmt: Statement) -> RowIterator {
+        RowIterator {
+            stmt: stmt,
+            done: false,
+        }
+    }
+
+    /// Returns the statement the rows are fetched from, for example to read its query info.
+    pub fn statement(&self) -> &Statement {
+        &self.stmt
+    }
+}
+
+impl<'a> FusedIterator for Rows<'a> {}
+
+impl Iterator for RowIterator {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Result<Row>> {
+        if self.done {
+            return None;
+        }
+
+        match self.stmt.fetch_row() {
+            Ok(Some(row)) => Some(Ok(row)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+
+    /// The number of remaining rows is unknown until they are exhausted, as for `Rows`.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done { (0, Some(0)) } else { (0, None) }
+    }
+}
+
+impl FusedIterator for RowIterator {}
+
+/// A statement that can be moved to the thread started by `Statement::spawn_stream()`. ODPI-C
+/// creates all handles in threaded mode, the statement is only used by that thread once moved, and
+/// its metrics sink, if any, is required to be `Send` and `Sync`.
+struct StreamStmt(Statement);
+
+unsafe impl Send for StreamStmt {}
 
 /// This structure is used for passing information about a statement from ODPI-C. It is used by the
 /// function `Statement::getInfo()`.
@@ -437,184 +1579,1253 @@ impl Info {
     pub fn is_returning(&self) -> bool {
         self.inner.is_returning == 1
     }
-}
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Bind, Statement, ADAPTIVE_FETCH_ARRAY_SIZE_CAP, MAX_FETCH_ARRAY_SIZE};
+    use common::version::{self, Feature};
+    use connection::Connection;
+    use data::{Data, FromData, OwnedData};
+    use error::{self, Result};
+    use odpi::{constants, flags};
+    use odpi::flags::ODPIFetchMode::*;
+    use odpi::flags::ODPINativeTypeNum::*;
+    use odpi::flags::ODPIOracleTypeNum::*;
+    use odpi::flags::ODPIStatementType::*;
+    use odpi::structs::{ODPIBytes, ODPIDataValueUnion, ODPIVersionInfo};
+    use rand::{self, Rng};
+    use std::ptr;
+    use std::sync::mpsc;
+    use test::{ConnResult, ContextResult, CONN, CREDS, CTXT, ENC};
+    use util::ODPIStr;
+
+    #[test]
+    fn add_ref_release() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+        match conn.prepare_stmt(Some("select 1 from dual"), None, false) {
+            Ok(stmt) => {
+                match stmt.add_ref() {
+                    Ok(_) => {
+                        match stmt.release() {
+                            Ok(_) => assert!(true),
+                            Err(_) => assert!(false),
+                        }
+                    }
+                    Err(e) => ::test::error_info(e),
+                }
+            }
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    fn bind_by_name() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+        match conn.new_var(Varchar, Bytes, 1, 256, false, false) {
+            Ok(var) => {
+                match conn.prepare_stmt(Some("select * from username where username = :username"),
+                                        None,
+                                        false) {
+                    Ok(stmt) => {
+                        match stmt.bind_by_name(":username", var) {
+                            Ok(_) => assert!(true),
+                            Err(e) => ::test::error_info(e),
+                        }
+                    }
+                    Err(e) => ::test::error_info(e),
+                }
+            }
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    fn bind_by_pos() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+        match conn.new_var(Varchar, Bytes, 1, 256, false, false) {
+            Ok(ref var) => {
+                match conn.prepare_stmt(Some("select * from username where username = :username"),
+                                        None,
+                                        false) {
+                    Ok(stmt) => {
+                        match stmt.bind_by_pos(1, var) {
+                            Ok(_) => assert!(true),
+                            Err(e) => ::test::error_info(e),
+                        }
+                    }
+                    Err(e) => ::test::error_info(e),
+                }
+            }
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    fn bind_value_by_name() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+        match conn.prepare_stmt(Some("select * from username where username = :username"),
+                                None,
+                                false) {
+            Ok(stmt) => {
+                let blah = ODPIStr::from("test");
+                let enc = String::from("UTF-8\0");
+
+                let odpi_bytes = ODPIBytes {
+                    ptr: blah.ptr() as *mut i8,
+                    length: blah.len(),
+                    encoding: enc.as_ptr() as *const ::std::os::raw::c_char,
+                };
+
+                let data = Data::new(false, ODPIDataValueUnion { as_bytes: odpi_bytes });
+                match stmt.bind_value_by_name(":username", Bytes, data) {
+                    Ok(_) => assert!(true),
+                    Err(e) => ::test::error_info(e),
+                }
+            }
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    #[test]
+    fn bind_value_by_pos() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+        match conn.prepare_stmt(Some("select * from username where username = :username"),
+                                None,
+                                false) {
+            Ok(stmt) => {
+                let blah = ODPIStr::from("test");
+                let enc = String::from("UTF-8\0");
+
+                let odpi_bytes = ODPIBytes {
+                    ptr: blah.ptr() as *mut i8,
+                    length: blah.len(),
+                    encoding: enc.as_ptr() as *const ::std::os::raw::c_char,
+                };
+
+                let data = Data::new(false, ODPIDataValueUnion { as_bytes: odpi_bytes });
+                match stmt.bind_value_by_pos(1, Bytes, data) {
+                    Ok(_) => assert!(true),
+                    Err(_e) => assert!(false),
+                }
+            }
+            Err(_e) => assert!(false),
+        }
+    }
+
+    struct User {
+        id: i64,
+        username: String,
+    }
+
+    impl Bind for User {
+        fn binds(&self) -> Vec<(&'static str, OwnedData)> {
+            vec![(":id", OwnedData::Int64(self.id)),
+                 (":username", OwnedData::String(self.username.clone()))]
+        }
+    }
+
+    fn affected_rowids_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+        conn.modify("insert into username (id, username) \
+                     select level + 30, 'affected_rowids' from dual connect by level <= 3",
+                    &[])?;
+
+        let rowid_var = conn.new_var(RowID, Rowid, 3, 0, false, false)?;
+        let stmt = conn.prepare_stmt(Some("update username set username = username \
+                                           where username = 'affected_rowids' \
+                                           returning rowid into :1"),
+                          None,
+                          false)?;
+        match stmt.affected_rowids() {
+            Err(error::Error(error::ErrorKind::NoRowidVar, _)) => assert!(true),
+            _ => assert!(false),
+        }
+        stmt.bind_by_pos(1, &rowid_var)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let rowids = stmt.affected_rowids()?;
+        assert_eq!(rowids.len(), 3);
+        let mut refetched = Vec::new();
+        for rowid in &rowids {
+            let sql = "select id from username where rowid = chartorowid(:1)";
+            if let Some((id,)) = conn.query_row::<(i64,)>(sql, &[&rowid.get_string_value()?])? {
+                refetched.push(id);
+            }
+            rowid.release()?;
+        }
+        refetched.sort();
+        assert_eq!(refetched, vec![31, 32, 33]);
+
+        stmt.execute_many(flags::EXEC_DEFAULT, 1)?;
+        match stmt.affected_rowids() {
+            Err(error::Error(error::ErrorKind::RowidsAfterExecuteMany, _)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        stmt.close(None)?;
+        conn.rollback()?;
+        Ok(())
+    }
+
+    #[test]
+    fn affected_rowids() {
+        match affected_rowids_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn batch_errors_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        let mut rng = rand::thread_rng();
+        let too_long: String = ::std::iter::repeat('x').take(300).collect();
+
+        let stmt = conn.prepare_stmt(Some("insert into username values (:1, :2)"), None, false)?;
+        let id_var = conn.new_var(Number, Int64, 5, 0, false, false)?;
+        for data in id_var.get_data()?.iter_mut() {
+            (*data).is_null = 0;
+            (*data).value.as_int_64 = rng.gen::<i64>().abs();
+        }
+        let username_var = conn.new_var(Varchar, Bytes, 5, 512, true, false)?;
+        for i in 0..5 {
+            if i == 1 || i == 3 {
+                username_var.set_from_bytes(i, &too_long)?;
+            } else {
+                username_var.set_from_bytes(i, "jozias")?;
+            }
+        }
+
+        stmt.bind_by_pos(1, &id_var)?;
+        stmt.bind_by_pos(2, &username_var)?;
+        stmt.execute_many(flags::BATCH_ERRORS, 5)?;
+
+        let errors = stmt.batch_errors()?;
+        let offsets: Vec<u32> = errors.iter().map(|&(offset, _)| offset).collect();
+        assert_eq!(offsets, vec![1, 3]);
+        assert!(errors.iter().all(|&(_, ref err)| err.code() == 12899));
+        conn.rollback()?;
+        Ok(())
+    }
+
+    #[test]
+    fn batch_errors() {
+        match batch_errors_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn get_batch_errors_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+        let mut rng = rand::thread_rng();
+        let first = rng.gen::<i32>().abs() as i64;
+        let ids = [first, first, first + 1, first + 1];
+
+        let stmt = conn.prepare_stmt(Some("insert into keyed values (:1, :2)"), None, false)?;
+        let id_var = conn.new_var(Number, Int64, 4, 0, false, false)?;
+        for (data, id) in id_var.get_data()?.iter_mut().zip(ids.iter()) {
+            (*data).is_null = 0;
+            (*data).value.as_int_64 = *id;
+        }
+        let name_var = conn.new_var(Varchar, Bytes, 4, 256, true, false)?;
+        for i in 0..4 {
+            name_var.set_from_bytes(i, "batch")?;
+        }
+        stmt.bind_by_pos(1, &id_var)?;
+        stmt.bind_by_pos(2, &name_var)?;
+        stmt.execute_many(flags::BATCH_ERRORS, 4)?;
+
+        let count = stmt.get_batch_error_count()?;
+        assert_eq!(count, 2);
+        let errors = stmt.get_batch_errors(count)?;
+        assert_eq!(errors.iter().map(|err| err.offset()).collect::<Vec<u16>>(),
+                   vec![1, 3]);
+        for err in &errors {
+            assert_eq!(err.code(), 1);
+            assert!(err.message().starts_with("ORA-00001: unique constraint"));
+        }
+        conn.rollback()?;
+        Ok(())
+    }
+
+    #[test]
+    fn get_batch_errors() {
+        match get_batch_errors_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn bind_bytes_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+        let mut rng = rand::thread_rng();
+        let id = rng.gen::<i32>().abs() as i64;
+        let bytes: Vec<u8> = (0..100).map(|i| (i * 7 % 256) as u8).collect();
+
+        let sql = format!("insert into rawdata values ({}, :1)", id);
+        let stmt = conn.prepare_stmt(Some(&sql), None, false)?;
+        stmt.bind_bytes(1, &bytes)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let query = conn.prepare_stmt(Some("select data from rawdata where id = :id"),
+                                      None,
+                                      false)?;
+        query.bind_data_by_name(":id", &OwnedData::Int64(id))?;
+        query.execute(flags::EXEC_DEFAULT)?;
+        let row = query.fetch_row()?.expect("expected the inserted row");
+        assert_eq!(row.get::<Vec<u8>>(1)?, bytes);
+
+        let invalid_utf8: &[u8] = &[0xff, 0xfe, 0x00, 0xc3, 0x28];
+        conn.modify("insert into rawdata values (:1, :2)", &[&(id + 1), &invalid_utf8])?;
+        let sql = "select data from rawdata where id = :1";
+        match conn.query_row::<(Vec<u8>,)>(sql, &[&(id + 1)])? {
+            Some((data,)) => assert_eq!(data, invalid_utf8),
+            None => assert!(false),
+        }
+        conn.rollback()?;
+        Ok(())
+    }
+
+    #[test]
+    fn bind_bytes() {
+        match bind_bytes_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn bind_cursor_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+
+        let count_stmt = conn.prepare_stmt(Some("select count(*) from username"), None, false)?;
+        count_stmt.execute(flags::EXEC_DEFAULT)?;
+        let expected = count_stmt.fetch_row()?.expect("expected a count").get::<i64>(1)?;
+
+        let cursor = conn.prepare_stmt(Some("select id, username from username"), None, false)?;
+        cursor.execute(flags::EXEC_DEFAULT)?;
+
+        let stmt = conn.prepare_stmt(Some("declare \
+                                             c sys_refcursor := :1; \
+                                             id number; \
+                                             name varchar2(256); \
+                                             n number := 0; \
+                                           begin \
+                                             loop \
+                                               fetch c into id, name; \
+                                               exit when c%notfound; \
+                                               n := n + 1; \
+                                             end loop; \
+                                             :2 := n; \
+                                           end;"),
+                          None,
+                          false)?;
+        let count_var = conn.new_var(Number, Int64, 1, 0, false, false)?;
+        stmt.bind_cursor(1, &cursor)?;
+        stmt.bind_by_pos(2, &count_var)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        assert_eq!(stmt.get_out::<i64, _>(2)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn bind_cursor() {
+        match bind_cursor_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn spawn_stream_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        ::test::ensure_test_schema()?;
+        let count = conn.query_scalar::<i64>("select count(*) from username")?
+            .expect("expected a count");
+
+        let stmt = conn.prepare_stmt(Some("select id, username from username"), None, false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        let (sender, receiver) = mpsc::channel();
+        let handle = stmt.spawn_stream(sender);
+
+        let mut rows = 0;
+        for row in receiver {
+            assert_eq!(row?.len(), 2);
+            rows += 1;
+        }
+        handle.join().expect("stream thread panicked");
+        assert_eq!(rows, count);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn spawn_stream() {
+        match spawn_stream_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn total_affected_after_many_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        let mut rng = rand::thread_rng();
+
+        let stmt = conn.prepare_stmt(Some("insert into username values (:1, :2)"), None, false)?;
+        let id_var = conn.new_var(Number, Int64, 10, 0, false, false)?;
+        for data in id_var.get_data()?.iter_mut() {
+            (*data).is_null = 0;
+            (*data).value.as_int_64 = rng.gen::<i64>().abs();
+        }
+        let username_var = conn.new_var(Varchar, Bytes, 10, 256, true, false)?;
+        for i in 0..10 {
+            username_var.set_from_bytes(i, "total_affected")?;
+        }
+
+        stmt.bind_by_pos(1, &id_var)?;
+        stmt.bind_by_pos(2, &username_var)?;
+        stmt.execute_many(flags::ARRAY_DML_ROWCOUNTS, 10)?;
+
+        assert_eq!(stmt.get_row_counts()?, vec![1; 10]);
+        assert_eq!(stmt.total_affected_after_many()?, 10);
+        conn.rollback()?;
+        Ok(())
+    }
+
+    #[test]
+    fn total_affected_after_many() {
+        match total_affected_after_many_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn get_row_counts_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+        let mut rng = rand::thread_rng();
+        let marker = format!("row_counts_{}", rng.gen::<u32>());
+        let markers = [format!("{}_a", marker), format!("{}_b", marker), format!("{}_c", marker)];
+
+        for (id, username) in vec![&markers[0], &markers[0], &markers[1]].into_iter().enumerate() {
+            conn.modify("insert into username values (:1, :2)", &[&(id as i64), username])?;
+        }
+
+        let stmt = conn.prepare_stmt(Some("delete from username where username = :1"),
+                          None,
+                          false)?;
+        let username_var = conn.new_var(Varchar, Bytes, 3, 256, true, false)?;
+        for (i, username) in markers.iter().enumerate() {
+            username_var.set_from_bytes(i as u32, username)?;
+        }
+        stmt.bind_by_pos(1, &username_var)?;
+        stmt.execute_many(flags::ARRAY_DML_ROWCOUNTS, 3)?;
+
+        assert_eq!(stmt.get_row_counts()?, vec![2, 1, 0]);
+        conn.rollback()?;
+        Ok(())
+    }
+
+    #[test]
+    fn get_row_counts() {
+        match get_row_counts_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn bind_struct_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        let mut rng = rand::thread_rng();
+        let user = User {
+            id: rng.gen::<i32>().abs() as i64,
+            username: "bind_struct".to_string(),
+        };
+
+        let stmt = conn.prepare_stmt(Some("insert into username values (:id, :username)"),
+                          None,
+                          false)?;
+        stmt.bind_struct(&user)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        assert_eq!(stmt.get_row_count()?, 1);
+
+        let query = conn.prepare_stmt(Some("select username from username where id = :id"),
+                          None,
+                          false)?;
+        query.bind_data_by_name(":id", &OwnedData::Int64(user.id))?;
+        query.execute(flags::EXEC_DEFAULT)?;
+        let row = query.fetch_row()?.expect("expected the inserted row");
+        assert_eq!(row.get::<String>(1)?, user.username);
+        conn.rollback()?;
+        Ok(())
+    }
+
+    #[test]
+    fn bind_struct() {
+        match bind_struct_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn describe_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        ::test::ensure_test_schema()?;
+
+        let stmt = conn.prepare_stmt(Some("select id, name from keyed"), None, false)?;
+        let infos = stmt.describe()?;
+        assert_eq!(infos.len(), 2);
+        assert_eq!(infos[0].name(), "ID");
+        assert!(!infos[0].null_ok());
+        assert_eq!(infos[1].name(), "NAME");
+        assert!(infos[1].null_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn describe() {
+        match describe_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn get_sql_id_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        let mut rng = rand::thread_rng();
+        let sql = format!("select 'get_sql_id_{}' from dual", rng.gen::<u32>());
+
+        let stmt = conn.prepare_stmt(Some(&sql), None, false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        stmt.fetch_row()?;
+
+        match stmt.get_sql_id()? {
+            Some(sql_id) => assert_eq!(sql_id.len(), 13),
+            None => assert!(false),
+        }
+
+        // Statements that only differ after the 1000 characters kept in v$sql.sql_text.
+        let prefix = format!("select '{}_{}'", "x".repeat(1000), rng.gen::<u32>());
+        let mut sql_ids = Vec::new();
+        for suffix in &["1", "2"] {
+            let stmt = conn.prepare_stmt(Some(&format!("{}, {} from dual", prefix, suffix)),
+                                         None,
+                                         false)?;
+            stmt.execute(flags::EXEC_DEFAULT)?;
+            stmt.fetch_row()?;
+            sql_ids.push(stmt.get_sql_id()?.expect("missing sql_id"));
+        }
+        assert!(sql_ids[0] != sql_ids[1]);
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn get_sql_id() {
+        match get_sql_id_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn execute_returning_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        let mut rng = rand::thread_rng();
+        let marker = format!("returning_{}", rng.gen::<u32>());
+        let first = rng.gen::<i32>().abs() as i64;
+
+        for id in &[first, first + 1] {
+            let insert = conn.prepare_stmt(Some("insert into username values (:id, :username)"),
+                              None,
+                              false)?;
+            insert.bind_data_by_name(":id", &OwnedData::Int64(*id))?;
+            insert.bind_data_by_name(":username", &OwnedData::String(marker.clone()))?;
+            insert.execute(flags::EXEC_DEFAULT)?;
+        }
+
+        let stmt = conn.prepare_stmt(Some("delete from username where username = :m \
+                                           returning id, username into :i, :u"),
+                          None,
+                          false)?;
+        let id_var = conn.new_var(Number, Int64, 2, 0, false, false)?;
+        let username_var = conn.new_var(Varchar, Bytes, 2, 256, true, false)?;
+        stmt.bind_data_by_name(":m", &OwnedData::String(marker.clone()))?;
+        let rows = stmt.execute_returning(flags::EXEC_DEFAULT,
+                                          &[(":i", &id_var), (":u", &username_var)])?;
+
+        let mut pairs = Vec::new();
+        for row in &rows {
+            pairs.push((i64::from_data(&row[0])?, String::from_data(&row[1])?));
+        }
+        pairs.sort();
+        assert_eq!(pairs, vec![(first, marker.clone()), (first + 1, marker)]);
+        conn.rollback()?;
+        Ok(())
+    }
+
+    #[test]
+    fn execute_returning() {
+        match execute_returning_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn execute_returning_commit_res() -> Result<()> {
+        // The statement commits, so it must not share a connection with other tests.
+        ::test::ensure_test_schema()?;
+        let conn = ::test::connect()?;
+        let mut rng = rand::thread_rng();
+        let marker = format!("returning_commit_{}", rng.gen::<u32>());
+        let id = rng.gen::<i32>().abs() as i64;
+
+        let stmt = conn.prepare_stmt(Some("insert into username values (:id, :m) \
+                                           returning id into :i"),
+                          None,
+                          false)?;
+        let id_var = conn.new_var(Number, Int64, 1, 0, false, false)?;
+        stmt.bind_data_by_name(":id", &OwnedData::Int64(id))?;
+        stmt.bind_data_by_name(":m", &OwnedData::String(marker.clone()))?;
+        let rows = stmt.execute_returning(flags::COMMIT_ON_SUCCESS, &[(":i", &id_var)])?;
+        assert_eq!(rows, vec![vec![OwnedData::Int64(id)]]);
+        assert_eq!(id_var.get_value(0)?, OwnedData::Int64(id));
+
+        // The insert was committed, so a rollback leaves it in place.
+        conn.rollback()?;
+        let sql = "select id from username where username = :1";
+        assert_eq!(conn.query_row::<(i64,)>(sql, &[&marker])?, Some((id,)));
+
+        // A statement returning no rows leaves nothing to read.
+        let delete = conn.prepare_stmt(Some("delete from username where username = :m \
+                                             returning id into :i"),
+                          None,
+                          false)?;
+        let none_var = conn.new_var(Number, Int64, 1, 0, false, false)?;
+        delete.bind_data_by_name(":m", &OwnedData::String(format!("{}_none", marker)))?;
+        assert!(delete.execute_returning(flags::COMMIT_ON_SUCCESS, &[(":i", &none_var)])?
+                    .is_empty());
+        match none_var.get_value(0) {
+            Err(error::Error(error::ErrorKind::ArrayPosition(0, 0), _)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        conn.modify("delete from username where username = :1", &[&marker])?;
+        conn.commit()?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn execute_returning_commit() {
+        match execute_returning_commit_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn fetch_dynamic_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        ::test::ensure_test_schema()?;
+        let stmt = conn.prepare_stmt(Some("select id, username from username where id = 1"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let values = stmt.fetch_dynamic()?.expect("expected a row");
+        assert_eq!(values.len(), 2);
+        assert_eq!(i64::from_data(&values[0])?, 1);
+        assert_eq!(values[1], OwnedData::String("jozias".to_string()));
+        assert!(stmt.fetch_dynamic()?.is_none());
+        stmt.close(None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_dynamic() {
+        match fetch_dynamic_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn drop_releases_res() -> Result<()> {
+        // Other tests open cursors on the shared connection, which would skew the count.
+        let conn = ::test::connect()?;
+        let before = conn.open_cursor_count()?;
+
+        for i in 0..1000 {
+            let stmt = conn.prepare_stmt(Some(&format!("select {} from dual", i)), None, false)?;
+            stmt.execute(flags::EXEC_DEFAULT)?;
+            assert!(stmt.fetch_row()?.is_some());
+        }
+
+        // Dropped statements may stay open in the statement cache, but no more than that.
+        let cache_size = conn.get_statement_cache_size()?;
+        assert!(conn.open_cursor_count()? <= before + cache_size + 1);
+        Ok(())
+    }
+
+    #[test]
+    fn drop_releases() {
+        match drop_releases_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn rows_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        let stmt = conn.prepare_stmt(Some("select level, 'row ' || level from dual \
+                                           connect by level <= 3 order by 1"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let mut count = 0;
+        for row in stmt.rows() {
+            let row = row?;
+            count += 1;
+            assert_eq!(row.get::<i64>(1)?, count);
+            assert_eq!(row.get::<String>(2)?, format!("row {}", count));
+        }
+        assert_eq!(count, 3);
+        assert!(stmt.rows().next().is_none());
+
+        // The iterator stays exhausted once it has returned None.
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        {
+            let mut rows = stmt.rows();
+            assert_eq!(rows.size_hint(), (0, None));
+            assert_eq!(rows.by_ref().collect::<Result<Vec<_>>>()?.len(), 3);
+            assert_eq!(rows.size_hint(), (0, Some(0)));
+            assert!(rows.next().is_none());
+            assert!(rows.next().is_none());
+        }
+        stmt.close(None)?;
+
+        // Fetching from a statement that was never executed fails, and the error ends the rows.
+        let unexecuted = conn.prepare_stmt(Some("select 1 from dual"), None, false)?;
+        {
+            let mut rows = unexecuted.rows();
+            assert!(match rows.next() {
+                        Some(Err(_)) => true,
+                        _ => false,
+                    });
+            assert!(rows.next().is_none());
+        }
+        unexecuted.close(None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn rows() {
+        match rows_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn last_warning_res() -> Result<()> {
+        // DDL commits, so it must not share a connection with other tests.
+        let conn = ::test::connect()?;
+        let broken = conn.prepare_stmt(Some("create or replace procedure mimir_broken as \
+                                             begin \
+                                               mimir_no_such_proc; \
+                                             end;"),
+                                       None,
+                                       false)?;
+        broken.execute(flags::EXEC_DEFAULT)?;
+        match broken.last_warning() {
+            Some(warning) => {
+                assert_eq!(warning.code(), 24344);
+                assert!(warning.message().contains("PROCEDURE MIMIR_BROKEN"));
+            }
+            None => assert!(false),
+        }
+        broken.close(None)?;
+
+        let fixed = conn.prepare_stmt(Some("create or replace procedure mimir_broken as \
+                                            begin \
+                                              null; \
+                                            end;"),
+                                      None,
+                                      false)?;
+        fixed.execute(flags::EXEC_DEFAULT)?;
+        assert!(fixed.last_warning().is_none());
+        fixed.close(None)?;
+
+        let drop = conn.prepare_stmt(Some("drop procedure mimir_broken"), None, false)?;
+        drop.execute(flags::EXEC_DEFAULT)?;
+        assert!(drop.last_warning().is_none());
+        drop.close(None)?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn last_warning() {
+        match last_warning_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn define_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        let stmt = conn.prepare_stmt(Some("select level * 10 from dual connect by level <= 5"),
+                          None,
+                          false)?;
+        stmt.set_fetch_array_size(5)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let var = conn.new_var(Number, Int64, 5, 0, false, false)?;
+        stmt.define(1, &var)?;
+        let (row_index, num_rows, _) = stmt.fetch_rows(5)?;
+        assert_eq!(num_rows, 5);
+
+        let data = var.get_data()?;
+        let values: Vec<i64> = (row_index..(row_index + num_rows))
+            .map(|pos| unsafe { data[pos as usize].value.as_int_64 })
+            .collect();
+        assert_eq!(values, vec![10, 20, 30, 40, 50]);
+        stmt.close(None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn define() {
+        match define_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn define_as_bytes_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        ::test::ensure_test_schema()?;
+        let stmt = conn.prepare_stmt(Some("select id from username where username = 'jozias'"),
+                          None,
+                          false)?;
+        stmt.set_fetch_array_size(1)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        // The NUMBER column is converted to a string by Oracle when it is fetched.
+        let var = conn.new_var(Varchar, Bytes, 1, 40, true, false)?;
+        stmt.define(1, &var)?;
+        let (found, row_index) = stmt.fetch()?;
+        assert!(found);
+        assert_eq!(var.get_value(row_index)?, OwnedData::String("1".to_string()));
+        stmt.close(None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn define_as_bytes() {
+        match define_as_bytes_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
 
-#[cfg(test)]
-mod test {
-    use connection::Connection;
-    use data::Data;
-    use error;
-    use odpi::flags;
-    use odpi::flags::ODPIFetchMode::*;
-    use odpi::flags::ODPINativeTypeNum::*;
-    use odpi::flags::ODPIOracleTypeNum::*;
-    use odpi::flags::ODPIStatementType::*;
-    use odpi::structs::{ODPIBytes, ODPIDataValueUnion};
-    use rand::{self, Rng};
-    use test::{ContextResult, CREDS, CTXT, ENC};
-    use util::ODPIStr;
+    fn define_truncation_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        let stmt = conn.prepare_stmt(Some("select cast(rpad('x', 100, 'x') as varchar2(100)) \
+                                           from dual"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let small = conn.new_var(Varchar, Bytes, 1, 10, true, false)?;
+        match stmt.define(1, &small) {
+            Err(error::Error(error::ErrorKind::Truncation(1, needed, 10), _)) => {
+                assert!(needed >= 100)
+            }
+            _ => assert!(false),
+        }
 
-    enum ConnResult {
-        Ok(Connection),
-        Err(error::Error),
+        let big = conn.new_var(Varchar, Bytes, 1, 400, true, false)?;
+        stmt.define(1, &big)?;
+        let (_, num_rows, _) = stmt.fetch_rows(1)?;
+        assert_eq!(num_rows, 1);
+        stmt.close(None)?;
+        Ok(())
     }
 
-    unsafe impl Sync for ConnResult {}
-
-    lazy_static! {
-        static ref CONN: ConnResult = {
-            let ctxt = match *CTXT {
-                ContextResult::Ok(ref ctxt) => ctxt,
-                ContextResult::Err(ref _e) => return ConnResult::Err(
-                    error::ErrorKind::Connection("CONTEXT".to_string()).into()
-                ),
-            };
-            let ccp = match ctxt.init_common_create_params() {
-                Ok(mut ccp) => {
-                    ccp.set_encoding(ENC.as_ptr());
-                    ccp.set_nchar_encoding(ENC.as_ptr());
-                    ccp
-                },
-                Err(e) => return ConnResult::Err(e),
-            };
+    #[test]
+    fn define_truncation() {
+        match define_truncation_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
 
-            match Connection::create(ctxt,
-                                     Some(&CREDS[0]),
-                                     Some(&CREDS[1]),
-                                     Some("//oic.cbsnae86d3iv.us-east-2.rds.amazonaws.com/ORCL"),
-                                     Some(ccp),
-                                     None) {
-                Ok(conn) => ConnResult::Ok(conn),
-                Err(e) => ConnResult::Err(e),
+    fn fetch_cursor_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
             }
         };
+        let stmt = conn.prepare_stmt(Some("select cursor(select level, 'nested ' || level \
+                                           from dual connect by level <= 3 order by 1), 1 \
+                                           from dual"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        let (found, _) = stmt.fetch()?;
+        assert!(found);
+
+        let cursor = stmt.fetch_cursor(1)?;
+        let mut count = 0;
+        for row in cursor.rows() {
+            let row = row?;
+            count += 1;
+            assert_eq!(row.get::<i64>(1)?, count);
+            assert_eq!(row.get::<String>(2)?, format!("nested {}", count));
+        }
+        assert_eq!(count, 3);
+        cursor.close(None)?;
+
+        match stmt.fetch_cursor(2) {
+            Err(error::Error(error::ErrorKind::TypeMismatch(..), _)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        match stmt.fetch_row() {
+            Err(error::Error(error::ErrorKind::CursorColumn(pos), _)) => assert_eq!(pos, 1),
+            _ => assert!(false),
+        }
+        stmt.close(None)?;
+        Ok(())
     }
 
     #[test]
-    fn add_ref_release() {
+    #[ignore]
+    fn fetch_cursor() {
+        match fetch_cursor_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn fetch_lob_res() -> Result<()> {
         let conn = match *CONN {
             ConnResult::Ok(ref conn) => conn,
-            ConnResult::Err(ref _e) => return assert!(false),
-        };
-        match conn.prepare_stmt(Some("select 1 from dual"), None, false) {
-            Ok(stmt) => {
-                match stmt.add_ref() {
-                    Ok(_) => {
-                        match stmt.release() {
-                            Ok(_) => assert!(true),
-                            Err(_) => assert!(false),
-                        }
-                    }
-                    Err(e) => ::test::error_info(e),
-                }
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
             }
+        };
+        let stmt = conn.prepare_stmt(Some("select to_clob('large object'), 1 from dual"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        let (found, _) = stmt.fetch()?;
+        assert!(found);
+
+        let lob = stmt.fetch_lob(1)?;
+        assert_eq!(lob.read_all()?, b"large object".to_vec());
+        lob.release()?;
+
+        match stmt.fetch_lob(2) {
+            Err(error::Error(error::ErrorKind::TypeMismatch(..), _)) => assert!(true),
+            _ => assert!(false),
+        }
+        stmt.close(None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn fetch_lob() {
+        match fetch_lob_res() {
+            Ok(_) => assert!(true),
             Err(e) => ::test::error_info(e),
         }
     }
 
+    fn fetch_nclob_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+        let value = "h\u{e9}llo \u{4e2d}\u{6587} \u{1f600}";
+        let stmt = conn.prepare_stmt(Some("select to_nclob(:val), to_clob(:val) from dual"),
+                          None,
+                          false)?;
+        stmt.bind_data_by_name(":val", &OwnedData::String(value.to_string()))?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        let (found, _) = stmt.fetch()?;
+        assert!(found);
+
+        for col in 1..3 {
+            let lob = stmt.fetch_lob(col)?;
+            assert_eq!(lob.read_string()?, value);
+            lob.release()?;
+        }
+        stmt.close(None)?;
+
+        let mut rng = rand::thread_rng();
+        let id = rng.gen::<i32>().abs() as i64;
+        let nvar = conn.new_var(NVarchar, Bytes, 1, 100, false, false)?;
+        nvar.set_from_bytes(0, value)?;
+        let insert = conn.prepare_stmt(Some("insert into nclobdata values (:1, :2)"), None, false)?;
+        insert.bind_data_by_pos(1, &OwnedData::Int64(id))?;
+        insert.bind_by_pos(2, &nvar)?;
+        insert.execute(flags::EXEC_DEFAULT)?;
+        insert.close(None)?;
+
+        let query = conn.prepare_stmt(Some("select data from nclobdata where id = :1"),
+                                      None,
+                                      false)?;
+        query.bind_data_by_pos(1, &OwnedData::Int64(id))?;
+        query.execute(flags::EXEC_DEFAULT)?;
+        let (found, _) = query.fetch()?;
+        assert!(found);
+        let lob = query.fetch_lob(1)?;
+        assert_eq!(lob.read_string()?, value);
+        lob.release()?;
+        query.close(None)?;
+        conn.rollback()?;
+        Ok(())
+    }
+
     #[test]
-    fn bind_by_name() {
+    fn fetch_nclob() {
+        match fetch_nclob_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn get_implicit_result_res() -> Result<()> {
         let conn = match *CONN {
             ConnResult::Ok(ref conn) => conn,
-            ConnResult::Err(ref _e) => return assert!(false),
-        };
-        match conn.new_var(Varchar, Bytes, 1, 256, false, false) {
-            Ok(var) => {
-                match conn.prepare_stmt(Some("select * from username where username = :username"),
-                                        None,
-                                        false) {
-                    Ok(stmt) => {
-                        match stmt.bind_by_name(":username", var) {
-                            Ok(_) => assert!(true),
-                            Err(e) => ::test::error_info(e),
-                        }
-                    }
-                    Err(e) => ::test::error_info(e),
-                }
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
             }
-            Err(e) => ::test::error_info(e),
+        };
+        let stmt = conn.prepare_stmt(Some("declare \
+                                             c1 sys_refcursor; \
+                                             c2 sys_refcursor; \
+                                           begin \
+                                             open c1 for select 1 as first_id from dual; \
+                                             dbms_sql.return_result(c1); \
+                                             open c2 for select 'a' as name, 2 as second_id \
+                                                         from dual; \
+                                             dbms_sql.return_result(c2); \
+                                           end;"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let mut names = Vec::new();
+        while let Some(implicit) = stmt.get_implicit_result()? {
+            let columns = implicit.get_query_columns()?;
+            names.push(columns.iter().map(|c| c.name()).collect::<Vec<String>>());
+            assert!(implicit.fetch_row()?.is_some());
+            implicit.release()?;
         }
+        assert_eq!(names,
+                   vec![vec!["FIRST_ID".to_string()],
+                        vec!["NAME".to_string(), "SECOND_ID".to_string()]]);
+        assert!(stmt.get_implicit_result()?.is_none());
+        stmt.close(None)?;
+        Ok(())
     }
 
     #[test]
-    fn bind_by_pos() {
+    #[ignore]
+    fn get_implicit_result() {
+        match get_implicit_result_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn get_implicit_result_rows_res() -> Result<()> {
         let conn = match *CONN {
             ConnResult::Ok(ref conn) => conn,
-            ConnResult::Err(ref _e) => return assert!(false),
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
         };
-        match conn.new_var(Varchar, Bytes, 1, 256, false, false) {
-            Ok(ref var) => {
-                match conn.prepare_stmt(Some("select * from username where username = :username"),
-                                        None,
-                                        false) {
-                    Ok(stmt) => {
-                        match stmt.bind_by_pos(1, var) {
-                            Ok(_) => assert!(true),
-                            Err(e) => ::test::error_info(e),
-                        }
-                    }
-                    Err(e) => ::test::error_info(e),
-                }
+        let stmt = conn.prepare_stmt(Some("declare \
+                                             c1 sys_refcursor; \
+                                             c2 sys_refcursor; \
+                                           begin \
+                                             open c1 for select level from dual \
+                                                         connect by level <= 2; \
+                                             dbms_sql.return_result(c1); \
+                                             open c2 for select level * 10 from dual \
+                                                         connect by level <= 3; \
+                                             dbms_sql.return_result(c2); \
+                                           end;"),
+                          None,
+                          false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let mut results = Vec::new();
+        while let Some(implicit) = stmt.get_implicit_result()? {
+            let mut values = Vec::new();
+            for row in implicit.rows() {
+                values.push(row?.get::<i64>(1)?);
             }
-            Err(e) => ::test::error_info(e),
+            results.push(values);
         }
+        assert_eq!(results, vec![vec![1, 2], vec![10, 20, 30]]);
+        stmt.close(None)?;
+        Ok(())
     }
 
     #[test]
-    fn bind_value_by_name() {
+    #[ignore]
+    fn get_implicit_result_rows() {
+        match get_implicit_result_rows_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
+    fn get_out_res() -> Result<()> {
         let conn = match *CONN {
             ConnResult::Ok(ref conn) => conn,
-            ConnResult::Err(ref _e) => return assert!(false),
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
         };
-        match conn.prepare_stmt(Some("select * from username where username = :username"),
-                                None,
-                                false) {
-            Ok(stmt) => {
-                let blah = ODPIStr::from("test");
-                let enc = String::from("UTF-8\0");
 
-                let odpi_bytes = ODPIBytes {
-                    ptr: blah.ptr() as *mut i8,
-                    length: blah.len(),
-                    encoding: enc.as_ptr() as *const ::std::os::raw::c_char,
-                };
+        let stmt = conn.prepare_stmt(Some("begin :out := :in * 2; end;"), None, false)?;
+        let in_var = conn.new_var(Number, Int64, 1, 0, false, false)?;
+        {
+            let in_data = in_var.get_data()?;
+            in_data[0].is_null = 0;
+            in_data[0].value.as_int_64 = 21;
+        }
+        let out_var = conn.new_var(Number, Int64, 1, 0, false, false)?;
 
-                let data = Data::new(false, ODPIDataValueUnion { as_bytes: odpi_bytes });
-                match stmt.bind_value_by_name(":username", Bytes, data) {
-                    Ok(_) => assert!(true),
-                    Err(e) => ::test::error_info(e),
-                }
-            }
+        stmt.bind_by_name(":in", in_var)?;
+        stmt.bind_by_name(":out", out_var)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        assert_eq!(stmt.get_out::<i64, _>(":out")?, 42);
+        assert_eq!(stmt.get_out::<i64, _>("OUT")?, 42);
+        assert_eq!(stmt.get_out::<i64, _>(":in")?, 21);
+        assert!(stmt.get_out::<i64, _>(1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn get_out() {
+        match get_out_res() {
+            Ok(_) => assert!(true),
             Err(e) => ::test::error_info(e),
         }
     }
 
-    #[test]
-    fn bind_value_by_pos() {
+    fn get_out_boolean_res() -> Result<()> {
         let conn = match *CONN {
             ConnResult::Ok(ref conn) => conn,
-            ConnResult::Err(ref _e) => return assert!(false),
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
         };
-        match conn.prepare_stmt(Some("select * from username where username = :username"),
-                                None,
-                                false) {
-            Ok(stmt) => {
-                let blah = ODPIStr::from("test");
-                let enc = String::from("UTF-8\0");
+        if !conn.supports_feature(Feature::PlsqlBoolean)? {
+            return Ok(());
+        }
 
-                let odpi_bytes = ODPIBytes {
-                    ptr: blah.ptr() as *mut i8,
-                    length: blah.len(),
-                    encoding: enc.as_ptr() as *const ::std::os::raw::c_char,
-                };
+        let stmt = conn.prepare_stmt(Some("begin :t := sys.diutil.int_to_bool(1); \
+                                           :f := sys.diutil.int_to_bool(0); end;"),
+                          None,
+                          false)?;
+        let t_var = conn.new_var(flags::ODPIOracleTypeNum::Boolean,
+                                 flags::ODPINativeTypeNum::Boolean,
+                                 1,
+                                 0,
+                                 false,
+                                 false)?;
+        let f_var = conn.new_var(flags::ODPIOracleTypeNum::Boolean,
+                                 flags::ODPINativeTypeNum::Boolean,
+                                 1,
+                                 0,
+                                 false,
+                                 false)?;
+        stmt.bind_by_name(":t", t_var)?;
+        stmt.bind_by_name(":f", f_var)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        assert!(stmt.get_out::<bool, _>(":t")?);
+        assert!(!stmt.get_out::<bool, _>(":f")?);
+        Ok(())
+    }
 
-                let data = Data::new(false, ODPIDataValueUnion { as_bytes: odpi_bytes });
-                match stmt.bind_value_by_pos(1, Bytes, data) {
-                    Ok(_) => assert!(true),
-                    Err(_e) => assert!(false),
-                }
-            }
-            Err(_e) => assert!(false),
+    #[test]
+    fn get_out_boolean() {
+        match get_out_boolean_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
         }
     }
 
@@ -738,6 +2949,10 @@ mod test {
             ConnResult::Ok(ref conn) => conn,
             ConnResult::Err(ref _e) => return assert!(false),
         };
+        match ::test::ensure_test_schema() {
+            Ok(_) => assert!(true),
+            Err(e) => return ::test::error_info(e),
+        }
         match conn.prepare_stmt(Some("select * from username where username = 'jozias'"),
                                 None,
                                 false) {
@@ -760,6 +2975,97 @@ mod test {
         }
     }
 
+    #[test]
+    fn unsupported() {
+        let old: version::Info = ODPIVersionInfo {
+                version_num: 11,
+                release_num: 2,
+                update_num: 0,
+                port_release_num: 4,
+                port_update_num: 0,
+                full_version_num: 1102000400,
+            }
+            .into();
+        let mut stmt = Statement::new(ptr::null_mut());
+        stmt.set_versions(None, old);
+
+        match stmt.get_batch_errors(1) {
+            Err(error::Error(error::ErrorKind::Unsupported(Feature::BatchErrors, (12, 1)), _)) => {
+                assert!(true)
+            }
+            _ => assert!(false),
+        }
+        match stmt.execute_many(flags::ARRAY_DML_ROWCOUNTS, 2) {
+            Err(error::Error(error::ErrorKind::Unsupported(Feature::ArrayDmlRowCounts, _), _)) => {
+                assert!(true)
+            }
+            _ => assert!(false),
+        }
+    }
+
+    /// Fetch the 10000 rows of a query with `fetch_rows(max_rows)`, checking that every row is
+    /// returned in order, and return the fetch array size seen after each call.
+    fn fetch_array_sizes(adaptive: bool, max_rows: u32) -> Result<Vec<u32>> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        let stmt = conn.prepare_stmt(Some("select level from dual connect by level <= 10000"),
+                          None,
+                          false)?;
+        if adaptive {
+            stmt.enable_adaptive_fetch();
+        }
+        stmt.execute(flags::EXEC_DEFAULT)?;
+
+        let mut sizes = Vec::new();
+        let mut total = 0;
+        loop {
+            let (_, num_rows_fetched, more_rows) = stmt.fetch_rows(max_rows)?;
+            assert!(num_rows_fetched <= max_rows);
+            total += num_rows_fetched;
+            sizes.push(stmt.get_fetch_array_size()?);
+            if num_rows_fetched > 0 {
+                // The current row is the last one fetched, which must not have been lost when
+                // the variables were replaced.
+                let (_, ptr) = stmt.get_query_value(1)?;
+                let data: Data = ptr.into();
+                assert_eq!(data.as_double(), total as f64);
+            }
+            if !more_rows {
+                break;
+            }
+        }
+        assert_eq!(total, 10000);
+        Ok(sizes)
+    }
+
+    #[test]
+    fn enable_adaptive_fetch() {
+        let default = constants::DPI_DEFAULT_FETCH_ARRAY_SIZE;
+        match (fetch_array_sizes(false, u32::max_value()),
+               fetch_array_sizes(true, u32::max_value()),
+               fetch_array_sizes(true, 30)) {
+            (Ok(naive), Ok(adaptive), Ok(sliced)) => {
+                // The last round trip is full, so one more call is needed to find the end.
+                assert_eq!(naive, vec![default; (10000 / default + 1) as usize]);
+                assert_eq!(adaptive,
+                           vec![default,
+                                default * 2,
+                                default * 4,
+                                default * 8,
+                                default * 16,
+                                default * 32,
+                                ADAPTIVE_FETCH_ARRAY_SIZE_CAP]);
+                assert!(sliced.windows(2).all(|w| w[1] == w[0] || w[1] == w[0] * 2));
+                assert_eq!(sliced.last(), Some(&ADAPTIVE_FETCH_ARRAY_SIZE_CAP));
+            }
+            (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => ::test::error_info(e),
+        }
+    }
+
     #[test]
     fn fetch_rows() {
         let conn = match *CONN {
@@ -827,6 +3133,56 @@ mod test {
         }
     }
 
+    #[test]
+    fn binds() {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => return assert!(false),
+        };
+
+        let stmt = match conn.prepare_stmt(Some("insert into t values (:a, :b, :a)"), None, false) {
+            Ok(stmt) => stmt,
+            Err(e) => return ::test::error_info(e),
+        };
+
+        match stmt.binds() {
+            Ok(binds) => {
+                let found: Vec<(u32, Option<&str>)> =
+                    binds.iter().map(|bind| (bind.position(), bind.name())).collect();
+                assert_eq!(found, vec![(1, Some("A")), (2, Some("B"))]);
+            }
+            Err(e) => ::test::error_info(e),
+        }
+
+        let stmt = match conn.prepare_stmt(Some("select :a, :a, :b, :4 from dual"), None, false) {
+            Ok(stmt) => stmt,
+            Err(e) => return ::test::error_info(e),
+        };
+
+        match stmt.binds() {
+            Ok(binds) => {
+                let found: Vec<(u32, Option<&str>)> =
+                    binds.iter().map(|bind| (bind.position(), bind.name())).collect();
+                assert_eq!(found, vec![(1, Some("A")), (3, Some("B")), (4, None)]);
+            }
+            Err(e) => ::test::error_info(e),
+        }
+
+        let block = match conn.prepare_stmt(Some("begin :a := :b + :a; end;"), None, false) {
+            Ok(block) => block,
+            Err(e) => return ::test::error_info(e),
+        };
+
+        match block.binds() {
+            Ok(binds) => {
+                let found: Vec<(u32, Option<&str>)> =
+                    binds.iter().map(|bind| (bind.position(), bind.name())).collect();
+                assert_eq!(found, vec![(1, Some("A")), (2, Some("B"))]);
+            }
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
     #[test]
     fn get_bind_names() {
         let conn = match *CONN {
@@ -1023,6 +3379,42 @@ mod test {
         }
     }
 
+    fn set_fetch_array_size_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        let stmt = conn.prepare_stmt(Some("select * from username"), None, false)?;
+
+        stmt.set_fetch_array_size(500)?;
+        assert_eq!(stmt.get_fetch_array_size()?, 500);
+        stmt.set_fetch_array_size(0)?;
+        assert_eq!(stmt.get_fetch_array_size()?,
+                   constants::DPI_DEFAULT_FETCH_ARRAY_SIZE);
+        match stmt.set_fetch_array_size(MAX_FETCH_ARRAY_SIZE + 1) {
+            Err(error::Error(error::ErrorKind::InvalidFetchArraySize(size, max), _)) => {
+                assert_eq!(size, MAX_FETCH_ARRAY_SIZE + 1);
+                assert_eq!(max, MAX_FETCH_ARRAY_SIZE);
+            }
+            _ => assert!(false),
+        }
+        assert_eq!(stmt.get_fetch_array_size()?,
+                   constants::DPI_DEFAULT_FETCH_ARRAY_SIZE);
+
+        stmt.close(None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn set_fetch_array_size() {
+        match set_fetch_array_size_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+
     #[test]
     fn scroll() {
         let conn = match *CONN {
@@ -1050,4 +3442,37 @@ mod test {
             Err(e) => return ::test::error_info(e),
         }
     }
+
+    fn scroll_first_res() -> Result<()> {
+        let conn = match *CONN {
+            ConnResult::Ok(ref conn) => conn,
+            ConnResult::Err(ref _e) => {
+                return Err(error::ErrorKind::Connection("CONN".to_string()).into())
+            }
+        };
+        let sql = "select id from username order by id";
+
+        let stmt = conn.prepare_stmt(Some(sql), None, true)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        let first = stmt.fetch_row()?.map(|row| row.into_values());
+        stmt.fetch_row()?;
+        stmt.scroll(First, 0, 0)?;
+        assert_eq!(stmt.fetch_row()?.map(|row| row.into_values()), first);
+        stmt.close(None)?;
+
+        let stmt = conn.prepare_stmt(Some(sql), None, false)?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        stmt.fetch_row()?;
+        assert!(stmt.scroll(First, 0, 0).is_err());
+        stmt.close(None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn scroll_first() {
+        match scroll_first_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
 }
@@ -6,7 +6,6 @@
 // option. All files in the project carrying such notice may not be copied,
 // modified, or distributed except according to those terms.
 
-//! [NOT IMPL]
 //! Subscription handles are used to represent subscriptions to events such as continuous query
 //! notification and object change notification. They are created by calling the function
 //! `Connection::new_subscription()` and are destroyed by calling the function
@@ -15,30 +14,80 @@
 use error::{ErrorKind, Result};
 use odpi::externs;
 use odpi::opaque::ODPISubscr;
+use statement::Statement;
+use std::ptr;
+use util::ODPIStr;
 
-/// ODPI-C Message Props wrapper.
+/// Subscription handles are used to represent subscriptions to events such as continuous query
+/// notification and object change notification.
 #[derive(Clone)]
 pub struct Subscription {
-    /// The ODPI-C MsgProps pointer.
+    /// The ODPI-C subscription pointer.
     inner: *mut ODPISubscr,
     /// The subscription id.
     id: u32,
 }
 
 impl Subscription {
+    /// Get the pointer to the inner ODPI struct.
     #[doc(hidden)]
     pub fn inner(&self) -> *mut ODPISubscr {
         self.inner
     }
 
-    ///
+    /// Adds a reference to the subscription. This is intended for situations where a reference to
+    /// the subscription needs to be maintained independently of the reference returned when the
+    /// subscription was created.
     pub fn add_ref(&self) -> Result<()> {
         try_dpi!(externs::dpiSubscr_addRef(self.inner),
                  Ok(()),
                  ErrorKind::Subscription("dpiSubscr_addRef".to_string()))
     }
 
+    /// Closes the subscription now, rather than when the last reference is released. This
+    /// deregisters it so that notifications will no longer be sent.
+    pub fn close(&self) -> Result<()> {
+        try_dpi!(externs::dpiSubscr_close(self.inner),
+                 Ok(()),
+                 ErrorKind::Subscription("dpiSubscr_close".to_string()))
+    }
+
+    /// Returns the identifier assigned to the subscription when it was created by
+    /// `Connection::new_subscription()`.
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    /// Prepares a statement for registration on the subscription. The statement is then registered
+    /// by calling the function `Statement::execute()`. The subscription must have been created for
+    /// continuous query notification, in the namespace DPI_SUBSCR_NAMESPACE_DBCHANGE, and the
+    /// connection must have been created with the create mode DPI_MODE_CREATE_EVENTS.
     ///
+    /// * `sql` - the SQL that is to be prepared and registered for change notification.
+    pub fn prepare_stmt(&self, sql: &str) -> Result<Statement> {
+        let sql_s = ODPIStr::from(sql);
+        let mut stmt_ptr = ptr::null_mut();
+
+        try_dpi!(externs::dpiSubscr_prepareStmt(self.inner,
+                                                sql_s.ptr(),
+                                                sql_s.len(),
+                                                &mut stmt_ptr),
+                 Ok(Statement::new(stmt_ptr)),
+                 ErrorKind::Subscription("dpiSubscr_prepareStmt".to_string()))
+    }
+
+    /// Releases a reference to the subscription. A count of the references to the subscription is
+    /// maintained and when this count reaches zero, the memory associated with the subscription is
+    /// freed and the subscription is deregistered so that notifications are no longer sent, if
+    /// this was not already done using the function `Subscription::close()`.
+    pub fn release(&self) -> Result<()> {
+        try_dpi!(externs::dpiSubscr_release(self.inner),
+                 Ok(()),
+                 ErrorKind::Subscription("dpiSubscr_release".to_string()))
+    }
+
+    /// Set the subscription id.
+    #[doc(hidden)]
     pub fn set_id(&mut self, id: u32) -> &mut Subscription {
         self.id = id;
         self
@@ -53,3 +102,78 @@ impl From<*mut ODPISubscr> for Subscription {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use connection::Connection;
+    use error::Result;
+    use odpi::flags;
+    use odpi::structs::ODPISubscrMessage;
+    use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+    use std::thread;
+    use std::time::Duration;
+    use test::{CONN_STR, CREDS, ENC};
+
+    static NOTIFICATIONS: AtomicUsize = ATOMIC_USIZE_INIT;
+
+    extern "C" fn subscr_callback(_context: *mut ::std::os::raw::c_void,
+                                  _message: *mut ODPISubscrMessage) {
+        NOTIFICATIONS.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn prepare_stmt_res() -> Result<()> {
+        // Notifications need a connection created in events mode, so the shared helper won't do.
+        let ctxt = ::test::context()?;
+        let mut ccp = ctxt.init_common_create_params()?;
+        ccp.set_encoding(ENC.as_ptr());
+        ccp.set_nchar_encoding(ENC.as_ptr());
+        ccp.set_create_mode(flags::DPI_MODE_CREATE_EVENTS | flags::DPI_MODE_CREATE_THREADED);
+        let conn = Connection::create(ctxt,
+                                      Some(&CREDS[0]),
+                                      Some(&CREDS[1]),
+                                      Some(CONN_STR),
+                                      Some(ccp),
+                                      None)?;
+        ::test::ensure_test_schema()?;
+
+        let mut scp = ctxt.init_subscr_create_params()?;
+        scp.set_name("mimir_cqn");
+        scp.set_qos(flags::DPI_SUBSCR_QOS_QUERY | flags::DPI_SUBSCR_QOS_ROWIDS);
+        scp.set_timeout(60);
+        scp.set_callback(Some(subscr_callback));
+        let subscription = conn.new_subscription(scp)?;
+
+        let stmt = subscription.prepare_stmt("select id from username where username = 'jozias'")?;
+        stmt.execute(flags::EXEC_DEFAULT)?;
+        while stmt.fetch()?.0 {}
+        stmt.close(None)?;
+
+        let dml = conn.prepare_stmt(Some("update username set username = username \
+                                          where username = 'jozias'"),
+                         None,
+                         false)?;
+        dml.execute(flags::COMMIT_ON_SUCCESS)?;
+        dml.close(None)?;
+
+        let mut waited = 0;
+        while NOTIFICATIONS.load(Ordering::SeqCst) == 0 && waited < 20 {
+            thread::sleep(Duration::from_millis(500));
+            waited += 1;
+        }
+        assert!(NOTIFICATIONS.load(Ordering::SeqCst) > 0);
+
+        subscription.close()?;
+        subscription.release()?;
+        conn.close(flags::ODPIConnCloseMode::DefaultClose, None)?;
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn prepare_stmt() {
+        match prepare_stmt_res() {
+            Ok(_) => assert!(true),
+            Err(e) => ::test::error_info(e),
+        }
+    }
+}
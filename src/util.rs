@@ -7,9 +7,12 @@
 // modified, or distributed except according to those terms.
 
 //! `oic` utilities
+use error::{ErrorKind, Result};
+use slog::Logger;
 use std::os::raw::c_char;
 use std::ptr;
 use std::slice;
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
 
 /// Holds a pointer and a length for ODPI-C strings.
 #[derive(Clone, Copy, Debug)]
@@ -35,6 +38,21 @@ impl ODPIStr {
     pub fn len(&self) -> u32 {
         self.len
     }
+
+    /// Copy the raw bytes of the string. A null pointer yields an empty vector.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if self.ptr.is_null() || self.len == 0 {
+            Vec::new()
+        } else {
+            unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len as usize) }.to_vec()
+        }
+    }
+
+    /// Convert the string to a `String`, replacing any invalid UTF-8 sequences with
+    /// `U+FFFD REPLACEMENT CHARACTER`. A null pointer yields an empty string.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.to_bytes()).into_owned()
+    }
 }
 
 impl Default for ODPIStr {
@@ -79,11 +97,492 @@ impl From<String> for ODPIStr {
 
 impl From<ODPIStr> for String {
     fn from(s: ODPIStr) -> String {
-        if s.ptr.is_null() {
-            "".to_string()
+        s.to_string_lossy()
+    }
+}
+
+/// The address of the boxed logger set by `set_drop_logger()`, or 0 if none is set.
+static DROP_LOGGER: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// Sets the logger that failures to release a LOB, statement, variable, message properties or
+/// enqueue or dequeue options when it is dropped are written to as warnings. Unlike `Pool` and
+/// `Connection`, those handles are created in large numbers by other handles, so they share this
+/// one logger rather than carrying their own. None, the default, disables the warnings. A logger
+/// that is replaced is leaked, as a handle on another thread may be writing to it.
+///
+/// * `stderr` - the logger to write warnings to.
+pub fn set_drop_logger(stderr: Option<Logger>) {
+    let addr = stderr.map_or(0, |logger| Box::into_raw(Box::new(logger)) as usize);
+    DROP_LOGGER.store(addr, Ordering::SeqCst);
+}
+
+/// Writes a warning about a failure in a `Drop` impl to the logger set by `set_drop_logger()`.
+pub fn warn_on_drop(msg: &str) {
+    let addr = DROP_LOGGER.load(Ordering::SeqCst);
+    if addr != 0 {
+        let logger = unsafe { &*(addr as *const Logger) };
+        warn!(logger, "{}", msg);
+    }
+}
+
+/// Returns true if the given name is a simple Oracle identifier of at most 30 characters: a letter
+/// followed by letters, digits, '_', '$' or '#'.
+fn is_simple_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some('a'...'z') | Some('A'...'Z') => {}
+        _ => return false,
+    }
+    name.len() <= 30 &&
+    chars.all(|c| match c {
+                  'a'...'z' | 'A'...'Z' | '0'...'9' | '_' | '$' | '#' => true,
+                  _ => false,
+              })
+}
+
+/// The reserved words of Oracle SQL, which cannot be used as unquoted identifiers.
+const RESERVED_WORDS: &'static [&'static str] =
+    &["ACCESS", "ADD", "ALL", "ALTER", "AND", "ANY", "AS", "ASC", "AUDIT", "BETWEEN", "BY",
+      "CHAR", "CHECK", "CLUSTER", "COLUMN", "COMMENT", "COMPRESS", "CONNECT", "CREATE", "CURRENT",
+      "DATE", "DECIMAL", "DEFAULT", "DELETE", "DESC", "DISTINCT", "DROP", "ELSE", "EXCLUSIVE",
+      "EXISTS", "FILE", "FLOAT", "FOR", "FROM", "GRANT", "GROUP", "HAVING", "IDENTIFIED",
+      "IMMEDIATE", "IN", "INCREMENT", "INDEX", "INITIAL", "INSERT", "INTEGER", "INTERSECT",
+      "INTO", "IS", "LEVEL", "LIKE", "LOCK", "LONG", "MAXEXTENTS", "MINUS", "MLSLABEL", "MODE",
+      "MODIFY", "NOAUDIT", "NOCOMPRESS", "NOT", "NOWAIT", "NULL", "NUMBER", "OF", "OFFLINE", "ON",
+      "ONLINE", "OPTION", "OR", "ORDER", "PCTFREE", "PRIOR", "PUBLIC", "RAW", "RENAME",
+      "RESOURCE", "REVOKE", "ROW", "ROWID", "ROWNUM", "ROWS", "SELECT", "SESSION", "SET",
+      "SHARE", "SIZE", "SMALLINT", "START", "SUCCESSFUL", "SYNONYM", "SYSDATE", "TABLE", "THEN",
+      "TO", "TRIGGER", "UID", "UNION", "UNIQUE", "UPDATE", "USER", "VALIDATE", "VALUES",
+      "VARCHAR", "VARCHAR2", "VIEW", "WHENEVER", "WHERE", "WITH"];
+
+/// Make an identifier safe to place in the text of a statement, such as dynamic DDL. Simple
+/// identifiers are returned unchanged, so that Oracle folds them to upper case as usual. Simple
+/// identifiers that are reserved words, such as `date`, are folded to upper case here and enclosed
+/// in double quotes, so they still name the same object. Any other name is enclosed in double
+/// quotes, with embedded double quotes doubled, which makes it case sensitive. Empty names and
+/// names containing control characters are rejected with `ErrorKind::InvalidIdentifier`.
+///
+/// * `name` - the identifier to quote.
+pub fn quote_ident(name: &str) -> Result<String> {
+    if name.is_empty() || name.chars().any(|c| c.is_control()) {
+        return Err(ErrorKind::InvalidIdentifier(name.to_string()).into());
+    }
+
+    if is_simple_ident(name) {
+        let upper = name.to_uppercase();
+        if RESERVED_WORDS.contains(&&upper[..]) {
+            Ok(format!("\"{}\"", upper))
         } else {
-            let vec = unsafe { slice::from_raw_parts(s.ptr as *mut u8, s.len as usize) };
-            String::from_utf8_lossy(vec).into_owned()
+            Ok(name.to_string())
         }
+    } else {
+        Ok(format!("\"{}\"", name.replace('"', "\"\"")))
+    }
+}
+
+/// Returns true if the statement, with comments removed, is a PL/SQL block or the definition of a
+/// stored unit, whose body contains semicolons and which therefore ends with a line holding only
+/// a '/'.
+fn is_plsql(code: &str) -> bool {
+    match code.split_whitespace().next().map(|w| w.to_uppercase()) {
+        Some(ref word) if word == "BEGIN" || word == "DECLARE" => true,
+        _ => stored_unit(code).is_some(),
+    }
+}
+
+/// Returns the type and name of the stored unit defined by a `CREATE` statement, such as
+/// `("PACKAGE BODY", "MIMIR_PKG")`, in the form they take in the `USER_ERRORS` view, or `None` if
+/// the statement does not define a stored unit. Unquoted names are folded to upper case and any
+/// schema is dropped.
+#[doc(hidden)]
+pub fn stored_unit(code: &str) -> Option<(String, String)> {
+    let mut words = code.split_whitespace().take(8);
+    if words.next().map(|w| w.to_uppercase()) != Some("CREATE".to_string()) {
+        return None;
+    }
+
+    let mut word = words.next().map(|w| w.to_uppercase());
+    if word == Some("OR".to_string()) {
+        words.next();
+        word = words.next().map(|w| w.to_uppercase());
+    }
+    if word == Some("EDITIONABLE".to_string()) || word == Some("NONEDITIONABLE".to_string()) {
+        word = words.next().map(|w| w.to_uppercase());
+    }
+    let mut unit_type = match word {
+        Some(ref w) if w == "FUNCTION" || w == "LIBRARY" || w == "PACKAGE" || w == "PROCEDURE" ||
+                       w == "TRIGGER" || w == "TYPE" => w.clone(),
+        _ => return None,
+    };
+
+    let mut name = words.next();
+    if name.map(|w| w.to_uppercase()) == Some("BODY".to_string()) {
+        unit_type.push_str(" BODY");
+        name = words.next();
+    }
+    let name = name.unwrap_or("").split('(').next().unwrap_or("");
+    let name = name.rsplit('.').next().unwrap_or("");
+    let name = if name.len() > 1 && name.starts_with('"') && name.ends_with('"') {
+        name[1..name.len() - 1].to_string()
+    } else {
+        name.to_uppercase()
+    };
+    Some((unit_type, name))
+}
+
+/// Returns the index just past the string literal or quoted identifier that starts with the quote
+/// at `chars[start]`, or the end of `chars` if it is not closed. Doubled quotes close and reopen
+/// the literal, so need no special handling.
+fn quoted_end(chars: &[char], start: usize) -> usize {
+    let quote = chars[start];
+    chars[start + 1..]
+        .iter()
+        .position(|&q| q == quote)
+        .map(|pos| start + 1 + pos + 1)
+        .unwrap_or_else(|| chars.len())
+}
+
+/// Returns true if the character may be part of an unquoted identifier or placeholder name.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$' || c == '#'
+}
+
+/// Returns the index just past the alternative quoting literal, such as `q'[it's]'`, starting at
+/// `chars[start]`, or `None` if no such literal starts there. The literal may be prefixed with `N`
+/// for national character data. It ends with the closing delimiter, the matching bracket for
+/// `[`, `{`, `(` and `<` or else the opening delimiter itself, followed by a quote.
+fn q_quoted_end(chars: &[char], start: usize) -> Option<usize> {
+    if start > 0 && is_name_char(chars[start - 1]) {
+        return None;
+    }
+    let mut i = start;
+    if chars[i] == 'n' || chars[i] == 'N' {
+        i += 1;
+    }
+    match (chars.get(i), chars.get(i + 1)) {
+        (Some(&'q'), Some(&'\'')) |
+        (Some(&'Q'), Some(&'\'')) => {}
+        _ => return None,
+    }
+    let open = match chars.get(i + 2) {
+        Some(&open) => open,
+        None => return Some(chars.len()),
+    };
+    let close = match open {
+        '[' => ']',
+        '{' => '}',
+        '(' => ')',
+        '<' => '>',
+        other => other,
+    };
+    Some((i + 3..chars.len())
+             .find(|&e| chars[e] == close && chars.get(e + 1) == Some(&'\''))
+             .map(|e| e + 2)
+             .unwrap_or_else(|| chars.len()))
+}
+
+/// Returns the index of the end of the line comment starting at `chars[start]`.
+fn line_comment_end(chars: &[char], start: usize) -> usize {
+    chars[start..]
+        .iter()
+        .position(|&e| e == '\n')
+        .map(|pos| start + pos)
+        .unwrap_or_else(|| chars.len())
+}
+
+/// Returns the index just past the block comment starting at `chars[start]`.
+fn block_comment_end(chars: &[char], start: usize) -> usize {
+    (start + 2..chars.len())
+        .find(|&e| chars[e - 1] == '*' && chars[e] == '/' && e > start + 2)
+        .map(|e| e + 1)
+        .unwrap_or_else(|| chars.len())
+}
+
+/// Returns the names of the placeholders of a statement, one for each occurrence, in the order
+/// they appear. As with `dpiStmt_getBindNames()`, names are given without the leading colon and
+/// are folded to upper case unless they are quoted, and positional placeholders such as `:1` are
+/// named by their digits. Colons in string literals, including alternative quoting literals such
+/// as `q'[...]'`, quoted identifiers and comments are ignored.
+pub fn placeholders(sql: &str) -> Vec<String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut names = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        if let Some(end) = q_quoted_end(&chars, i) {
+            i = end;
+            continue;
+        }
+        let next = chars.get(i + 1).cloned();
+        match chars[i] {
+            '\'' | '"' => {
+                i = quoted_end(&chars, i);
+                continue;
+            }
+            '-' if next == Some('-') => {
+                i = line_comment_end(&chars, i);
+                continue;
+            }
+            '/' if next == Some('*') => {
+                i = block_comment_end(&chars, i);
+                continue;
+            }
+            ':' if next == Some('"') => {
+                let end = quoted_end(&chars, i + 1);
+                let name_end = if chars[end - 1] == '"' && end > i + 2 {
+                    end - 1
+                } else {
+                    end
+                };
+                names.push(chars[i + 2..name_end].iter().cloned().collect());
+                i = end;
+                continue;
+            }
+            ':' => {
+                let end = (i + 1..chars.len())
+                    .find(|&e| !is_name_char(chars[e]))
+                    .unwrap_or_else(|| chars.len());
+                if end > i + 1 {
+                    let name: String = chars[i + 1..end].iter().cloned().collect();
+                    names.push(name.to_uppercase());
+                    i = end;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    names
+}
+
+/// Returns true if `chars[start..]` holds nothing but whitespace up to the end of the line.
+fn rest_of_line_blank(chars: &[char], start: usize) -> bool {
+    chars[start..].iter().take_while(|&&c| c != '\n').all(|c| c.is_whitespace())
+}
+
+/// Split a script into the statements it contains, as SQL*Plus would. SQL statements end with a
+/// ';' or a line holding only a '/', and the terminator is not part of the statement. PL/SQL
+/// blocks and stored unit definitions (`BEGIN`, `DECLARE`, `CREATE PROCEDURE`, `CREATE PACKAGE`
+/// and so on) may contain semicolons, so they only end with a '/' line, and keep their final ';'.
+/// Semicolons inside string literals, including alternative quoting literals such as `q'[ ; ]'`,
+/// quoted identifiers and comments are ignored. Statements made up only of whitespace and comments
+/// are dropped.
+pub fn split_script(script: &str) -> Vec<String> {
+    let chars: Vec<char> = script.chars().collect();
+    let mut stmts = Vec::new();
+    // The text of the current statement, and the same text without comments.
+    let mut text = String::new();
+    let mut code = String::new();
+    let mut line_blank = true;
+    let mut i = 0;
+
+    {
+        let mut finish = |text: &mut String, code: &mut String| {
+            if !code.trim().is_empty() {
+                stmts.push(text.trim().to_string());
+            }
+            text.clear();
+            code.clear();
+        };
+
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).cloned();
+            let q_end = q_quoted_end(&chars, i);
+
+            match c {
+                _ if q_end.is_some() || c == '\'' || c == '"' => {
+                    let end = q_end.unwrap_or_else(|| quoted_end(&chars, i));
+                    let literal: String = chars[i..end].iter().cloned().collect();
+                    text.push_str(&literal);
+                    code.push_str(&literal);
+                    line_blank = false;
+                    i = end;
+                    continue;
+                }
+                '-' if next == Some('-') => {
+                    let end = line_comment_end(&chars, i);
+                    text.extend(chars[i..end].iter());
+                    i = end;
+                    continue;
+                }
+                '/' if next == Some('*') => {
+                    let end = block_comment_end(&chars, i);
+                    text.extend(chars[i..end].iter());
+                    code.push(' ');
+                    i = end;
+                    continue;
+                }
+                '/' if line_blank && rest_of_line_blank(&chars, i + 1) => {
+                    finish(&mut text, &mut code);
+                    line_blank = false;
+                }
+                ';' if !is_plsql(&code) => finish(&mut text, &mut code),
+                '\n' => {
+                    text.push(c);
+                    code.push(c);
+                    line_blank = true;
+                }
+                _ => {
+                    text.push(c);
+                    code.push(c);
+                    if !c.is_whitespace() {
+                        line_blank = false;
+                    }
+                }
+            }
+            i += 1;
+        }
+        finish(&mut text, &mut code);
+    }
+
+    stmts
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_simple_ident, placeholders, quote_ident, split_script, stored_unit, ODPIStr};
+    use error::{Error, ErrorKind};
+    use std::os::raw::c_char;
+    use std::ptr;
+
+    #[test]
+    fn round_trip() {
+        let s = "jozias ✓";
+        let odpi_s = ODPIStr::from(s);
+        assert_eq!(odpi_s.len() as usize, s.len());
+        assert_eq!(odpi_s.to_bytes(), s.as_bytes());
+        assert_eq!(odpi_s.to_string_lossy(), s);
+        let res: String = odpi_s.into();
+        assert_eq!(res, s);
+    }
+
+    #[test]
+    fn simple_ident() {
+        assert!(is_simple_ident("after_a"));
+        assert!(is_simple_ident("SP$1#"));
+        assert!(!is_simple_ident(""));
+        assert!(!is_simple_ident("1sp"));
+        assert!(!is_simple_ident("sp; drop table username"));
+        assert!(!is_simple_ident("\"sp\""));
+        assert!(!is_simple_ident(&"s".repeat(31)));
+    }
+
+    #[test]
+    fn quote_ident_simple() {
+        assert_eq!(quote_ident("username").expect("badness"), "username");
+        assert_eq!(quote_ident("SP$1#").expect("badness"), "SP$1#");
+    }
+
+    #[test]
+    fn quote_ident_reserved() {
+        assert_eq!(quote_ident("date").expect("badness"), "\"DATE\"");
+        assert_eq!(quote_ident("Number").expect("badness"), "\"NUMBER\"");
+        assert_eq!(quote_ident("dates").expect("badness"), "dates");
+    }
+
+    #[test]
+    fn quote_ident_quoted() {
+        assert_eq!(quote_ident("my table").expect("badness"), "\"my table\"");
+        assert_eq!(quote_ident("1sp").expect("badness"), "\"1sp\"");
+        assert_eq!(quote_ident("a\"; drop table username; --").expect("badness"),
+                   "\"a\"\"; drop table username; --\"");
+    }
+
+    #[test]
+    fn quote_ident_rejected() {
+        for name in &["", "bad\nname", "tab\tname", "nul\0name"] {
+            match quote_ident(name) {
+                Err(Error(ErrorKind::InvalidIdentifier(_), _)) => assert!(true),
+                _ => assert!(false),
+            }
+        }
+    }
+
+    #[test]
+    fn split_script_sql() {
+        let script = "create table t (id number, name varchar2(30));\n\
+                      insert into t values (1, 'a;b');\n\
+                      -- a comment; with a semicolon\n\
+                      insert into t values (2, 'it''s;');\n\
+                      /* another; */ select \"odd;name\" from t\n\
+                      /\n\
+                      ;\n";
+        assert_eq!(split_script(script),
+                   vec!["create table t (id number, name varchar2(30))",
+                        "insert into t values (1, 'a;b')",
+                        "-- a comment; with a semicolon\ninsert into t values (2, 'it''s;')",
+                        "/* another; */ select \"odd;name\" from t"]);
+    }
+
+    #[test]
+    fn split_script_q_quoted() {
+        let script = "insert into t values (1, q'[ ; ]');\n\
+                      insert into t values (2, Q'{it's; here}');\n\
+                      insert into t values (3, nq'!;'!');\n\
+                      select seq from t;";
+        assert_eq!(split_script(script),
+                   vec!["insert into t values (1, q'[ ; ]')",
+                        "insert into t values (2, Q'{it's; here}')",
+                        "insert into t values (3, nq'!;'!')",
+                        "select seq from t"]);
+    }
+
+    #[test]
+    fn split_script_plsql() {
+        let script = "begin\n  null;\nend;\n/\n\
+                      create or replace procedure p as\nbegin\n  null;\nend;\n/\n\
+                      select 10 / 2 from dual;\n\
+                      declare\n  x number := 1;\nbegin\n  null;\nend;";
+        assert_eq!(split_script(script),
+                   vec!["begin\n  null;\nend;",
+                        "create or replace procedure p as\nbegin\n  null;\nend;",
+                        "select 10 / 2 from dual",
+                        "declare\n  x number := 1;\nbegin\n  null;\nend;"]);
+    }
+
+    #[test]
+    fn placeholder_names() {
+        assert_eq!(placeholders("select :a, :b, :a, :3 from dual"),
+                   vec!["A", "B", "A", "3"]);
+        let sql = "select ':x', \":y\" -- :z\n from t /* :w */ where a = :\"Mixed\"";
+        assert_eq!(placeholders(sql), vec!["Mixed"]);
+        assert_eq!(placeholders("begin :out := to_char(sysdate, 'HH24:MI'); end;"),
+                   vec!["OUT"]);
+        assert_eq!(placeholders("select q'{it's :x}', nq'!:y!', :z from dual"),
+                   vec!["Z"]);
+    }
+
+    #[test]
+    fn stored_units() {
+        assert_eq!(stored_unit("create or replace procedure p(x number) as begin null; end;"),
+                   Some(("PROCEDURE".to_string(), "P".to_string())));
+        assert_eq!(stored_unit("CREATE EDITIONABLE PACKAGE BODY mimir.Pkg AS END;"),
+                   Some(("PACKAGE BODY".to_string(), "PKG".to_string())));
+        assert_eq!(stored_unit("create type \"Mixed\" as object (x number)"),
+                   Some(("TYPE".to_string(), "Mixed".to_string())));
+        assert_eq!(stored_unit("create table t (id number)"), None);
+        assert_eq!(stored_unit("begin null; end;"), None);
+    }
+
+    #[test]
+    fn null() {
+        let odpi_s = ODPIStr::new(ptr::null(), 10);
+        assert!(odpi_s.to_bytes().is_empty());
+        assert_eq!(odpi_s.to_string_lossy(), "");
+        let res: String = ODPIStr::from(None).into();
+        assert_eq!(res, "");
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let bytes: Vec<u8> = vec![0x6a, 0xff, 0xfe, 0x6f, 0xc3, 0x28, 0xe2, 0x82];
+        let odpi_s = ODPIStr::new(bytes.as_ptr() as *const c_char, bytes.len() as u32);
+        assert_eq!(odpi_s.to_bytes(), bytes);
+        assert_eq!(odpi_s.to_string_lossy(), "j\u{fffd}\u{fffd}o\u{fffd}(\u{fffd}");
+        let res: String = odpi_s.into();
+        assert_eq!(res, odpi_s.to_string_lossy());
     }
 }
@@ -13,25 +13,55 @@
 //! are bound to statements by calling the function `Statement::bindByName()` or the function
 //! `Statement::bindByPos()`. They can also be used for fetching data from the database by calling
 //! the function `Statement::define()`.
+use data::{Data, OwnedData, ToData};
 use error::{ErrorKind, Result};
 use lob::Lob;
 use object::Object;
-use odpi::externs;
+use odpi::{constants, externs};
+use odpi::flags::ODPINativeTypeNum;
 use odpi::opaque::ODPIVar;
 use odpi::structs::ODPIData;
 use rowid::Rowid;
 use statement::Statement;
+use std::cell::Cell;
 use std::{ptr, slice};
-use util::ODPIStr;
+use util::{warn_on_drop, ODPIStr};
 
 /// This structure represents memory areas used for transferring data to and from the database and
-/// is available by handle to a calling application or driver.
+/// is available by handle to a calling application or driver. A `Var` owns the reference to the
+/// ODPI-C variable it was created with, and any added with `add_ref()`, and releases whatever
+/// references it still holds when it is dropped.
 pub struct Var {
     /// The ODPI-C var
     inner: *mut ODPIVar,
+    /// The native type the variable was created with, if known.
+    native_type: ODPINativeTypeNum,
+    /// The number of references to `inner` held by this handle: the one it was created with plus
+    /// those added by `add_ref()`, less those given up by `release()`.
+    refs: Cell<u32>,
 }
 
 impl Var {
+    /// Create a new variable from an `ODPIVar` pointer and the native type it was created with,
+    /// taking ownership of one reference to it. The reference is released when the variable is
+    /// dropped.
+    #[doc(hidden)]
+    pub fn new(inner: *mut ODPIVar, native_type: ODPINativeTypeNum) -> Var {
+        Var {
+            inner: inner,
+            native_type: native_type,
+            refs: Cell::new(1),
+        }
+    }
+
+    /// Create another handle to the same ODPI-C variable, owning a reference of its own.
+    #[doc(hidden)]
+    pub fn new_ref(&self) -> Result<Var> {
+        try_dpi!(externs::dpiVar_addRef(self.inner),
+                 Ok(Var::new(self.inner, self.native_type)),
+                 ErrorKind::Var("dpiVar_addRef".to_string()))
+    }
+
     /// Get the `inner` value.
     #[doc(hidden)]
     pub fn inner(&self) -> *mut ODPIVar {
@@ -43,10 +73,33 @@ impl Var {
     /// was created.
     pub fn add_ref(&self) -> Result<()> {
         try_dpi!(externs::dpiVar_addRef(self.inner),
-                 Ok(()),
+                 {
+                     self.refs.set(self.refs.get() + 1);
+                     Ok(())
+                 },
                  ErrorKind::Var("dpiVar_addRef".to_string()))
     }
 
+    /// Copies every element of this variable into the same position of another variable, using
+    /// `copy_data()`. This allows the values fetched or returned into one variable to be bound to
+    /// a subsequent statement. An error is returned if the destination has fewer elements allocated
+    /// than this variable.
+    ///
+    /// * `dst` - the variable into which data is to be copied.
+    pub fn clone_into(&self, dst: &mut Var) -> Result<()> {
+        let num_elements = self.get_num_elements_in_array()?;
+        let dst_elements = dst.get_data()?.len() as u32;
+
+        if dst_elements < num_elements {
+            return Err(ErrorKind::VarTooSmall(num_elements, dst_elements).into());
+        }
+
+        for pos in 0..num_elements {
+            self.copy_data(pos, dst, pos)?;
+        }
+        Ok(())
+    }
+
     /// Copies the data from one variable to another variable.
     ///
     /// * `src_pos` - the array position from which the data is to be copied. The first position is
@@ -62,6 +115,26 @@ impl Var {
                  ErrorKind::Var("dpiVar_copyData".to_string()))
     }
 
+    /// Sets the leading elements of the variable from the given values, using `set_from_data()`.
+    /// Elements given as `None` are set to NULL, which allows a nullable column to be bound for
+    /// array DML. The number of elements set is returned, and an error is returned if the variable
+    /// has fewer elements allocated than there are values.
+    ///
+    /// * `values` - the values which should be set, starting at array position 0.
+    pub fn fill_opt<T: ToData>(&self, values: &[Option<T>]) -> Result<u32> {
+        let num_values = values.len() as u32;
+        let num_elements = self.get_data()?.len() as u32;
+
+        if num_elements < num_values {
+            return Err(ErrorKind::VarTooSmall(num_values, num_elements).into());
+        }
+
+        for (pos, value) in values.iter().enumerate() {
+            self.set_from_data(pos as u32, &value.to_data())?;
+        }
+        Ok(num_values)
+    }
+
     /// Returns a pointer to an array of `ODPIData` structures used for transferring data to and
     /// from the database. These structures are allocated by the variable itself and are made
     /// available when the variable is first created using the function `Connection::new_var()`. If
@@ -84,6 +157,41 @@ impl Var {
                  ErrorKind::Var("dpiVar_getData".to_string()))
     }
 
+    /// Returns the LOB at the given array position, for a variable of native type
+    /// DPI_NATIVE_TYPE_LOB such as one defined for an array fetch of a CLOB or BLOB column. A
+    /// reference is added to the LOB, so it remains valid after the variable is reused by the next
+    /// fetch, and should be released with `Lob::release()` when it is no longer needed.
+    ///
+    /// * `pos` - the array position in the variable which is to be read. The first position is 0.
+    pub fn get_lob(&self, pos: u32) -> Result<Lob> {
+        if self.native_type != ODPINativeTypeNum::Lob {
+            return Err(ErrorKind::TypeMismatch("Lob".to_string(),
+                                               format!("{:?}", self.native_type))
+                               .into());
+        }
+
+        let data = self.get_data()?;
+        let num_elements = data.len() as u32;
+        let d = match data.get(pos as usize) {
+            Some(d) => d,
+            None => return Err(ErrorKind::ArrayPosition(pos, num_elements).into()),
+        };
+        if d.is_null == 1 {
+            return Err(ErrorKind::NullValue.into());
+        }
+
+        let lob_ptr = unsafe { d.value.as_lob };
+        try_dpi!(externs::dpiLob_addRef(lob_ptr),
+                 Ok(lob_ptr.into()),
+                 ErrorKind::Lob("dpiLob_addRef".to_string()))
+    }
+
+    /// Returns the native type the variable was created with, or `ODPINativeTypeNum::Invalid` if
+    /// it is not known.
+    pub fn native_type(&self) -> ODPINativeTypeNum {
+        self.native_type
+    }
+
     /// Returns the number of elements in a PL/SQL index-by table if the variable was created as an
     /// array by the function `Connection::newVar()`. If the variable is one of the output bind
     /// variables of a DML returning statement, however, the value returned will correspond to the
@@ -96,6 +204,32 @@ impl Var {
                  ErrorKind::Var("dpiVar_getNumElementsInArray".to_string()))
     }
 
+    /// Returns the rowids held by a variable of native type DPI_NATIVE_TYPE_ROWID, such as the
+    /// output variable of `update ... returning rowid into :r`. Only the elements reported by
+    /// `get_num_elements_in_array()` are returned, which for a DML returning statement is one per
+    /// affected row. A reference is added to each rowid, which should be released with
+    /// `Rowid::release()` when it is no longer needed.
+    pub fn get_rowids(&self) -> Result<Vec<Rowid>> {
+        if self.native_type != ODPINativeTypeNum::Rowid {
+            return Err(ErrorKind::TypeMismatch("Rowid".to_string(),
+                                               format!("{:?}", self.native_type))
+                               .into());
+        }
+
+        let num_elements = self.get_num_elements_in_array()?;
+        let data = self.get_data()?;
+        let mut rowids = Vec::with_capacity(num_elements as usize);
+        for d in data.iter().take(num_elements as usize) {
+            if d.is_null == 1 {
+                return Err(ErrorKind::NullValue.into());
+            }
+            let rowid: Rowid = unsafe { d.value.as_rowid }.into();
+            rowid.add_ref()?;
+            rowids.push(rowid);
+        }
+        Ok(rowids)
+    }
+
     /// Returns the size of the buffer used for one element of the array used for fetching/binding
     /// Oracle data.
     pub fn get_size_in_bytes(&self) -> Result<u32> {
@@ -105,10 +239,34 @@ impl Var {
                  ErrorKind::Var("dpiVar_getSizeInBytes".to_string()))
     }
 
+    /// Returns an owned copy of the value at the given array position, interpreted as the native
+    /// type the variable was created with. Only the elements reported by
+    /// `get_num_elements_in_array()` can be read, so for an output variable of a DML returning
+    /// statement, reading past the rows returned by the last execution gives an
+    /// `ErrorKind::ArrayPosition` error rather than a stale value from an earlier execution.
+    ///
+    /// * `pos` - the array position in the variable which is to be read. The first position is 0.
+    pub fn get_value(&self, pos: u32) -> Result<OwnedData> {
+        let data = self.get_data()?;
+        let num_elements = self.get_num_elements_in_array()?.min(data.len() as u32);
+        if pos >= num_elements {
+            return Err(ErrorKind::ArrayPosition(pos, num_elements).into());
+        }
+
+        match data.get_mut(pos as usize) {
+            Some(d) => {
+                let data: Data = (d as *mut ODPIData).into();
+                OwnedData::from_data(self.native_type, &data)
+            }
+            None => Err(ErrorKind::ArrayPosition(pos, num_elements).into()),
+        }
+    }
+
     /// Releases a reference to the variable. A count of the references to the variable is
     /// maintained and when this count reaches zero, the memory associated with the variable is
     /// freed.
     pub fn release(&self) -> Result<()> {
+        self.refs.set(self.refs.get().saturating_sub(1));
         try_dpi!(externs::dpiVar_release(self.inner),
                  Ok(()),
                  ErrorKind::Var("dpiVar_release".to_string()))
@@ -129,12 +287,54 @@ impl Var {
                  ErrorKind::Var("dpiVar_setFromBytes".to_string()))
     }
 
+    /// Sets the variable value at the given position to the given owned value. Strings and byte
+    /// strings are copied into the variable's buffers, exactly as with `set_from_bytes()`. Other
+    /// values must match the native type the variable was created with.
+    ///
+    /// * `pos` - the array position in the variable which is to be set. The first position is 0. If
+    /// the position exceeds the number of elements allocated by the variable an error is returned.
+    /// * `value` - the value which should be set.
+    pub fn set_from_data(&self, pos: u32, value: &OwnedData) -> Result<()> {
+        let data = self.get_data()?;
+        let num_elements = data.len() as u32;
+        if pos >= num_elements {
+            return Err(ErrorKind::ArrayPosition(pos, num_elements).into());
+        }
+
+        let bytes = match *value {
+            OwnedData::Null => {
+                data[pos as usize].is_null = 1;
+                return Ok(());
+            }
+            OwnedData::String(ref s) => s.as_bytes(),
+            OwnedData::Bytes(ref b) => &b[..],
+            _ => {
+                let (native_type, odpi_data) = value.to_odpi();
+                if native_type != self.native_type {
+                    return Err(ErrorKind::TypeMismatch(format!("{:?}", self.native_type),
+                                                       value.type_name().to_string())
+                                       .into());
+                }
+                data[pos as usize] = odpi_data;
+                return Ok(());
+            }
+        };
+
+        try_dpi!(externs::dpiVar_setFromBytes(self.inner,
+                                              pos,
+                                              bytes.as_ptr() as *const ::std::os::raw::c_char,
+                                              bytes.len() as u32),
+                 Ok(()),
+                 ErrorKind::Var("dpiVar_setFromBytes".to_string()))
+    }
+
     /// Sets the variable value to the specified LOB.
     ///
     /// * `pos` - the array position in the variable which is to be set. The first position is 0. If
     /// the position exceeds the number of elements allocated by the variable an error is returned.
-    /// * `lob` - the LOB which should be set.
-    pub fn set_from_lob(&self, pos: u32, lob: Lob) -> Result<()> {
+    /// * `lob` - the LOB which should be set. The variable holds a reference of its own, so the
+    /// LOB remains owned by the caller and may be released once it has been set.
+    pub fn set_from_lob(&self, pos: u32, lob: &Lob) -> Result<()> {
         try_dpi!(externs::dpiVar_setFromLob(self.inner, pos, lob.inner()),
                  Ok(()),
                  ErrorKind::Var("dpiVar_setFromLob".to_string()))
@@ -144,8 +344,9 @@ impl Var {
     ///
     /// * `pos` - the array position in the variable which is to be set. The first position is 0. If
     /// the position exceeds the number of elements allocated by the variable an error is returned.
-    /// * `obj` - the object which should be set.
-    pub fn set_from_object(&self, pos: u32, obj: Object) -> Result<()> {
+    /// * `obj` - the object which should be set. The variable holds a reference of its own, so the
+    /// object remains owned by the caller and may be released once it has been set.
+    pub fn set_from_object(&self, pos: u32, obj: &Object) -> Result<()> {
         try_dpi!(externs::dpiVar_setFromObject(self.inner, pos, obj.inner()),
                  Ok(()),
                  ErrorKind::Var("dpiVar_setFromObject".to_string()))
@@ -155,8 +356,9 @@ impl Var {
     ///
     /// * `pos` - the array position in the variable which is to be set. The first position is 0. If
     /// the position exceeds the number of elements allocated by the variable an error is returned.
-    /// * `rowid` - the rowid which should be set.
-    pub fn set_from_rowid(&self, pos: u32, rowid: Rowid) -> Result<()> {
+    /// * `rowid` - the rowid which should be set. The variable holds a reference of its own, so the
+    /// rowid remains owned by the caller and may be released once it has been set.
+    pub fn set_from_rowid(&self, pos: u32, rowid: &Rowid) -> Result<()> {
         try_dpi!(externs::dpiVar_setFromRowid(self.inner, pos, rowid.inner()),
                  Ok(()),
                  ErrorKind::Var("dpiVar_setFromRowid".to_string()))
@@ -166,8 +368,9 @@ impl Var {
     ///
     /// * `pos` - the array position in the variable which is to be set. The first position is 0. If
     /// the position exceeds the number of elements allocated by the variable an error is returned.
-    /// * `stmt` - the statement which should be set.
-    pub fn set_from_stmt(&self, pos: u32, stmt: Statement) -> Result<()> {
+    /// * `stmt` - the statement which should be set. The variable holds a reference of its own, so
+    /// the statement remains owned by the caller and may be released once it has been set.
+    pub fn set_from_stmt(&self, pos: u32, stmt: &Statement) -> Result<()> {
         try_dpi!(externs::dpiVar_setFromStmt(self.inner, pos, stmt.inner()),
                  Ok(()),
                  ErrorKind::Var("dpiVar_setFromStmt".to_string()))
@@ -184,20 +387,42 @@ impl Var {
     }
 }
 
+impl Drop for Var {
+    fn drop(&mut self) {
+        if self.inner.is_null() {
+            return;
+        }
+
+        for _ in 0..self.refs.get() {
+            if unsafe { externs::dpiVar_release(self.inner) } != constants::DPI_SUCCESS {
+                warn_on_drop("Failed to release the variable on drop");
+                break;
+            }
+        }
+        self.refs.set(0);
+    }
+}
+
+/// Wrap an ODPI-C variable of unknown native type, taking ownership of one reference to it.
 impl From<*mut ODPIVar> for Var {
     fn from(inner: *mut ODPIVar) -> Var {
-        Var { inner: inner }
+        Var::new(inner, ODPINativeTypeNum::Invalid)
     }
 }
 
 #[cfg(test)]
 mod test {
-    use data::Data;
+    use data::{Data, OwnedData};
     use connection::Connection;
     use context::Context;
-    use error::Result;
+    use error::{Error, ErrorKind, Result};
+    use odpi::flags::EXEC_DEFAULT;
     use odpi::flags::ODPIConnCloseMode::*;
-    use odpi::flags::ODPINativeTypeNum::*;
+    use chrono::{Duration, TimeZone, UTC};
+    use odpi::flags::ODPINativeTypeNum::{Bytes, Int64, Lob, Rowid};
+    use odpi::flags::ODPINativeTypeNum::Double as NativeDouble;
+    use odpi::flags::ODPINativeTypeNum::IntervalDS as NativeIntervalDS;
+    use odpi::flags::ODPINativeTypeNum::Timestamp as NativeTimestamp;
     use odpi::flags::ODPIOracleTypeNum::*;
     use odpi::structs::ODPIData;
     use std::ffi::CString;
@@ -245,6 +470,397 @@ mod test {
         Ok(())
     }
 
+    fn clone_into_res() -> Result<()> {
+        let conn = ::test::connect()?;
+
+        let src = conn.new_var(Number, Int64, 3, 0, false, false)?;
+        for (idx, d) in src.get_data()?.iter_mut().enumerate() {
+            d.is_null = 0;
+            d.value.as_int_64 = (idx as i64 + 1) * 10;
+        }
+
+        let mut dst = conn.new_var(Number, Int64, 3, 0, false, false)?;
+        src.clone_into(&mut dst)?;
+
+        let src_data = src.get_data()?;
+        let dst_data = dst.get_data()?;
+        assert_eq!(src_data.len(), dst_data.len());
+        for (s, d) in src_data.iter_mut().zip(dst_data.iter_mut()) {
+            let src_d: Data = (s as *mut ODPIData).into();
+            let dst_d: Data = (d as *mut ODPIData).into();
+            assert_eq!(src_d.as_int64(), dst_d.as_int64());
+        }
+
+        let mut small = conn.new_var(Number, Int64, 2, 0, false, false)?;
+        match src.clone_into(&mut small) {
+            Err(Error(ErrorKind::VarTooSmall(3, 2), _)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        conn.close(DefaultClose, None)?;
+
+        Ok(())
+    }
+
+    fn fill_opt_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+
+        let ids = conn.new_var(Number, Int64, 3, 0, false, false)?;
+        assert_eq!(ids.fill_opt(&[Some(1i64), None, Some(3)])?, 3);
+        let usernames = conn.new_var(Varchar, Bytes, 3, 256, true, false)?;
+        usernames.fill_opt(&[Some("fill_opt_0"), Some("fill_opt_1"), Some("fill_opt_2")])?;
+
+        let stmt = conn.prepare_stmt(Some("insert into username values (:id, :username)"),
+                          None,
+                          false)?;
+        stmt.bind_by_pos(1, &ids)?;
+        stmt.bind_by_pos(2, &usernames)?;
+        stmt.execute_many(EXEC_DEFAULT, 3)?;
+
+        let nulls = conn.query_scalar::<i64>("select count(*) from username \
+                                              where username = 'fill_opt_1' and id is null")?;
+        assert_eq!(nulls, Some(1));
+        let third = conn.query_scalar::<i64>("select id from username \
+                                              where username = 'fill_opt_2'")?;
+        assert_eq!(third, Some(3));
+
+        let small = conn.new_var(Number, Int64, 2, 0, false, false)?;
+        match small.fill_opt(&[Some(1i64), None, Some(3)]) {
+            Err(Error(ErrorKind::VarTooSmall(3, 2), _)) => assert!(true),
+            _ => assert!(false),
+        }
+
+        conn.rollback()?;
+        conn.close(DefaultClose, None)?;
+
+        Ok(())
+    }
+
+    fn data_setters_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+
+        let ids = conn.new_var(Number, Int64, 3, 0, false, false)?;
+        for (idx, d) in ids.get_data()?.iter_mut().enumerate() {
+            let data: Data = (d as *mut ODPIData).into();
+            match idx {
+                2 => data.set_null(),
+                _ => data.set_int64(41 + idx as i64),
+            }
+            assert_eq!(data.is_null(), idx == 2);
+        }
+        let usernames = conn.new_var(Varchar, Bytes, 3, 256, true, false)?;
+        for pos in 0..3 {
+            usernames.set_from_bytes(pos, "data_setters")?;
+        }
+
+        let insert = conn.prepare_stmt(Some("insert into username values (:id, :username)"),
+                          None,
+                          false)?;
+        insert.bind_by_pos(1, &ids)?;
+        insert.bind_by_pos(2, &usernames)?;
+        insert.execute_many(EXEC_DEFAULT, 3)?;
+        insert.close(None)?;
+
+        let row = conn.query_row::<(i64, i64)>("select sum(id), count(*) - count(id) \
+                                                from username where username = :1",
+                                               &[&"data_setters".to_string()])?;
+        assert_eq!(row, Some((83, 1)));
+
+        let ts = conn.new_var(Timestamp, NativeTimestamp, 1, 0, false, false)?;
+        let ts_data: Data = (&mut ts.get_data()?[0] as *mut ODPIData).into();
+        ts_data.set_timestamp(UTC.ymd(2017, 6, 1).and_hms(12, 30, 15));
+        let int_ds = conn.new_var(IntervalDS, NativeIntervalDS, 1, 0, false, false)?;
+        let int_ds_data: Data = (&mut int_ds.get_data()?[0] as *mut ODPIData).into();
+        int_ds_data.set_interval_ds(Duration::hours(26));
+        let dbl = conn.new_var(Number, NativeDouble, 1, 0, false, false)?;
+        let dbl_data: Data = (&mut dbl.get_data()?[0] as *mut ODPIData).into();
+        dbl_data.set_double(1.5);
+
+        let select = conn.prepare_stmt(Some("select to_char(:1, 'YYYY-MM-DD HH24:MI:SS'), \
+                                                 extract(day from :2), :3 * 2 from dual"),
+                          None,
+                          false)?;
+        select.bind_by_pos(1, &ts)?;
+        select.bind_by_pos(2, &int_ds)?;
+        select.bind_by_pos(3, &dbl)?;
+        select.execute(EXEC_DEFAULT)?;
+        let row = select.fetch_row()?.expect("missing row");
+        assert_eq!(row.get::<String>(1)?, "2017-06-01 12:30:15");
+        assert_eq!(row.get::<i64>(2)?, 1);
+        assert_eq!(row.get::<f64>(3)?, 3.0);
+        select.close(None)?;
+
+        conn.rollback()?;
+        conn.close(DefaultClose, None)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    pub fn data_setters() {
+        use std::io::{self, Write};
+
+        match data_setters_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
+
+    fn get_lob_res() -> Result<()> {
+        let conn = ::test::connect()?;
+
+        let stmt = conn.prepare_stmt(Some("select to_clob('lob ' || level) from dual \
+                                           connect by level <= 3 order by 1"),
+                          None,
+                          false)?;
+        stmt.set_fetch_array_size(3)?;
+        stmt.execute(EXEC_DEFAULT)?;
+        let var = conn.new_var(Clob, Lob, 3, 0, false, false)?;
+        stmt.define(1, &var)?;
+
+        let (row_index, num_rows, _) = stmt.fetch_rows(3)?;
+        assert_eq!(num_rows, 3);
+        for (idx, pos) in (row_index..(row_index + num_rows)).enumerate() {
+            let lob = var.get_lob(pos)?;
+            assert_eq!(lob.read_all()?, format!("lob {}", idx + 1).into_bytes());
+            lob.release()?;
+        }
+
+        stmt.close(None)?;
+        conn.close(DefaultClose, None)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    pub fn get_lob() {
+        use std::io::{self, Write};
+
+        match get_lob_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
+
+    fn get_rowids_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        ::test::ensure_test_schema()?;
+
+        let ids = conn.new_var(Number, Int64, 3, 0, false, false)?;
+        ids.fill_opt(&[Some(1i64), Some(2), Some(3)])?;
+        let usernames = conn.new_var(Varchar, Bytes, 3, 256, true, false)?;
+        usernames.fill_opt(&[Some("get_rowids"), Some("get_rowids"), Some("get_rowids")])?;
+        let insert = conn.prepare_stmt(Some("insert into username values (:id, :username)"),
+                          None,
+                          false)?;
+        insert.bind_by_pos(1, &ids)?;
+        insert.bind_by_pos(2, &usernames)?;
+        insert.execute_many(EXEC_DEFAULT, 3)?;
+
+        let update = conn.prepare_stmt(Some("update username set id = id + 10 \
+                                             where username = 'get_rowids' \
+                                             returning rowid into :r"),
+                          None,
+                          false)?;
+        let rowid_var = conn.new_var(RowID, Rowid, 3, 0, false, false)?;
+        update.bind_by_pos(1, &rowid_var)?;
+        update.execute(EXEC_DEFAULT)?;
+
+        let rowids = rowid_var.get_rowids()?;
+        assert_eq!(rowids.len(), 3);
+        let mut values = Vec::new();
+        for rowid in &rowids {
+            values.push(rowid.get_string_value()?);
+            rowid.release()?;
+        }
+        values.sort();
+        values.dedup();
+        assert_eq!(values.len(), 3);
+
+        let mut refetched = Vec::new();
+        for value in &values {
+            let sql = "select id from username where rowid = chartorowid(:1)";
+            if let Some((id,)) = conn.query_row::<(i64,)>(sql, &[value])? {
+                refetched.push(id);
+            }
+        }
+        refetched.sort();
+        assert_eq!(refetched, vec![11, 12, 13]);
+
+        conn.rollback()?;
+        conn.close(DefaultClose, None)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    pub fn get_rowids() {
+        use std::io::{self, Write};
+
+        match get_rowids_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
+
+    fn set_from_lob_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        let ddl = conn.prepare_stmt(Some("begin \
+                                          execute immediate \
+                                            'create table mimir_lobs (id number, data clob)'; \
+                                        exception \
+                                          when others then \
+                                            if sqlcode != -955 then raise; end if; \
+                                        end;"),
+                       None,
+                       false)?;
+        ddl.execute(EXEC_DEFAULT)?;
+        ddl.close(None)?;
+
+        let lob = conn.new_temp_lob(Clob)?;
+        lob.set_from_bytes("set_from_lob".bytes().map(|b| b as i8).collect())?;
+        let var = conn.new_var(Clob, Lob, 1, 0, false, false)?;
+        var.set_from_lob(0, &lob)?;
+        // The variable holds its own reference, so the LOB can be given up before the insert.
+        lob.release()?;
+
+        let insert = conn.prepare_stmt(Some("insert into mimir_lobs values (1, :1)"), None, false)?;
+        insert.bind_by_pos(1, &var)?;
+        insert.execute(EXEC_DEFAULT)?;
+        insert.close(None)?;
+
+        let data = conn.query_scalar::<String>("select to_char(data) from mimir_lobs \
+                                                where id = 1")?;
+        assert_eq!(data, Some("set_from_lob".to_string()));
+
+        conn.rollback()?;
+        conn.close(DefaultClose, None)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    pub fn set_from_lob() {
+        use std::io::{self, Write};
+
+        match set_from_lob_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
+
+    fn set_from_object_res() -> Result<()> {
+        let conn = ::test::connect()?;
+        let ddls = ["begin \
+                       execute immediate \
+                         'create type mimir_var_point_t as object (x number, y number)'; \
+                     exception \
+                       when others then \
+                         if sqlcode != -955 then raise; end if; \
+                     end;",
+                    "begin \
+                       execute immediate \
+                         'create table mimir_var_points (id number, pt mimir_var_point_t)'; \
+                     exception \
+                       when others then \
+                         if sqlcode != -955 then raise; end if; \
+                     end;"];
+        for sql in &ddls {
+            let ddl = conn.prepare_stmt(Some(sql), None, false)?;
+            ddl.execute(EXEC_DEFAULT)?;
+            ddl.close(None)?;
+        }
+
+        let point_type = conn.get_object_type("MIMIR_VAR_POINT_T")?;
+        let attrs = point_type.get_attributes()?;
+        let point = point_type.create_object()?;
+        point.set_attribute_value(&attrs[0], &OwnedData::Int64(3))?;
+        point.set_attribute_value(&attrs[1], &OwnedData::Int64(4))?;
+        let var = conn.new_object_var(&point_type, 1, false)?;
+        var.set_from_object(0, &point)?;
+        point.release()?;
+
+        let insert = conn.prepare_stmt(Some("insert into mimir_var_points values (1, :1)"),
+                          None,
+                          false)?;
+        insert.bind_by_pos(1, &var)?;
+        insert.execute(EXEC_DEFAULT)?;
+        insert.close(None)?;
+
+        let row = conn.query_row::<(i64, i64)>("select p.pt.x, p.pt.y \
+                                                from mimir_var_points p \
+                                                where p.id = :1",
+                                               &[&1i64])?;
+        assert_eq!(row, Some((3, 4)));
+
+        conn.rollback()?;
+        for attr in &attrs {
+            attr.release()?;
+        }
+        point_type.release()?;
+        conn.close(DefaultClose, None)?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    pub fn set_from_object() {
+        use std::io::{self, Write};
+
+        match set_from_object_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
+
+    #[test]
+    pub fn clone_into() {
+        use std::io::{self, Write};
+
+        match clone_into_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
+
+    #[test]
+    pub fn fill_opt() {
+        use std::io::{self, Write};
+
+        match fill_opt_res() {
+            Ok(_) => assert!(true),
+            Err(e) => {
+                writeln!(io::stderr(), "{}", e).expect("badness");
+                assert!(false);
+            }
+        }
+    }
+
     #[test]
     pub fn var() {
         use std::io::{self, Write};